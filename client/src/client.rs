@@ -1,22 +1,229 @@
+use std::ops::Range;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::client_error::ClientError;
 use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_request::RpcError;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_multisig::{Account, TransactionStatus};
+use solana_program::program_pack::Pack;
+use solana_sdk::clock::DEFAULT_MS_PER_SLOT;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::packet::PACKET_DATA_SIZE;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, Signer};
-use solana_sdk::transaction::Transaction;
+use solana_sdk::sanitize::Sanitize;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, TransactionError};
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::utils;
 
-/// Establishes a RPC connection with the solana cluster configured by
-/// `solana config set --url <URL>`. Information about what cluster
-/// has been configured is gleened from the solana config file
-/// `~/.config/solana/cli/config.yml`.
-pub fn establish_connection() -> Result<RpcClient> {
-    let rpc_url = utils::get_rpc_url()?;
-    Ok(RpcClient::new_with_commitment(
-        rpc_url,
-        CommitmentConfig::confirmed(),
-    ))
+/// Establishes a RPC connection with `url`, or the solana cluster
+/// configured by `solana config set --url <URL>` when it is `None`.
+/// Information about what cluster has been configured is gleened from the
+/// solana config file `~/.config/solana/cli/config.yml`.
+pub fn establish_connection(url: Option<&str>, commitment: CommitmentConfig) -> Result<RpcClient> {
+    let rpc_url = match url {
+        Some(url) => url.to_string(),
+        None => utils::get_rpc_url()?,
+    };
+    Ok(RpcClient::new_with_commitment(rpc_url, commitment))
+}
+
+/// Establishes a RPC connection like `establish_connection`, but only with
+/// a node that reports itself healthy. A lagging node serves stale accounts,
+/// so `fallback_urls` are tried in order after the configured cluster.
+pub fn establish_healthy_connection(
+    url: Option<&str>,
+    commitment: CommitmentConfig,
+    fallback_urls: &[String],
+) -> Result<RpcClient> {
+    let rpc_url = match url {
+        Some(url) => url.to_string(),
+        None => utils::get_rpc_url()?,
+    };
+    let connections = std::iter::once(rpc_url)
+        .chain(fallback_urls.iter().cloned())
+        .map(|url| {
+            let connection = RpcClient::new_with_commitment(url.clone(), commitment);
+            (url, connection)
+        });
+
+    first_healthy_connection(connections)
+}
+
+/// Returns the first connection whose node passes `getHealth`, warning
+/// about every unhealthy one it skips.
+pub fn first_healthy_connection(
+    connections: impl IntoIterator<Item = (String, RpcClient)>,
+) -> Result<RpcClient> {
+    let mut tried = Vec::new();
+    for (url, connection) in connections {
+        match connection.get_health() {
+            Ok(()) => return Ok(connection),
+            Err(e) => eprintln!("Warning: skipping unhealthy RPC node {}: {}", url, e),
+        }
+        tried.push(url);
+    }
+
+    Err(Error::NoHealthyNode(tried.join(", ")))
+}
+
+/// Number of times `send_confirmed` sends a transaction whose blockhash
+/// expired before it was confirmed
+pub const SEND_ATTEMPTS: usize = 3;
+
+/// How long `send_and_confirm` waits for a confirmation
+pub const CONFIRM_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Signs `transaction` with the latest blockhash and sends it with
+/// `send_and_confirm`. If the blockhash expires before the transaction is
+/// confirmed, it is signed again with a fresh one, up to `SEND_ATTEMPTS`
+/// times in total.
+pub fn send_confirmed(
+    mut transaction: Transaction,
+    signers: &[&Keypair],
+    connection: &RpcClient,
+) -> Result<Signature> {
+    let mut attempt = 1;
+    loop {
+        transaction.sign(&signers.to_vec(), connection.get_latest_blockhash()?);
+
+        match send_and_confirm(&transaction, connection) {
+            Err(Error::ClientError(e))
+                if attempt < SEND_ATTEMPTS
+                    && e.get_transaction_error() == Some(TransactionError::BlockhashNotFound) =>
+            {
+                eprintln!(
+                    "Warning: blockhash expired, retrying ({}/{})",
+                    attempt, SEND_ATTEMPTS
+                );
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Sends `transaction` with `send_confirmed`, or with `dry_run` signs and
+/// simulates it without broadcasting. The simulation logs and compute units
+/// are printed, a failed simulation is returned as its transaction error.
+pub fn send_or_simulate(
+    mut transaction: Transaction,
+    signers: &[&Keypair],
+    connection: &RpcClient,
+    dry_run: bool,
+) -> Result<Signature> {
+    if !dry_run {
+        return send_confirmed(transaction, signers, connection);
+    }
+
+    transaction.sign(&signers.to_vec(), connection.get_latest_blockhash()?);
+    let result = connection.simulate_transaction(&transaction)?.value;
+    println!("{}", describe_simulation(&result));
+
+    match result.err {
+        Some(err) => Err(ClientError::from(err).into()),
+        None => Ok(transaction.signatures[0]),
+    }
+}
+
+/// Renders the logs and compute units of a simulated transaction.
+pub fn describe_simulation(result: &RpcSimulateTransactionResult) -> String {
+    let mut description = String::from("Simulation logs:");
+    for log in result.logs.iter().flatten() {
+        description.push_str(&format!("\n  {}", log));
+    }
+    if let Some(units_consumed) = result.units_consumed {
+        description.push_str(&format!("\nCompute units consumed: {}", units_consumed));
+    }
+    description
+}
+
+/// Sends a transaction and waits for its confirmation like
+/// `RpcClient::send_and_confirm_transaction`, reporting progress on an
+/// interactive stderr. Gives up after `CONFIRM_TIMEOUT`, naming the
+/// signature so the transaction can still be looked up.
+pub fn send_and_confirm(transaction: &Transaction, connection: &RpcClient) -> Result<Signature> {
+    let mut spinner = utils::Spinner::stderr();
+    let started = Instant::now();
+
+    spinner.set_message("Submitting...");
+    let signature = match connection.send_transaction(transaction) {
+        Ok(signature) => signature,
+        Err(e) => {
+            spinner.clear();
+            return Err(e.into());
+        }
+    };
+
+    loop {
+        spinner.set_message("Confirming...");
+
+        let status = connection
+            .get_signature_statuses(&[signature])?
+            .value
+            .remove(0);
+        match status {
+            Some(status) => {
+                if let Some(err) = status.err {
+                    spinner.clear();
+                    return Err(ClientError::from(err).into());
+                }
+                if status.satisfies_commitment(connection.commitment()) {
+                    spinner.finish(&format!("Confirmed in slot {}", status.slot));
+                    return Ok(signature);
+                }
+            }
+            None => {
+                if !connection.is_blockhash_valid(
+                    &transaction.message.recent_blockhash,
+                    CommitmentConfig::processed(),
+                )? {
+                    spinner.clear();
+                    return Err(ClientError::from(TransactionError::BlockhashNotFound).into());
+                }
+            }
+        }
+
+        if started.elapsed() > CONFIRM_TIMEOUT {
+            spinner.clear();
+            return Err(ClientError::from(RpcError::ForUser(format!(
+                "timed out confirming transaction {}",
+                signature
+            )))
+            .into());
+        }
+
+        sleep(Duration::from_millis(500));
+    }
+}
+
+/// Lamports kept on top of rent to pay the fees of creating an account
+pub const FEE_BUFFER: u64 = 10_000;
+
+/// Lamports the payer needs to create a multisig account: the rent of the
+/// associated account plus `FEE_BUFFER`.
+pub fn minimum_funding(connection: &RpcClient) -> Result<u64> {
+    Ok(connection.get_minimum_balance_for_rent_exemption(Account::LEN)? + FEE_BUFFER)
+}
+
+/// Checks up front that `payer` can fund a multisig account, so a failing
+/// creation isn't submitted.
+pub fn check_funding(payer: &Pubkey, connection: &RpcClient) -> Result<()> {
+    let needed = minimum_funding(connection)?;
+    let have = connection.get_balance(payer)?;
+    if have < needed {
+        return Err(Error::InsufficientFunds { needed, have });
+    }
+    Ok(())
 }
 
 pub fn create_account(
@@ -24,40 +231,537 @@ pub fn create_account(
     wallet: &Keypair,
     threshold: u32,
     owners: Vec<Pubkey>,
+    max_pending: u32,
     connection: &RpcClient,
-) -> Result<()> {
-    let mut transaction = Transaction::new_with_payer(
+    dry_run: bool,
+) -> Result<Signature> {
+    let transaction = Transaction::new_with_payer(
         &[solana_multisig::create_associated_account(
             &payer.pubkey(),
             &wallet.pubkey(),
-            solana_multisig::MultisigInstruction::CreateAccount { threshold, owners }
-                .pack()
-                .expect("pack"),
+            solana_multisig::MultisigInstruction::CreateAccount {
+                threshold,
+                owners,
+                max_pending,
+            }
+            .pack()
+            .expect("pack"),
         )],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, wallet], connection.get_latest_blockhash()?);
 
-    connection.send_and_confirm_transaction(&transaction)?;
+    let size = transaction_size(&transaction);
+    if size > PACKET_DATA_SIZE {
+        return Err(Error::TransactionTooLarge(size));
+    }
 
-    Ok(())
+    send_or_simulate(transaction, &[payer, wallet], connection, dry_run)
 }
 
+/// Creates multisig accounts for several wallets, packing as many creation
+/// instructions into a transaction as fit. Returns the result of every batch
+/// along with the range of accounts it covers.
+pub fn create_accounts(
+    payer: &Keypair,
+    accounts: &[(Keypair, u32, Vec<Pubkey>)],
+    connection: &RpcClient,
+) -> Vec<(Range<usize>, Result<Signature>)> {
+    let instructions = accounts
+        .iter()
+        .map(|(wallet, threshold, owners)| {
+            solana_multisig::create_associated_account(
+                &payer.pubkey(),
+                &wallet.pubkey(),
+                solana_multisig::MultisigInstruction::CreateAccount {
+                    threshold: *threshold,
+                    owners: owners.clone(),
+                    max_pending: solana_multisig::MAX_TRANSACTIONS as u32,
+                }
+                .pack()
+                .expect("pack"),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    batch_instructions(&payer.pubkey(), &instructions)
+        .into_iter()
+        .map(|range| {
+            let wallets = accounts[range.clone()]
+                .iter()
+                .map(|(wallet, _, _)| wallet)
+                .collect::<Vec<_>>();
+            let result = send_batch(payer, &wallets, &instructions[range.clone()], connection);
+            (range, result)
+        })
+        .collect()
+}
+
+fn send_batch(
+    payer: &Keypair,
+    wallets: &[&Keypair],
+    instructions: &[Instruction],
+    connection: &RpcClient,
+) -> Result<Signature> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+
+    let size = transaction_size(&transaction);
+    if size > PACKET_DATA_SIZE {
+        return Err(Error::TransactionTooLarge(size));
+    }
+
+    let mut signers = vec![payer];
+    signers.extend_from_slice(wallets);
+    transaction.sign(&signers, connection.get_latest_blockhash()?);
+
+    send_and_confirm(&transaction, connection)
+}
+
+/// Splits instructions into consecutive ranges that each fit into
+/// a single transaction paid by `payer`.
+pub fn batch_instructions(payer: &Pubkey, instructions: &[Instruction]) -> Vec<Range<usize>> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    for end in 1..=instructions.len() {
+        let transaction = Transaction::new_with_payer(&instructions[start..end], Some(payer));
+        if end - start > 1 && transaction_size(&transaction) > PACKET_DATA_SIZE {
+            batches.push(start..end - 1);
+            start = end - 1;
+        }
+    }
+    if start < instructions.len() {
+        batches.push(start..instructions.len());
+    }
+    batches
+}
+
+/// Creates a transaction at the address derived from the next multisig
+/// nonce, returning it so it can be approved and executed later. A
+/// concurrent proposal taking the nonce first makes the program reject it.
+/// Multisigs created before nonces get a fresh transaction keypair instead.
+#[allow(clippy::too_many_arguments)]
 pub fn create_transaction(
     payer: &Keypair,
     wallet: &Keypair,
-    transaction: &Keypair,
     recipient: &Pubkey,
     amount: u64,
+    memo: String,
+    not_before_slot: u64,
+    expires_at_slot: u64,
+    soft: bool,
+    auto_approve: bool,
+    deposit: u64,
+    connection: &RpcClient,
+    dry_run: bool,
+) -> Result<(Pubkey, Signature)> {
+    let multisig = solana_multisig::get_associated_address(&wallet.pubkey());
+    let (_, multisig_data) =
+        fetch_multisig_account(&multisig, connection)?.ok_or(Error::AccountNotFound(multisig))?;
+    let nonce = multisig_data.nonce + 1;
+
+    let data = solana_multisig::MultisigInstruction::CreateTransaction {
+        amount,
+        memo,
+        not_before_slot,
+        expires_at_slot,
+        soft,
+        auto_approve,
+    }
+    .pack()
+    .expect("pack");
+
+    // Multisigs created before nonces only take transaction keypairs
+    let transaction_keypair = Keypair::new();
+    let has_nonce = Account::has_nonce(connection.get_account_data(&multisig)?.len());
+    let (transaction_address, mut instruction) = if has_nonce {
+        (
+            solana_multisig::get_transaction_address(&multisig, nonce),
+            solana_multisig::create_derived_transaction(
+                &payer.pubkey(),
+                &wallet.pubkey(),
+                nonce,
+                recipient,
+                data,
+            ),
+        )
+    } else {
+        (
+            transaction_keypair.pubkey(),
+            solana_multisig::create_transaction(
+                &payer.pubkey(),
+                &wallet.pubkey(),
+                &transaction_keypair.pubkey(),
+                recipient,
+                data,
+            ),
+        )
+    };
+    // An auto-approved transfer may execute at once and needs a writable recipient
+    instruction.accounts[3].is_writable = auto_approve;
+
+    // A deposit lands before the proposal checks the multisig balance
+    let mut instructions = Vec::with_capacity(2);
+    if deposit > 0 {
+        instructions.push(system_instruction::transfer(
+            &payer.pubkey(),
+            &multisig,
+            deposit,
+        ));
+    }
+    instructions.push(instruction);
+
+    let transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+    let mut signers = vec![payer, wallet];
+    if !has_nonce {
+        signers.push(&transaction_keypair);
+    }
+    let signature = send_or_simulate(transaction, &signers, connection, dry_run)?;
+
+    Ok((transaction_address, signature))
+}
+
+/// Transfers `amount` lamports from the payer to the multisig.
+pub fn deposit(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    amount: u64,
+    connection: &RpcClient,
+) -> Result<Signature> {
+    let transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            multisig,
+            amount,
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    send_confirmed(transaction, &[payer], connection)
+}
+
+pub fn approve_transaction(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    connection: &RpcClient,
+    dry_run: bool,
+) -> Result<Signature> {
+    let transaction = Transaction::new_with_payer(
+        &[solana_multisig::approve_transaction(
+            &payer.pubkey(),
+            multisig,
+            transaction,
+            solana_multisig::MultisigInstruction::ApproveTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    send_or_simulate(transaction, &[payer], connection, dry_run)
+}
+
+/// Signs an approval of `transaction` by `owner`, who also pays its fee, and
+/// serializes it so anyone can submit it with `import_and_submit`. The
+/// bundle has to be submitted before its blockhash expires.
+pub fn export_approval(
+    owner: &Keypair,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    connection: &RpcClient,
+) -> Result<Vec<u8>> {
+    let mut approval = Transaction::new_with_payer(
+        &[solana_multisig::approve_transaction(
+            &owner.pubkey(),
+            multisig,
+            transaction,
+            solana_multisig::MultisigInstruction::ApproveTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&owner.pubkey()),
+    );
+    approval.sign(&[owner], connection.get_latest_blockhash()?);
+
+    bincode::serialize(&approval).map_err(|e| Error::InvalidApprovalBundle(e.to_string()))
+}
+
+/// Decodes a bundle of `export_approval`, checking it is a single signed
+/// multisig approval.
+pub fn decode_approval(bundle: &[u8]) -> Result<Transaction> {
+    let approval: Transaction =
+        bincode::deserialize(bundle).map_err(|e| Error::InvalidApprovalBundle(e.to_string()))?;
+    approval
+        .sanitize()
+        .map_err(|e| Error::InvalidApprovalBundle(e.to_string()))?;
+
+    let is_approval = match approval.message.instructions.as_slice() {
+        [instruction] => {
+            *instruction.program_id(&approval.message.account_keys) == solana_multisig::id()
+                && matches!(
+                    solana_multisig::MultisigInstruction::unpack(&instruction.data),
+                    Ok(solana_multisig::MultisigInstruction::ApproveTransaction)
+                )
+        }
+        _ => false,
+    };
+    if !is_approval {
+        return Err(Error::InvalidApprovalBundle(
+            "not a multisig approval".to_string(),
+        ));
+    }
+
+    approval
+        .verify()
+        .map_err(|e| Error::InvalidApprovalBundle(e.to_string()))?;
+
+    Ok(approval)
+}
+
+/// Submits an approval bundle exported by an owner, as is, returning the
+/// approved transaction and the signature.
+pub fn import_and_submit(bundle: &[u8], connection: &RpcClient) -> Result<(Pubkey, Signature)> {
+    let approval = decode_approval(bundle)?;
+    // Accounts of `approve_transaction` are the owner, multisig and transaction
+    let message = &approval.message;
+    let transaction = message.account_keys[message.instructions[0].accounts[2] as usize];
+
+    Ok((transaction, send_and_confirm(&approval, connection)?))
+}
+
+/// Executes a transaction, passing the accounts its transfer or instruction
+/// touches.
+pub fn execute_transaction(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    transaction_data: &solana_multisig::Transaction,
+    connection: &RpcClient,
+) -> Result<()> {
+    let data = solana_multisig::MultisigInstruction::ExecuteTransaction
+        .pack()
+        .expect("pack");
+    let instruction = match &transaction_data.instruction {
+        Some(instruction) => solana_multisig::execute_instruction(
+            &payer.pubkey(),
+            multisig,
+            transaction,
+            instruction,
+            data,
+        ),
+        None => solana_multisig::execute_multi_transfer(
+            &payer.pubkey(),
+            multisig,
+            transaction,
+            &transaction_data.recipients(),
+            data,
+        ),
+    };
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    transaction.sign(&[payer], connection.get_latest_blockhash()?);
+
+    send_and_confirm(&transaction, connection)?;
+
+    Ok(())
+}
+
+/// Promotes an approved soft proposal, freezing its amount.
+pub fn promote_transaction(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    connection: &RpcClient,
+) -> Result<()> {
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::promote_transaction(
+            &payer.pubkey(),
+            multisig,
+            transaction,
+            solana_multisig::MultisigInstruction::PromoteTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer], connection.get_latest_blockhash()?);
+
+    send_and_confirm(&transaction, connection)?;
+
+    Ok(())
+}
+
+/// Closes an executed transaction, sending its rent to `destination`.
+pub fn close_transaction(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    destination: &Pubkey,
     connection: &RpcClient,
 ) -> Result<()> {
     let mut transaction = Transaction::new_with_payer(
-        &[solana_multisig::create_transaction(
+        &[solana_multisig::close_transaction(
             &payer.pubkey(),
+            multisig,
+            transaction,
+            destination,
+            solana_multisig::MultisigInstruction::CloseTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer], connection.get_latest_blockhash()?);
+
+    send_and_confirm(&transaction, connection)?;
+
+    Ok(())
+}
+
+/// Reaps every expired pending transaction of a multisig, unfreezing its
+/// amount and sending its rent to `destination`. Returns the reaped
+/// transaction addresses.
+pub fn reap_expired_transactions(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    destination: &Pubkey,
+    connection: &RpcClient,
+) -> Result<Vec<Pubkey>> {
+    let slot = connection.get_slot()?;
+
+    let mut reaped = Vec::new();
+    for (pending_transaction, transaction_data) in list_pending_transactions(multisig, connection)?
+    {
+        if !transaction_data.is_expired(slot) {
+            continue;
+        }
+
+        let mut transaction = Transaction::new_with_payer(
+            &[solana_multisig::reap_expired(
+                &payer.pubkey(),
+                multisig,
+                &pending_transaction,
+                destination,
+                solana_multisig::MultisigInstruction::ReapExpired
+                    .pack()
+                    .expect("pack"),
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[payer], connection.get_latest_blockhash()?);
+
+        send_and_confirm(&transaction, connection)?;
+
+        reaped.push(pending_transaction);
+    }
+
+    Ok(reaped)
+}
+
+/// Cancels a pending transaction of a multisig, unfreezing its amount and
+/// sending its rent to `destination`. `owners` sign together with the
+/// payer, duplicates are signed once.
+pub fn cancel_transaction(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    destination: &Pubkey,
+    owners: &[&Keypair],
+    connection: &RpcClient,
+) -> Result<()> {
+    let mut signers = vec![payer];
+    for owner in owners {
+        if !signers
+            .iter()
+            .any(|signer| signer.pubkey() == owner.pubkey())
+        {
+            signers.push(owner);
+        }
+    }
+    let owners = signers
+        .iter()
+        .map(|signer| signer.pubkey())
+        .collect::<Vec<_>>();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::cancel_transaction(
+            multisig,
+            transaction,
+            destination,
+            &owners,
+            solana_multisig::MultisigInstruction::CancelTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&signers, connection.get_latest_blockhash()?);
+
+    send_and_confirm(&transaction, connection)?;
+
+    Ok(())
+}
+
+/// Transactions of one multisig handled by `cancel_pending`
+#[derive(Debug, Default, PartialEq)]
+pub struct CancelSummary {
+    pub cancelled: Vec<Pubkey>,
+    // Left pending because the signers don't reach the threshold
+    pub rejected: Vec<Pubkey>,
+}
+
+/// Cancels every pending transaction of `multisig`, see
+/// `cancel_transaction`. Transactions the program rejects for missing owner
+/// signatures are listed as rejected, any other error stops the run.
+pub fn cancel_pending(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    destination: &Pubkey,
+    owners: &[&Keypair],
+    connection: &RpcClient,
+) -> Result<CancelSummary> {
+    let mut summary = CancelSummary::default();
+    for (transaction, _) in list_pending_transactions(multisig, connection)? {
+        match cancel_transaction(
+            payer,
+            multisig,
+            &transaction,
+            destination,
+            owners,
+            connection,
+        ) {
+            Ok(()) => summary.cancelled.push(transaction),
+            Err(e) if is_missing_signature(&e) => summary.rejected.push(transaction),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Returns true if the program rejected a transaction because its owner
+/// signatures don't reach the threshold.
+pub fn is_missing_signature(error: &Error) -> bool {
+    match error {
+        Error::ClientError(e) => matches!(
+            e.get_transaction_error(),
+            Some(TransactionError::InstructionError(
+                _,
+                InstructionError::MissingRequiredSignature
+            ))
+        ),
+        _ => false,
+    }
+}
+
+pub fn set_reserve(
+    payer: &Keypair,
+    wallet: &Keypair,
+    reserved_balance: u64,
+    connection: &RpcClient,
+) -> Result<()> {
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::set_reserve(
             &wallet.pubkey(),
-            &transaction.pubkey(),
-            recipient,
-            solana_multisig::MultisigInstruction::CreateTransaction { amount }
+            solana_multisig::MultisigInstruction::SetReserve { reserved_balance }
                 .pack()
                 .expect("pack"),
         )],
@@ -65,25 +769,23 @@ pub fn create_transaction(
     );
     transaction.sign(&[payer, wallet], connection.get_latest_blockhash()?);
 
-    connection.send_and_confirm_transaction(&transaction)?;
+    send_and_confirm(&transaction, connection)?;
 
     Ok(())
 }
 
-pub fn approve_transaction(
+pub fn revoke_transaction(
     payer: &Keypair,
     multisig: &Pubkey,
     transaction: &Pubkey,
-    recipient: &Pubkey,
     connection: &RpcClient,
 ) -> Result<()> {
     let mut transaction = Transaction::new_with_payer(
-        &[solana_multisig::approve_transaction(
+        &[solana_multisig::revoke_approval(
             &payer.pubkey(),
             multisig,
             transaction,
-            recipient,
-            solana_multisig::MultisigInstruction::ApproveTransaction
+            solana_multisig::MultisigInstruction::RevokeApproval
                 .pack()
                 .expect("pack"),
         )],
@@ -91,7 +793,593 @@ pub fn approve_transaction(
     );
     transaction.sign(&[payer], connection.get_latest_blockhash()?);
 
-    connection.send_and_confirm_transaction(&transaction)?;
+    send_and_confirm(&transaction, connection)?;
 
     Ok(())
 }
+
+/// Closes an emptied multisig, sending its lamports to `destination`.
+/// `owners` sign together with the payer, duplicates are signed once.
+pub fn close_account(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    destination: &Pubkey,
+    owners: &[&Keypair],
+    connection: &RpcClient,
+) -> Result<()> {
+    let mut signers = vec![payer];
+    for owner in owners {
+        if !signers
+            .iter()
+            .any(|signer| signer.pubkey() == owner.pubkey())
+        {
+            signers.push(owner);
+        }
+    }
+    let owners = signers
+        .iter()
+        .map(|signer| signer.pubkey())
+        .collect::<Vec<_>>();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::close_account(
+            multisig,
+            destination,
+            &owners,
+            solana_multisig::MultisigInstruction::CloseAccount
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&signers, connection.get_latest_blockhash()?);
+
+    send_and_confirm(&transaction, connection)?;
+
+    Ok(())
+}
+
+/// Adds `owner` to the multisig, signed by the payer as an existing owner.
+/// The payer funds the rent when a full first release multisig is grown.
+/// Returns the resulting owner set.
+pub fn add_owner(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    owner: &Pubkey,
+    connection: &RpcClient,
+) -> Result<Vec<Pubkey>> {
+    let (_, multisig_data) =
+        fetch_multisig_account(multisig, connection)?.ok_or(Error::AccountNotFound(*multisig))?;
+    let owners = owners_after_add(&multisig_data, &payer.pubkey(), owner)?;
+
+    let transaction = Transaction::new_with_payer(
+        &[solana_multisig::add_owner(
+            multisig,
+            &payer.pubkey(),
+            &[payer.pubkey()],
+            solana_multisig::MultisigInstruction::AddOwner { owner: *owner }
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&payer.pubkey()),
+    );
+    send_confirmed(transaction, &[payer], connection)?;
+
+    Ok(owners)
+}
+
+/// Removes `owner` from the multisig, signed by the payer as an existing
+/// owner. Nothing is sent if the remaining owners can't reach the threshold.
+/// Returns the resulting owner set.
+pub fn remove_owner(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    owner: &Pubkey,
+    connection: &RpcClient,
+) -> Result<Vec<Pubkey>> {
+    let (_, multisig_data) =
+        fetch_multisig_account(multisig, connection)?.ok_or(Error::AccountNotFound(*multisig))?;
+    let owners = owners_after_removal(&multisig_data, &payer.pubkey(), owner)?;
+
+    let transaction = Transaction::new_with_payer(
+        &[solana_multisig::remove_owner(
+            multisig,
+            &[payer.pubkey()],
+            solana_multisig::MultisigInstruction::RemoveOwner { owner: *owner }
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&payer.pubkey()),
+    );
+    send_confirmed(transaction, &[payer], connection)?;
+
+    Ok(owners)
+}
+
+/// Owner set after `signer` adds `owner`, failing the way the program would.
+pub fn owners_after_add(
+    multisig: &Account,
+    signer: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Vec<Pubkey>> {
+    if !multisig.owners.contains(signer) {
+        return Err(Error::NotMultisigOwner(*signer));
+    }
+    if multisig.owners.contains(owner) {
+        return Err(Error::DuplicateOwner(*owner));
+    }
+
+    let mut owners = multisig.owners.clone();
+    owners.push(*owner);
+    Ok(owners)
+}
+
+/// Owner set after `signer` removes `owner`, failing the way the program
+/// would.
+pub fn owners_after_removal(
+    multisig: &Account,
+    signer: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Vec<Pubkey>> {
+    if !multisig.owners.contains(signer) {
+        return Err(Error::NotMultisigOwner(*signer));
+    }
+    if !multisig.owners.contains(owner) {
+        return Err(Error::NotMultisigOwner(*owner));
+    }
+
+    let remaining_weight = multisig.total_weight() - multisig.owner_weight(owner);
+    if remaining_weight < multisig.threshold {
+        return Err(Error::ThresholdUnreachable(
+            remaining_weight,
+            multisig.threshold,
+        ));
+    }
+
+    Ok(multisig
+        .owners
+        .iter()
+        .filter(|key| *key != owner)
+        .copied()
+        .collect())
+}
+
+/// Fetches account data together with the slot it was read at.
+pub fn fetch_account_data(pubkey: &Pubkey, connection: &RpcClient) -> Result<(u64, Vec<u8>)> {
+    let response = connection.get_account_with_commitment(pubkey, connection.commitment())?;
+    match response.value {
+        Some(account) => Ok((response.context.slot, account.data)),
+        None => Err(Error::AccountNotFound(*pubkey)),
+    }
+}
+
+/// Derives the multisig associated with `wallet` and fetches it,
+/// `None` if it hasn't been created yet.
+pub fn fetch_multisig(wallet: &Pubkey, connection: &RpcClient) -> Result<Option<Account>> {
+    let multisig = solana_multisig::get_associated_address(wallet);
+    Ok(fetch_multisig_account(&multisig, connection)?.map(|(_, multisig_data)| multisig_data))
+}
+
+/// Fetches and decodes the multisig at `multisig` together with its balance,
+/// `None` if the account doesn't exist.
+pub fn fetch_multisig_account(
+    multisig: &Pubkey,
+    connection: &RpcClient,
+) -> Result<Option<(u64, Account)>> {
+    let response = connection.get_account_with_commitment(multisig, connection.commitment())?;
+    let account = match response.value {
+        Some(account) => account,
+        None => return Ok(None),
+    };
+
+    if account.owner != solana_multisig::id() {
+        return Err(Error::InvalidAccountOwner(*multisig));
+    }
+    let multisig_data =
+        Account::unpack(&account.data).map_err(|_| Error::AccountDecode(*multisig))?;
+
+    Ok(Some((account.lamports, multisig_data)))
+}
+
+/// Finds every multisig listing `owner`. Every multisig of each account
+/// layout is fetched with a single query and filtered after decoding.
+pub fn find_multisigs_for_owner(
+    owner: &Pubkey,
+    connection: &RpcClient,
+) -> Result<Vec<(Pubkey, Account)>> {
+    let mut accounts = Vec::new();
+    for len in Account::layout_lens() {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::DataSize(len as u64)]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: None,
+        };
+        accounts
+            .extend(connection.get_program_accounts_with_config(&solana_multisig::id(), config)?);
+    }
+
+    Ok(multisigs_with_owner(owner, accounts))
+}
+
+/// Decodes the multisigs among `accounts` that list `owner`, once each.
+/// Slots past the owner count hold pending transactions, so finding the
+/// key in the owner region alone doesn't make `owner` an owner.
+pub fn multisigs_with_owner(
+    owner: &Pubkey,
+    accounts: Vec<(Pubkey, solana_sdk::account::Account)>,
+) -> Vec<(Pubkey, Account)> {
+    let mut multisigs: Vec<(Pubkey, Account)> = Vec::new();
+    for (address, account) in accounts {
+        if multisigs.iter().any(|(known, _)| *known == address) {
+            continue;
+        }
+        match Account::unpack(&account.data) {
+            Ok(multisig) if multisig.owners.contains(owner) => multisigs.push((address, multisig)),
+            _ => {}
+        }
+    }
+    multisigs
+}
+
+/// Fetches and decodes the multisig transaction at `transaction`.
+pub fn fetch_transaction(
+    transaction: &Pubkey,
+    connection: &RpcClient,
+) -> Result<solana_multisig::Transaction> {
+    let account = connection
+        .get_account_with_commitment(transaction, connection.commitment())?
+        .value
+        .ok_or(Error::AccountNotFound(*transaction))?;
+
+    if account.owner != solana_multisig::id() {
+        return Err(Error::InvalidAccountOwner(*transaction));
+    }
+
+    solana_multisig::Transaction::unpack_unchecked(&account.data)
+        .map_err(|_| Error::AccountDecode(*transaction))
+}
+
+/// Checks that `owner` can still approve `transaction`, so a transaction
+/// the program would reject isn't paid for.
+pub fn check_can_approve(
+    multisig: &Account,
+    transaction: &solana_multisig::Transaction,
+    owner: &Pubkey,
+) -> Result<()> {
+    match transaction.signed_by(multisig, owner) {
+        None => Err(Error::NotTransactionOwner(*owner)),
+        Some(true) => Err(Error::AlreadyApproved(*owner)),
+        Some(false) => Ok(()),
+    }
+}
+
+/// Fetches and decodes the pending transactions of a multisig. Transaction
+/// accounts closed in the meantime are skipped with a warning.
+pub fn list_pending_transactions(
+    multisig: &Pubkey,
+    connection: &RpcClient,
+) -> Result<Vec<(Pubkey, solana_multisig::Transaction)>> {
+    let (_, multisig_data) = fetch_account_data(multisig, connection)?;
+    let multisig_data = Account::unpack(&multisig_data)?;
+
+    let mut transactions = Vec::with_capacity(multisig_data.pending_transactions.len());
+    for pending_transaction in multisig_data.pending_transactions {
+        let transaction_data = match fetch_account_data(&pending_transaction, connection) {
+            Ok((_, data)) => data,
+            Err(Error::AccountNotFound(_)) => {
+                eprintln!(
+                    "Warning: pending transaction {} not found, skipping",
+                    pending_transaction
+                );
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        transactions.push((
+            pending_transaction,
+            solana_multisig::Transaction::unpack_unchecked(&transaction_data)?,
+        ));
+    }
+
+    Ok(transactions)
+}
+
+/// Returns true if `transaction` expires within `seconds` after `slot`, so
+/// its owners are warned to approve before it lapses. Seconds are converted
+/// at the nominal slot time; transactions without an expiry or already
+/// expired are never flagged.
+pub fn expires_within(transaction: &solana_multisig::Transaction, slot: u64, seconds: u64) -> bool {
+    let window_slots = seconds.saturating_mul(1_000) / DEFAULT_MS_PER_SLOT;
+    transaction.expires_at_slot != 0
+        && !transaction.is_expired(slot)
+        && transaction.expires_at_slot - slot <= window_slots
+}
+
+/// Transactions of one multisig handled by `approve_pending`
+#[derive(Debug, Default, PartialEq)]
+pub struct ApprovalSummary {
+    pub approved: Vec<Pubkey>,
+    // Reached the threshold but weren't executed
+    pub ready: usize,
+    pub executed: usize,
+}
+
+/// Approves every pending transaction of `multisig` that `payer` can still
+/// sign. With `execute`, transactions reaching the threshold are executed
+/// right away instead of being left ready.
+pub fn approve_pending(
+    payer: &Keypair,
+    multisig: &Pubkey,
+    execute: bool,
+    connection: &RpcClient,
+) -> Result<ApprovalSummary> {
+    let (_, multisig_data) =
+        fetch_multisig_account(multisig, connection)?.ok_or(Error::AccountNotFound(*multisig))?;
+
+    let mut summary = ApprovalSummary::default();
+    for (transaction, transaction_data) in list_pending_transactions(multisig, connection)? {
+        if check_can_approve(&multisig_data, &transaction_data, &payer.pubkey()).is_err() {
+            continue;
+        }
+
+        approve_transaction(payer, multisig, &transaction, connection, false)?;
+        summary.approved.push(transaction);
+
+        let transaction_data = fetch_transaction(&transaction, connection)?;
+        if !is_ready(&multisig_data, &transaction_data) {
+            continue;
+        }
+        if execute {
+            execute_transaction(payer, multisig, &transaction, &transaction_data, connection)?;
+            summary.executed += 1;
+        } else {
+            summary.ready += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Returns true if the transaction collected enough approvals
+/// but has not been executed yet.
+pub fn is_ready(multisig: &Account, transaction: &solana_multisig::Transaction) -> bool {
+    let approval_weight = multisig.approval_weight(&transaction.signer_status(multisig));
+
+    transaction.status == TransactionStatus::Pending && approval_weight >= multisig.threshold
+}
+
+/// Returns the pending transactions matching `recipient`, `amount` and,
+/// if given, `memo`, used to recover the address of a transaction created
+/// earlier.
+pub fn find_transactions<'a>(
+    transactions: &'a [(Pubkey, solana_multisig::Transaction)],
+    recipient: &Pubkey,
+    amount: u64,
+    memo: Option<&str>,
+) -> Vec<(&'a Pubkey, &'a solana_multisig::Transaction)> {
+    transactions
+        .iter()
+        .filter(|(_, transaction)| {
+            transaction.recipient == *recipient
+                && transaction.amount == amount
+                && memo.map_or(true, |memo| transaction.memo == memo)
+        })
+        .map(|(address, transaction)| (address, transaction))
+        .collect()
+}
+
+/// Formats the state of a multisig holding `lamports`, its `Display` block
+/// followed by the live balance.
+pub fn describe_account(multisig: &Account, lamports: u64) -> String {
+    format!("{}\nbalance: {}", multisig, lamports)
+}
+
+/// Machine-readable view of a multisig account, see `describe_account`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AccountView {
+    pub address: String,
+    pub threshold: u32,
+    pub owners: Vec<String>,
+    // Empty when every owner counts as one
+    pub owner_weights: Vec<u16>,
+    pub pending_transactions: Vec<String>,
+    pub frozen_amount: u64,
+    pub balance: u64,
+    pub is_frozen: bool,
+    pub last_updated_slot: u64,
+}
+
+impl AccountView {
+    pub fn new(address: &Pubkey, multisig: &Account, lamports: u64) -> Self {
+        Self {
+            address: address.to_string(),
+            threshold: multisig.threshold,
+            owners: multisig.owners.iter().map(Pubkey::to_string).collect(),
+            owner_weights: multisig.owner_weights.clone(),
+            pending_transactions: multisig
+                .pending_transactions
+                .iter()
+                .map(Pubkey::to_string)
+                .collect(),
+            frozen_amount: multisig.frozen_amount,
+            balance: lamports,
+            is_frozen: multisig.is_frozen,
+            last_updated_slot: multisig.last_updated_slot,
+        }
+    }
+}
+
+/// Machine-readable view of a multisig transaction.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TransactionView {
+    pub address: String,
+    pub recipient: String,
+    pub amount: u64,
+    pub memo: String,
+    pub creator: String,
+    pub status: String,
+    pub signed_by: Vec<String>,
+    // Number of owners that signed out of `owners`
+    pub approvals: u32,
+    pub owners: u32,
+    // Set by `list-transactions --warn-within` when the expiry is near
+    pub expires_soon: bool,
+}
+
+impl TransactionView {
+    pub fn new(
+        address: &Pubkey,
+        transaction: &solana_multisig::Transaction,
+        multisig: &Account,
+    ) -> Self {
+        Self {
+            address: address.to_string(),
+            recipient: transaction.recipient.to_string(),
+            amount: transaction.amount,
+            memo: transaction.memo.clone(),
+            creator: transaction.creator.to_string(),
+            status: status_name(transaction.status).to_string(),
+            signed_by: transaction
+                .signer_status(multisig)
+                .iter()
+                .filter(|(_, is_signed)| *is_signed)
+                .map(|(signer, _)| signer.to_string())
+                .collect(),
+            approvals: transaction.approval_count(multisig),
+            owners: multisig.owners.len() as u32,
+            expires_soon: false,
+        }
+    }
+}
+
+/// Machine-readable result of a command that sent a transaction.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SignatureView {
+    // Multisig or transaction account the command created or acted on
+    pub account: String,
+    pub signature: String,
+}
+
+impl SignatureView {
+    pub fn new(account: &Pubkey, signature: &Signature) -> Self {
+        Self {
+            account: account.to_string(),
+            signature: signature.to_string(),
+        }
+    }
+}
+
+/// Machine-readable outcome of one multisig of `create-accounts`, `error`
+/// is set if its batch failed.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CreatedAccountView {
+    pub wallet: String,
+    pub account: String,
+    pub signature: Option<String>,
+    pub error: Option<String>,
+}
+
+impl CreatedAccountView {
+    pub fn new(wallet: &Pubkey, result: &Result<Signature>) -> Self {
+        Self {
+            wallet: wallet.to_string(),
+            account: solana_multisig::get_associated_address(wallet).to_string(),
+            signature: result.as_ref().ok().map(|signature| signature.to_string()),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Machine-readable outcome of one multisig of `approve-all`, `error` is
+/// set if it failed.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ApprovalSummaryView {
+    pub account: String,
+    pub approved: Vec<String>,
+    pub executed: usize,
+    pub ready: usize,
+    pub error: Option<String>,
+}
+
+impl ApprovalSummaryView {
+    pub fn new(multisig: &Pubkey, result: &Result<ApprovalSummary>) -> Self {
+        let summary = result.as_ref().ok();
+        Self {
+            account: multisig.to_string(),
+            approved: summary.map_or(vec![], |summary| {
+                summary.approved.iter().map(|key| key.to_string()).collect()
+            }),
+            executed: summary.map_or(0, |summary| summary.executed),
+            ready: summary.map_or(0, |summary| summary.ready),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Machine-readable result of `export-approval`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ApprovalFileView {
+    // Transaction the exported approval is for
+    pub account: String,
+    pub file: String,
+}
+
+/// Multisig address derived from a wallet, computed locally.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AddressView {
+    pub wallet: String,
+    pub address: String,
+    pub bump_seed: u8,
+}
+
+impl AddressView {
+    pub fn new(wallet: &Pubkey) -> Self {
+        let (address, bump_seed) =
+            solana_multisig::get_associated_address_and_bump_seed(wallet, &solana_multisig::id());
+        Self {
+            wallet: wallet.to_string(),
+            address: address.to_string(),
+            bump_seed,
+        }
+    }
+}
+
+/// Lower-case name of a transaction status, as shown by the CLI.
+pub fn status_name(status: TransactionStatus) -> &'static str {
+    match status {
+        TransactionStatus::Pending => "pending",
+        TransactionStatus::Executed => "executed",
+        TransactionStatus::Cancelled => "cancelled",
+        TransactionStatus::Expired => "expired",
+    }
+}
+
+/// Formats a transaction address followed by its memo, if any.
+pub fn describe_transaction(
+    address: &Pubkey,
+    transaction: &solana_multisig::Transaction,
+) -> String {
+    if transaction.memo.is_empty() {
+        address.to_string()
+    } else {
+        format!("{} {}", address, transaction.memo)
+    }
+}
+
+/// Estimates the wire size of a transaction: compact-encoded signatures
+/// followed by the serialized message.
+pub fn transaction_size(transaction: &Transaction) -> usize {
+    let signatures_len = transaction.signatures.len();
+
+    let mut short_vec_len = 1;
+    let mut rem = signatures_len >> 7;
+    while rem > 0 {
+        short_vec_len += 1;
+        rem >>= 7;
+    }
+
+    short_vec_len + signatures_len * 64 + transaction.message_data().len()
+}
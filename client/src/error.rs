@@ -12,15 +12,47 @@ pub enum Error {
     InvalidThreshold,
     #[error("invalid owners")]
     InvalidOwners,
-    #[error("owners cannot be greater than the threshold")]
+    #[error("threshold cannot be greater than the number of owners")]
     InvalidOwnersNumber,
     #[error("invalid recipient")]
     InvalidRecipient,
     #[error("invalid amount")]
     InvalidAmount,
+    #[error("invalid csv row ({0})")]
+    InvalidCsvRow(usize),
+    #[error("invalid multisig address ({0})")]
+    InvalidMultisig(String),
+    #[error("transaction size {0} exceeds packet limit, try fewer owners")]
+    TransactionTooLarge(usize),
+    #[error("account not found ({0})")]
+    AccountNotFound(solana_sdk::pubkey::Pubkey),
+    #[error("account is not owned by the multisig program ({0})")]
+    InvalidAccountOwner(solana_sdk::pubkey::Pubkey),
+    #[error("failed to decode multisig account ({0})")]
+    AccountDecode(solana_sdk::pubkey::Pubkey),
+    #[error("failed to write account cache: ({0})")]
+    CacheWriteError(std::io::Error),
+    #[error("no healthy RPC node, tried ({0})")]
+    NoHealthyNode(String),
+    #[error("you are not an owner of this transaction ({0})")]
+    NotTransactionOwner(solana_sdk::pubkey::Pubkey),
+    #[error("transaction is already approved by ({0})")]
+    AlreadyApproved(solana_sdk::pubkey::Pubkey),
+    #[error("not an owner of this multisig ({0})")]
+    NotMultisigOwner(solana_sdk::pubkey::Pubkey),
+    #[error("already an owner of this multisig ({0})")]
+    DuplicateOwner(solana_sdk::pubkey::Pubkey),
+    #[error("removing the owner leaves a weight of {0}, below the threshold of {1}")]
+    ThresholdUnreachable(u32, u32),
+    #[error("insufficient funds, needed {needed} lamports but have {have}")]
+    InsufficientFunds { needed: u64, have: u64 },
+    #[error("invalid approval bundle: ({0})")]
+    InvalidApprovalBundle(String),
 
     #[error("solana client error: ({0})")]
     ClientError(#[from] solana_client::client_error::ClientError),
+    #[error("failed to decode account: ({0})")]
+    DecodeError(#[from] solana_program::program_error::ProgramError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
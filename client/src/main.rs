@@ -5,11 +5,14 @@ use clap::{
 };
 
 use solana_clap_utils::input_parsers::value_of;
-use solana_clap_utils::input_validators::{is_amount, is_valid_pubkey};
-use solana_multisig::{Account, Transaction, MAX_SIGNERS, MIN_SIGNERS};
+use solana_clap_utils::input_validators::{is_amount, is_parsable, is_valid_pubkey};
+use solana_multisig::{
+    Account, Transaction, MAX_MEMO_LEN, MAX_SIGNERS, MAX_TRANSACTIONS, MIN_SIGNERS,
+};
 use solana_program::program_pack::Pack;
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::signature::{read_keypair_file, Signer};
 
 use solana_multisig_cli::client::*;
 use solana_multisig_cli::error;
@@ -20,6 +23,56 @@ fn main() -> anyhow::Result<()> {
         .about(crate_description!())
         .version(crate_version!())
         .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("no_cache")
+                .long("no-cache")
+                .global(true)
+                .takes_value(false)
+                .help("Always fetch accounts from the cluster instead of the local cache"),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .global(true)
+                .takes_value(false)
+                .help("Simulate create-account, create-transaction and approve without sending"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .value_name("FORMAT")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .help("Print results as human-readable text (default) or JSON"),
+        )
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .value_name("RPC_URL")
+                .global(true)
+                .takes_value(true)
+                .help("RPC endpoint to use instead of the one in the solana config file"),
+        )
+        .arg(
+            Arg::with_name("commitment")
+                .long("commitment")
+                .value_name("LEVEL")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["processed", "confirmed", "finalized"])
+                .help("Commitment level of reads and confirmations [default: confirmed]"),
+        )
+        .arg(
+            Arg::with_name("fallback_url")
+                .long("fallback-url")
+                .value_name("URL")
+                .global(true)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("RPC endpoint to use when the configured node is unhealthy"),
+        )
         .subcommand(
             SubCommand::with_name("create-account")
                 .about("Create a new multisig account")
@@ -50,6 +103,68 @@ fn main() -> anyhow::Result<()> {
                             signing members of this account. [{} <= N <= {}]",
                             MIN_SIGNERS, MAX_SIGNERS,
                         )),
+                )
+                .arg(
+                    Arg::with_name("max_pending")
+                        .long("max-pending")
+                        .value_name("N")
+                        .validator(is_max_pending_valid)
+                        .takes_value(true)
+                        .help(&format!(
+                            "The most transactions pending at once. [1 <= N <= {}, default {}]",
+                            MAX_TRANSACTIONS, MAX_TRANSACTIONS,
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("create-accounts")
+                .about("Create multisig accounts listed in a CSV file")
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .value_name("CSV")
+                        .takes_value(true)
+                        .required(true)
+                        .help("CSV file with `wallet_keypair_path,threshold,owners...` rows"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("show-account")
+                .about("Show the state of a multisig account")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("address")
+                .about("Print the multisig address derived from a wallet, offline")
+                .arg(
+                    Arg::with_name("wallet")
+                        .validator(is_valid_pubkey)
+                        .value_name("WALLET")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Wallet address"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("my-multisigs")
+                .about("List the multisig accounts an owner belongs to")
+                .arg(
+                    Arg::with_name("owner")
+                        .validator(is_valid_pubkey)
+                        .value_name("OWNER")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Owner address"),
                 ),
         )
         .subcommand(
@@ -71,7 +186,65 @@ fn main() -> anyhow::Result<()> {
                         .takes_value(true)
                         .index(2)
                         .required(true)
-                        .help("Amount to transfer"),
+                        .help("Amount to transfer in SOL"),
+                )
+                .arg(
+                    Arg::with_name("lamports")
+                        .long("lamports")
+                        .takes_value(false)
+                        .help("Read AMOUNT as whole lamports instead of SOL"),
+                )
+                .arg(
+                    Arg::with_name("memo")
+                        .long("memo")
+                        .value_name("MEMO")
+                        .validator(is_memo)
+                        .takes_value(true)
+                        .help("Description shown to the approving owners"),
+                )
+                .arg(
+                    Arg::with_name("not_before_slot")
+                        .long("not-before-slot")
+                        .value_name("SLOT")
+                        .validator(is_parsable::<u64>)
+                        .takes_value(true)
+                        .help("Slot before which the transaction can't be executed"),
+                )
+                .arg(
+                    Arg::with_name("expires_at_slot")
+                        .long("expires-at-slot")
+                        .value_name("SLOT")
+                        .validator(is_parsable::<u64>)
+                        .takes_value(true)
+                        .help("Slot from which the transaction can only be reaped"),
+                )
+                .arg(
+                    Arg::with_name("soft")
+                        .long("soft")
+                        .takes_value(false)
+                        .help("Propose without freezing the amount until promoted"),
+                )
+                .arg(
+                    Arg::with_name("deposit")
+                        .long("deposit")
+                        .value_name("AMOUNT")
+                        .validator(is_amount)
+                        .takes_value(true)
+                        .help("Fund the multisig with AMOUNT in the same transaction"),
+                )
+                .arg(
+                    Arg::with_name("approve")
+                        .long("approve")
+                        .takes_value(false)
+                        .help(
+                            "Approve as the proposing owner, executing once the threshold is met",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("explorer")
+                        .long("explorer")
+                        .takes_value(false)
+                        .help("Print Solana Explorer links for the created transaction"),
                 ),
         )
         .subcommand(
@@ -85,96 +258,1264 @@ fn main() -> anyhow::Result<()> {
                         .index(1)
                         .required(true)
                         .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("transaction")
+                        .long("transaction")
+                        .validator(is_valid_pubkey)
+                        .value_name("TRANSACTION")
+                        .takes_value(true)
+                        .conflicts_with("all")
+                        .help("Transaction address to approve"),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .takes_value(false)
+                        .help("Approve every pending transaction awaiting your signature"),
                 ),
         )
-        .get_matches();
-
-    let connection = establish_connection()?;
-    println!(
-        "Connected to remote solana node running version ({}).",
-        connection.get_version()?
-    );
-
-    let payer = get_payer()?;
-
-    let (sub_command, sub_matches) = app_matches.subcommand();
-
-    let _ = match (sub_command, sub_matches) {
-        ("create-account", Some(arg_matches)) => {
-            let threshold =
-                value_of::<u32>(arg_matches, "threshold").ok_or(error::Error::InvalidThreshold)?;
-            let owners = pubkeys_of_multiple_signers(arg_matches, "owners")?
-                .ok_or(error::Error::InvalidOwners)?;
-
-            if threshold < owners.len() as u32 {
-                return Err(error::Error::InvalidOwnersNumber.into());
-            }
-
-            create_account(&payer, &payer, threshold, owners, &connection)?
-        }
-        ("create-transaction", Some(arg_matches)) => {
-            let recipient = Pubkey::from_str(
-                value_of::<String>(arg_matches, "recipient")
-                    .ok_or(error::Error::InvalidThreshold)?
-                    .as_str(),
-            )?;
-
-            let amount =
-                value_of::<u64>(arg_matches, "amount").ok_or(error::Error::InvalidAmount)?;
-
-            let transaction = Keypair::new();
-
-            create_transaction(
-                &payer,
-                &payer,
-                &transaction,
-                &recipient,
-                amount,
-                &connection,
-            )?
-        }
-        ("approve", Some(arg_matches)) => {
-            let multisig = Pubkey::from_str(
-                value_of::<String>(arg_matches, "multisig")
-                    .ok_or(error::Error::InvalidThreshold)?
-                    .as_str(),
-            )?;
-
-            let multisig_info = connection.get_account(&multisig)?;
-            let multisig_data = Account::unpack(&multisig_info.data)?;
-
-            let mut need_to_approve = Vec::new();
-
-            for pending_transaction in multisig_data.pending_transactions {
-                let pending_transaction_info = connection.get_account(&pending_transaction)?;
-                let pending_transaction_data =
-                    Transaction::unpack_unchecked(&pending_transaction_info.data)?;
-
-                for (signer, is_signed) in pending_transaction_data.signers {
-                    if signer == payer.pubkey() && !is_signed {
-                        need_to_approve
-                            .push((pending_transaction, pending_transaction_data.recipient));
-                        break;
-                    }
-                }
-            }
-
-            for (transaction, recipient) in need_to_approve {
-                approve_transaction(&payer, &multisig, &transaction, &recipient, &connection)?;
-            }
-        }
-        _ => {}
-    };
-
-    Ok(())
-}
-
-fn is_signers_number_valid(string: String) -> Result<(), String> {
-    let v = u8::from_str(&string).map_err(|e| e.to_string())? as usize;
-    if v < MIN_SIGNERS {
-        Err(format!("must be at least {}", MIN_SIGNERS))
-    } else if v > MAX_SIGNERS {
-        Err(format!("must be at most {}", MAX_SIGNERS))
+        .subcommand(
+            SubCommand::with_name("approve-all")
+                .about("Approve everything pending for you across several multisigs")
+                .arg(
+                    Arg::with_name("multisigs")
+                        .long("multisigs")
+                        .value_name("MULTISIGS")
+                        .takes_value(true)
+                        .required_unless("file")
+                        .conflicts_with("file")
+                        .help("Comma separated multisig addresses"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("File listing multisig addresses, one per line"),
+                )
+                .arg(
+                    Arg::with_name("execute")
+                        .long("execute")
+                        .takes_value(false)
+                        .help("Execute transactions reaching the threshold"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export-approval")
+                .about("Sign an approval to be submitted from another machine")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("transaction")
+                        .validator(is_valid_pubkey)
+                        .value_name("TRANSACTION")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Transaction address to approve"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("File to write the signed approval to"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("submit-approval")
+                .about("Submit an approval signed with export-approval")
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("File with the signed approval"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("execute")
+                .about("Execute approved multisig transaction")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("transaction")
+                        .validator(is_valid_pubkey)
+                        .value_name("TRANSACTION")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Transaction address"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("promote")
+                .about("Freeze the amount of an approved soft proposal")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("transaction")
+                        .validator(is_valid_pubkey)
+                        .value_name("TRANSACTION")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Transaction address"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("revoke")
+                .about("Revoke approval of multisig transaction")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("transaction")
+                        .validator(is_valid_pubkey)
+                        .value_name("TRANSACTION")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Transaction address"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("ready")
+                .about("List transactions that are approved but not executed")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list-transactions")
+                .about("List pending multisig transactions with their approvals")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("warn_within")
+                        .long("warn-within")
+                        .value_name("SECONDS")
+                        .validator(is_parsable::<u64>)
+                        .takes_value(true)
+                        .help("Flag transactions expiring within this many seconds"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("find-transaction")
+                .about("Find pending multisig transactions by recipient and amount")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("recipient")
+                        .long("recipient")
+                        .validator(is_valid_pubkey)
+                        .value_name("RECIPIENT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Recipient address"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .validator(is_amount)
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount to transfer in SOL"),
+                )
+                .arg(
+                    Arg::with_name("lamports")
+                        .long("lamports")
+                        .takes_value(false)
+                        .help("Read AMOUNT as whole lamports instead of SOL"),
+                )
+                .arg(
+                    Arg::with_name("memo")
+                        .long("memo")
+                        .value_name("MEMO")
+                        .takes_value(true)
+                        .help("Only list transactions with this memo"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("reap-expired")
+                .about("Reap expired multisig transactions and unfreeze their amounts")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .validator(is_valid_pubkey)
+                        .value_name("DESTINATION")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Address receiving the rent of reaped transactions"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cancel-transaction")
+                .about("Cancel a pending multisig transaction and unfreeze its amount")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("transaction")
+                        .validator(is_valid_pubkey)
+                        .value_name("TRANSACTION")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Transaction address"),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .validator(is_valid_pubkey)
+                        .value_name("DESTINATION")
+                        .takes_value(true)
+                        .index(3)
+                        .required(true)
+                        .help("Address receiving the rent of the transaction"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Keypair file of an owner co-signing with the payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cancel-all")
+                .about("Cancel every pending multisig transaction and unfreeze their amounts")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Keypair file of an owner co-signing with the payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-reserve")
+                .about("Set balance that can never be spent through the multisig")
+                .arg(
+                    Arg::with_name("amount")
+                        .value_name("AMOUNT")
+                        .validator(is_amount)
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Amount to reserve in SOL, 0 lifts the reserve"),
+                )
+                .arg(
+                    Arg::with_name("lamports")
+                        .long("lamports")
+                        .takes_value(false)
+                        .help("Read AMOUNT as whole lamports instead of SOL"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("close-account")
+                .about("Close an emptied multisig account and reclaim its rent")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .validator(is_valid_pubkey)
+                        .value_name("DESTINATION")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Address receiving the multisig lamports"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Keypair file of an owner co-signing with the payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit")
+                .about("Transfer funds to a multisig")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .value_name("AMOUNT")
+                        .validator(is_amount)
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Amount to deposit in SOL"),
+                )
+                .arg(
+                    Arg::with_name("lamports")
+                        .long("lamports")
+                        .takes_value(false)
+                        .help("Read AMOUNT as whole lamports instead of SOL"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("add-owner")
+                .about("Add an owner to a multisig you own")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .validator(is_valid_pubkey)
+                        .value_name("OWNER")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Address of the new owner"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("remove-owner")
+                .about("Remove an owner from a multisig you own")
+                .arg(
+                    Arg::with_name("multisig")
+                        .validator(is_valid_pubkey)
+                        .value_name("MULTISIG")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Multisig address"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .validator(is_valid_pubkey)
+                        .value_name("OWNER")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Address of the owner to remove"),
+                ),
+        )
+        .get_matches();
+
+    let fallback_urls = app_matches
+        .values_of("fallback_url")
+        .or_else(|| {
+            let (_, sub_matches) = app_matches.subcommand();
+            sub_matches.and_then(|matches| matches.values_of("fallback_url"))
+        })
+        .map(|urls| urls.map(String::from).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let (sub_command, sub_matches) = app_matches.subcommand();
+
+    let output = app_matches
+        .value_of("output")
+        .or_else(|| sub_matches.and_then(|matches| matches.value_of("output")))
+        .map_or(Ok(OutputFormat::Text), OutputFormat::from_str)
+        .map_err(anyhow::Error::msg)?;
+
+    // Derived locally, no need to connect
+    if let ("address", Some(arg_matches)) = (sub_command, sub_matches) {
+        let wallet = Pubkey::from_str(
+            value_of::<String>(arg_matches, "wallet")
+                .ok_or(error::Error::InvalidThreshold)?
+                .as_str(),
+        )?;
+
+        let view = AddressView::new(&wallet);
+        match output {
+            OutputFormat::Text => println!("{} (bump seed {})", view.address, view.bump_seed),
+            OutputFormat::Json => print_json(&view)?,
+        }
+        return Ok(());
+    }
+
+    let url = app_matches
+        .value_of("url")
+        .or_else(|| sub_matches.and_then(|matches| matches.value_of("url")));
+    let commitment = app_matches
+        .value_of("commitment")
+        .or_else(|| sub_matches.and_then(|matches| matches.value_of("commitment")))
+        .map_or(
+            Ok(CommitmentConfig::confirmed()),
+            CommitmentConfig::from_str,
+        )?;
+
+    let rpc_url = match url {
+        Some(url) => url.to_string(),
+        None => get_rpc_url()?,
+    };
+    let connection = establish_healthy_connection(Some(&rpc_url), commitment, &fallback_urls)?;
+    if output == OutputFormat::Json {
+        disable_spinner();
+    } else {
+        println!(
+            "Connected to remote solana node running version ({}).",
+            connection.get_version()?
+        );
+    }
+
+    let payer = get_payer()?;
+
+    // Commands that can't simulate must not silently broadcast instead
+    let dry_run = app_matches.is_present("dry_run")
+        || sub_matches.map_or(false, |matches| matches.is_present("dry_run"));
+    if dry_run
+        && !matches!(
+            sub_command,
+            "create-account" | "create-transaction" | "approve"
+        )
+    {
+        return Err(anyhow::anyhow!(
+            "--dry-run is not supported by {}",
+            sub_command
+        ));
+    }
+
+    let no_cache = app_matches.is_present("no_cache")
+        || sub_matches.map_or(false, |matches| matches.is_present("no_cache"));
+    // The cache only saves round trips, commands run without it when there
+    // is nowhere to keep it
+    let mut cache = if no_cache {
+        AccountCache::disabled()
+    } else {
+        match get_cache_path() {
+            Ok(path) => AccountCache::open(path, CACHE_TTL, &rpc_url, commitment),
+            Err(err) => {
+                eprintln!("Warning: {}, running without the account cache", err);
+                AccountCache::disabled()
+            }
+        }
+    };
+
+    let _ = match (sub_command, sub_matches) {
+        ("create-account", Some(arg_matches)) => {
+            let threshold =
+                value_of::<u32>(arg_matches, "threshold").ok_or(error::Error::InvalidThreshold)?;
+            let owners = pubkeys_of_multiple_signers(arg_matches, "owners")?
+                .ok_or(error::Error::InvalidOwners)?;
+
+            let max_pending =
+                value_of::<u32>(arg_matches, "max_pending").unwrap_or(MAX_TRANSACTIONS as u32);
+
+            validate_threshold(threshold, owners.len())?;
+            check_funding(&payer.pubkey(), &connection)?;
+
+            let signature = create_account(
+                &payer,
+                &payer,
+                threshold,
+                owners,
+                max_pending,
+                &connection,
+                dry_run,
+            )?;
+            let multisig = solana_multisig::get_associated_address(&payer.pubkey());
+            cache.invalidate(&[multisig]);
+
+            if output == OutputFormat::Json && !dry_run {
+                print_json(&SignatureView::new(&multisig, &signature))?;
+            }
+        }
+        ("create-accounts", Some(arg_matches)) => {
+            let file = value_of::<String>(arg_matches, "file")
+                .ok_or_else(|| error::Error::InvalidConfig("missing csv file".to_string()))?;
+            let rows = parse_accounts_csv(&std::fs::read_to_string(file)?)?;
+
+            let mut accounts = Vec::with_capacity(rows.len());
+            for row in rows {
+                let wallet = read_keypair_file(&row.wallet).map_err(|e| {
+                    error::Error::InvalidConfig(format!(
+                        "failed to read keypair file ({}): ({})",
+                        row.wallet, e
+                    ))
+                })?;
+                accounts.push((wallet, row.threshold, row.owners));
+            }
+
+            let mut views = Vec::with_capacity(accounts.len());
+            for (range, result) in create_accounts(&payer, &accounts, &connection) {
+                for (wallet, _, _) in &accounts[range] {
+                    let multisig = solana_multisig::get_associated_address(&wallet.pubkey());
+                    cache.invalidate(&[multisig]);
+                    match (output, &result) {
+                        (OutputFormat::Json, _) => {
+                            views.push(CreatedAccountView::new(&wallet.pubkey(), &result))
+                        }
+                        (OutputFormat::Text, Ok(_)) => {
+                            println!("{}: created {}", wallet.pubkey(), multisig)
+                        }
+                        (OutputFormat::Text, Err(e)) => {
+                            println!("{}: failed ({})", wallet.pubkey(), e)
+                        }
+                    }
+                }
+            }
+            if output == OutputFormat::Json {
+                print_json(&views)?;
+            }
+        }
+        ("show-account", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            // Fetched directly, the owner and balance are not cached
+            let (lamports, multisig_data) = fetch_multisig_account(&multisig, &connection)?
+                .ok_or(error::Error::AccountNotFound(multisig))?;
+
+            match output {
+                OutputFormat::Text => println!("{}", describe_account(&multisig_data, lamports)),
+                OutputFormat::Json => {
+                    print_json(&AccountView::new(&multisig, &multisig_data, lamports))?
+                }
+            }
+        }
+        ("my-multisigs", Some(arg_matches)) => {
+            let owner = Pubkey::from_str(
+                value_of::<String>(arg_matches, "owner")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let multisigs = find_multisigs_for_owner(&owner, &connection)?;
+            match output {
+                OutputFormat::Text => {
+                    for (address, multisig_data) in &multisigs {
+                        println!(
+                            "{}: {} of {} owners",
+                            address,
+                            multisig_data.threshold,
+                            multisig_data.owners.len()
+                        );
+                    }
+                }
+                OutputFormat::Json => print_json(
+                    &multisigs
+                        .iter()
+                        .map(|(address, _)| address.to_string())
+                        .collect::<Vec<_>>(),
+                )?,
+            }
+        }
+        ("create-transaction", Some(arg_matches)) => {
+            let recipient = Pubkey::from_str(
+                value_of::<String>(arg_matches, "recipient")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let amount = parse_amount(
+                arg_matches
+                    .value_of("amount")
+                    .ok_or(error::Error::InvalidAmount)?,
+                arg_matches.is_present("lamports"),
+            )?;
+
+            let memo = value_of::<String>(arg_matches, "memo").unwrap_or_default();
+            let not_before_slot = value_of::<u64>(arg_matches, "not_before_slot").unwrap_or(0);
+            let expires_at_slot = value_of::<u64>(arg_matches, "expires_at_slot").unwrap_or(0);
+            let deposit = match arg_matches.value_of("deposit") {
+                Some(deposit) => parse_amount(deposit, arg_matches.is_present("lamports"))?,
+                None => 0,
+            };
+
+            let (transaction, signature) = create_transaction(
+                &payer,
+                &payer,
+                &recipient,
+                amount,
+                memo,
+                not_before_slot,
+                expires_at_slot,
+                arg_matches.is_present("soft"),
+                arg_matches.is_present("approve"),
+                deposit,
+                &connection,
+                dry_run,
+            )?;
+            let multisig = solana_multisig::get_associated_address(&payer.pubkey());
+            cache.invalidate(&[multisig]);
+
+            if dry_run {
+                // Nothing was created to print or link to
+            } else if output == OutputFormat::Json {
+                print_json(&SignatureView::new(&transaction, &signature))?;
+            } else {
+                println!("Created transaction: {}", transaction);
+                println!("Multisig: {}", multisig);
+
+                if arg_matches.is_present("explorer") {
+                    let rpc_url = get_rpc_url()?;
+                    println!(
+                        "Transaction account: {}",
+                        explorer_url(&format!("address/{}", transaction), &rpc_url)
+                    );
+                    println!(
+                        "Signature: {}",
+                        explorer_url(&format!("tx/{}", signature), &rpc_url)
+                    );
+                }
+            }
+        }
+        ("approve", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            if let Some(transaction) = value_of::<String>(arg_matches, "transaction") {
+                let transaction = Pubkey::from_str(transaction.as_str())?;
+
+                let (_, multisig_data) = fetch_multisig_account(&multisig, &connection)?
+                    .ok_or(error::Error::AccountNotFound(multisig))?;
+                let transaction_data = fetch_transaction(&transaction, &connection)?;
+                check_can_approve(&multisig_data, &transaction_data, &payer.pubkey())?;
+
+                let signature =
+                    approve_transaction(&payer, &multisig, &transaction, &connection, dry_run)?;
+                cache.invalidate(&[multisig, transaction]);
+
+                if output == OutputFormat::Json && !dry_run {
+                    print_json(&SignatureView::new(&transaction, &signature))?;
+                }
+            } else {
+                let (_, multisig_data) = fetch_multisig_account(&multisig, &connection)?
+                    .ok_or(error::Error::AccountNotFound(multisig))?;
+
+                let mut need_to_approve = Vec::new();
+
+                for (pending_transaction, pending_transaction_data) in
+                    list_pending_transactions(&multisig, &connection)?
+                {
+                    if pending_transaction_data
+                        .signer_status(&multisig_data)
+                        .iter()
+                        .any(|(signer, is_signed)| *signer == payer.pubkey() && !is_signed)
+                    {
+                        need_to_approve.push((pending_transaction, pending_transaction_data));
+                    }
+                }
+
+                if arg_matches.is_present("all") {
+                    let mut approved = Vec::new();
+                    for (transaction, _) in need_to_approve {
+                        let signature = approve_transaction(
+                            &payer,
+                            &multisig,
+                            &transaction,
+                            &connection,
+                            dry_run,
+                        )?;
+                        cache.invalidate(&[multisig, transaction]);
+                        approved.push(SignatureView::new(&transaction, &signature));
+                    }
+
+                    if output == OutputFormat::Json && !dry_run {
+                        print_json(&approved)?;
+                    }
+                } else if output == OutputFormat::Json {
+                    print_json(
+                        &need_to_approve
+                            .iter()
+                            .map(|(address, transaction)| {
+                                TransactionView::new(address, transaction, &multisig_data)
+                            })
+                            .collect::<Vec<_>>(),
+                    )?;
+                } else {
+                    // Nothing is signed unless asked for explicitly
+                    for (address, transaction) in &need_to_approve {
+                        println!("{}", describe_transaction(address, transaction));
+                    }
+                    println!("Pass --transaction <TRANSACTION> or --all to approve");
+                }
+            }
+        }
+        ("approve-all", Some(arg_matches)) => {
+            let multisigs = match arg_matches.value_of("file") {
+                Some(file) => parse_multisig_list(&std::fs::read_to_string(file)?)?,
+                None => parse_multisig_list(arg_matches.value_of("multisigs").unwrap_or_default())?,
+            };
+
+            // A failing multisig doesn't stop the others
+            let mut views = Vec::with_capacity(multisigs.len());
+            for multisig in multisigs {
+                let result = approve_pending(
+                    &payer,
+                    &multisig,
+                    arg_matches.is_present("execute"),
+                    &connection,
+                );
+                cache.invalidate(&[multisig]);
+                if let Ok(summary) = &result {
+                    cache.invalidate(&summary.approved);
+                }
+                match (output, &result) {
+                    (OutputFormat::Json, _) => {
+                        views.push(ApprovalSummaryView::new(&multisig, &result))
+                    }
+                    (OutputFormat::Text, Ok(summary)) => println!(
+                        "{}: approved {}, executed {}, ready {}",
+                        multisig,
+                        summary.approved.len(),
+                        summary.executed,
+                        summary.ready
+                    ),
+                    (OutputFormat::Text, Err(e)) => println!("{}: failed ({})", multisig, e),
+                }
+            }
+            if output == OutputFormat::Json {
+                print_json(&views)?;
+            }
+        }
+        ("export-approval", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let transaction = Pubkey::from_str(
+                value_of::<String>(arg_matches, "transaction")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let file = value_of::<String>(arg_matches, "file")
+                .ok_or_else(|| error::Error::InvalidConfig("missing approval file".to_string()))?;
+
+            std::fs::write(
+                &file,
+                export_approval(&payer, &multisig, &transaction, &connection)?,
+            )?;
+            match output {
+                OutputFormat::Text => println!(
+                    "Approval written to {}, submit it before its blockhash expires",
+                    file
+                ),
+                OutputFormat::Json => print_json(&ApprovalFileView {
+                    account: transaction.to_string(),
+                    file,
+                })?,
+            }
+        }
+        ("submit-approval", Some(arg_matches)) => {
+            let file = value_of::<String>(arg_matches, "file")
+                .ok_or_else(|| error::Error::InvalidConfig("missing approval file".to_string()))?;
+
+            let (transaction, signature) = import_and_submit(&std::fs::read(file)?, &connection)?;
+            cache.invalidate(&[transaction]);
+
+            match output {
+                OutputFormat::Text => println!("Signature: {}", signature),
+                OutputFormat::Json => print_json(&SignatureView::new(&transaction, &signature))?,
+            }
+        }
+        ("execute", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let transaction = Pubkey::from_str(
+                value_of::<String>(arg_matches, "transaction")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let transaction_info = cache.get_or_fetch(&transaction, || {
+                fetch_account_data(&transaction, &connection)
+            })?;
+            let transaction_data = Transaction::unpack_unchecked(&transaction_info)?;
+
+            execute_transaction(
+                &payer,
+                &multisig,
+                &transaction,
+                &transaction_data,
+                &connection,
+            )?;
+            cache.invalidate(&[multisig, transaction]);
+        }
+        ("promote", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let transaction = Pubkey::from_str(
+                value_of::<String>(arg_matches, "transaction")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            promote_transaction(&payer, &multisig, &transaction, &connection)?;
+            cache.invalidate(&[multisig, transaction]);
+        }
+        ("revoke", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let transaction = Pubkey::from_str(
+                value_of::<String>(arg_matches, "transaction")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            revoke_transaction(&payer, &multisig, &transaction, &connection)?;
+            cache.invalidate(&[multisig, transaction]);
+        }
+        ("ready", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let multisig_info =
+                cache.get_or_fetch(&multisig, || fetch_account_data(&multisig, &connection))?;
+            let multisig_data = Account::unpack(&multisig_info)?;
+
+            for pending_transaction in &multisig_data.pending_transactions {
+                let pending_transaction_info = cache.get_or_fetch(pending_transaction, || {
+                    fetch_account_data(pending_transaction, &connection)
+                })?;
+                let pending_transaction_data =
+                    Transaction::unpack_unchecked(&pending_transaction_info)?;
+
+                if is_ready(&multisig_data, &pending_transaction_data) {
+                    println!(
+                        "{}",
+                        describe_transaction(pending_transaction, &pending_transaction_data)
+                    );
+                }
+            }
+        }
+        ("list-transactions", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let (_, multisig_info) = fetch_account_data(&multisig, &connection)?;
+            let multisig_data = Account::unpack(&multisig_info)?;
+
+            let transactions = list_pending_transactions(&multisig, &connection)?;
+
+            let warn_within = value_of::<u64>(arg_matches, "warn_within");
+            let slot = match warn_within {
+                Some(_) => connection.get_slot()?,
+                None => 0,
+            };
+            let views = transactions
+                .iter()
+                .map(|(address, transaction)| {
+                    let mut view = TransactionView::new(address, transaction, &multisig_data);
+                    view.expires_soon = warn_within
+                        .map_or(false, |seconds| expires_within(transaction, slot, seconds));
+                    view
+                })
+                .collect::<Vec<_>>();
+
+            if output == OutputFormat::Json {
+                print_json(&views)?;
+            } else {
+                for ((address, transaction), view) in transactions.iter().zip(views) {
+                    println!("{}", describe_transaction(address, transaction));
+                    println!("  recipient: {}", view.recipient);
+                    println!("  amount: {}", view.amount);
+                    println!("  created by: {}", view.creator);
+                    println!("  status: {}", view.status);
+                    println!("  signed by: {}", view.signed_by.join(", "));
+                    println!("  approvals: {} of {}", view.approvals, view.owners);
+                    if view.expires_soon {
+                        println!(
+                            "  warning: expires at slot {}, approve before it lapses",
+                            transaction.expires_at_slot
+                        );
+                    }
+                }
+            }
+        }
+        ("find-transaction", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let recipient = Pubkey::from_str(
+                value_of::<String>(arg_matches, "recipient")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let amount = parse_amount(
+                arg_matches
+                    .value_of("amount")
+                    .ok_or(error::Error::InvalidAmount)?,
+                arg_matches.is_present("lamports"),
+            )?;
+            let memo = value_of::<String>(arg_matches, "memo");
+
+            let multisig_info =
+                cache.get_or_fetch(&multisig, || fetch_account_data(&multisig, &connection))?;
+            let multisig_data = Account::unpack(&multisig_info)?;
+
+            let mut pending_transactions = Vec::new();
+            for pending_transaction in multisig_data.pending_transactions {
+                let pending_transaction_info = cache.get_or_fetch(&pending_transaction, || {
+                    fetch_account_data(&pending_transaction, &connection)
+                })?;
+                pending_transactions.push((
+                    pending_transaction,
+                    Transaction::unpack_unchecked(&pending_transaction_info)?,
+                ));
+            }
+
+            for (address, transaction) in
+                find_transactions(&pending_transactions, &recipient, amount, memo.as_deref())
+            {
+                println!("{}", describe_transaction(address, transaction));
+            }
+        }
+        ("reap-expired", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let destination = Pubkey::from_str(
+                value_of::<String>(arg_matches, "destination")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let reaped = reap_expired_transactions(&payer, &multisig, &destination, &connection)?;
+            cache.invalidate(&[multisig]);
+            cache.invalidate(&reaped);
+
+            for transaction in reaped {
+                println!("{}", transaction);
+            }
+        }
+        ("cancel-transaction", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let transaction = Pubkey::from_str(
+                value_of::<String>(arg_matches, "transaction")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let destination = Pubkey::from_str(
+                value_of::<String>(arg_matches, "destination")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let mut owners = Vec::new();
+            for path in arg_matches.values_of("owner").unwrap_or_default() {
+                owners.push(read_keypair_file(path).map_err(|e| {
+                    error::Error::InvalidConfig(format!(
+                        "failed to read keypair file ({}): ({})",
+                        path, e
+                    ))
+                })?);
+            }
+            let owners = owners.iter().collect::<Vec<_>>();
+
+            cancel_transaction(
+                &payer,
+                &multisig,
+                &transaction,
+                &destination,
+                &owners,
+                &connection,
+            )?;
+            cache.invalidate(&[multisig, transaction]);
+        }
+        ("cancel-all", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let mut owners = Vec::new();
+            for path in arg_matches.values_of("owner").unwrap_or_default() {
+                owners.push(read_keypair_file(path).map_err(|e| {
+                    error::Error::InvalidConfig(format!(
+                        "failed to read keypair file ({}): ({})",
+                        path, e
+                    ))
+                })?);
+            }
+            let owners = owners.iter().collect::<Vec<_>>();
+
+            // The rent of cancelled transactions goes back to the payer
+            let summary = cancel_pending(&payer, &multisig, &payer.pubkey(), &owners, &connection)?;
+            cache.invalidate(&[multisig]);
+            cache.invalidate(&summary.cancelled);
+
+            for transaction in &summary.cancelled {
+                println!("{}", transaction);
+            }
+            for transaction in &summary.rejected {
+                println!("{}: not cancelled, insufficient approvals", transaction);
+            }
+        }
+        ("set-reserve", Some(arg_matches)) => {
+            let amount = arg_matches
+                .value_of("amount")
+                .ok_or(error::Error::InvalidAmount)?;
+            // Unlike a transfer amount, zero is valid and lifts the reserve
+            let reserved_balance = if f64::from_str(amount) == Ok(0.0) {
+                0
+            } else {
+                parse_amount(amount, arg_matches.is_present("lamports"))?
+            };
+
+            set_reserve(&payer, &payer, reserved_balance, &connection)?;
+            cache.invalidate(&[solana_multisig::get_associated_address(&payer.pubkey())]);
+        }
+        ("close-account", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let destination = Pubkey::from_str(
+                value_of::<String>(arg_matches, "destination")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+
+            let mut owners = Vec::new();
+            for path in arg_matches.values_of("owner").unwrap_or_default() {
+                owners.push(read_keypair_file(path).map_err(|e| {
+                    error::Error::InvalidConfig(format!(
+                        "failed to read keypair file ({}): ({})",
+                        path, e
+                    ))
+                })?);
+            }
+            let owners = owners.iter().collect::<Vec<_>>();
+
+            close_account(&payer, &multisig, &destination, &owners, &connection)?;
+            cache.invalidate(&[multisig]);
+        }
+        ("deposit", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let amount = parse_amount(
+                arg_matches
+                    .value_of("amount")
+                    .ok_or(error::Error::InvalidAmount)?,
+                arg_matches.is_present("lamports"),
+            )?;
+
+            let signature = deposit(&payer, &multisig, amount, &connection)?;
+            cache.invalidate(&[multisig]);
+
+            match output {
+                OutputFormat::Text => println!("Signature: {}", signature),
+                OutputFormat::Json => print_json(&SignatureView::new(&multisig, &signature))?,
+            }
+        }
+        ("add-owner", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let owner = Pubkey::from_str(
+                value_of::<String>(arg_matches, "owner")
+                    .ok_or(error::Error::InvalidOwners)?
+                    .as_str(),
+            )?;
+
+            let owners = add_owner(&payer, &multisig, &owner, &connection)?;
+            cache.invalidate(&[multisig]);
+
+            println!("Owners:");
+            for owner in owners {
+                println!("  {}", owner);
+            }
+        }
+        ("remove-owner", Some(arg_matches)) => {
+            let multisig = Pubkey::from_str(
+                value_of::<String>(arg_matches, "multisig")
+                    .ok_or(error::Error::InvalidThreshold)?
+                    .as_str(),
+            )?;
+            let owner = Pubkey::from_str(
+                value_of::<String>(arg_matches, "owner")
+                    .ok_or(error::Error::InvalidOwners)?
+                    .as_str(),
+            )?;
+
+            match remove_owner(&payer, &multisig, &owner, &connection) {
+                Ok(owners) => {
+                    cache.invalidate(&[multisig]);
+
+                    println!("Owners:");
+                    for owner in owners {
+                        println!("  {}", owner);
+                    }
+                }
+                Err(err @ error::Error::ThresholdUnreachable(..)) => {
+                    eprintln!("Warning: {}, not sending", err);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        _ => {}
+    };
+
+    // Whatever the command sent already went through
+    if let Err(err) = cache.save() {
+        eprintln!("Warning: {}", err);
+    }
+
+    Ok(())
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn is_memo(string: String) -> Result<(), String> {
+    if string.len() > MAX_MEMO_LEN {
+        Err(format!("must be at most {} bytes", MAX_MEMO_LEN))
+    } else {
+        Ok(())
+    }
+}
+
+fn is_signers_number_valid(string: String) -> Result<(), String> {
+    let v = u8::from_str(&string).map_err(|e| e.to_string())? as usize;
+    if v < MIN_SIGNERS {
+        Err(format!("must be at least {}", MIN_SIGNERS))
+    } else if v > MAX_SIGNERS {
+        Err(format!("must be at most {}", MAX_SIGNERS))
+    } else {
+        Ok(())
+    }
+}
+
+fn is_max_pending_valid(string: String) -> Result<(), String> {
+    let v = u32::from_str(&string).map_err(|e| e.to_string())? as usize;
+    if v < 1 {
+        Err("must be at least 1".to_string())
+    } else if v > MAX_TRANSACTIONS {
+        Err(format!("must be at most {}", MAX_TRANSACTIONS))
     } else {
         Ok(())
     }
@@ -1,5 +1,17 @@
+use std::collections::HashMap;
+use std::io::{Stderr, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
 use yaml_rust::YamlLoader;
 
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::{sol_to_lamports, LAMPORTS_PER_SOL};
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::keypair::read_keypair_file;
 
@@ -53,3 +65,347 @@ pub fn get_payer() -> Result<Keypair> {
         Error::InvalidConfig(format!("failed to read keypair file ({}): ({})", path, e))
     })
 }
+
+/// Builds a Solana Explorer link for `path` (e.g. `address/<PUBKEY>` or `tx/<SIGNATURE>`),
+/// selecting the explorer cluster from the RPC URL.
+pub fn explorer_url(path: &str, rpc_url: &str) -> String {
+    let cluster = if rpc_url.contains("devnet") {
+        "?cluster=devnet".to_string()
+    } else if rpc_url.contains("testnet") {
+        "?cluster=testnet".to_string()
+    } else if rpc_url.contains("mainnet") {
+        String::new()
+    } else {
+        format!("?cluster=custom&customUrl={}", url_encode(rpc_url))
+    };
+
+    format!("https://explorer.solana.com/{}{}", path, cluster)
+}
+
+fn url_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// How commands print their results.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// Pretty-printed JSON, one document per command
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown output format ({})", s)),
+        }
+    }
+}
+
+/// Multisig account description read from a `wallet,threshold,owners...` CSV row,
+/// where `wallet` is a path to the wallet keypair file.
+#[derive(Debug, PartialEq)]
+pub struct AccountRow {
+    pub wallet: String,
+    pub threshold: u32,
+    pub owners: Vec<Pubkey>,
+}
+
+pub fn parse_accounts_csv(content: &str) -> Result<Vec<AccountRow>> {
+    let mut rows = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let row = index + 1;
+        let mut fields = line.split(',').map(str::trim);
+
+        let wallet = fields
+            .next()
+            .filter(|wallet| !wallet.is_empty())
+            .ok_or(Error::InvalidCsvRow(row))?
+            .to_string();
+        let threshold = fields
+            .next()
+            .and_then(|threshold| u32::from_str(threshold).ok())
+            .ok_or(Error::InvalidCsvRow(row))?;
+        let owners = fields
+            .map(Pubkey::from_str)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::InvalidCsvRow(row))?;
+
+        if owners.is_empty() {
+            return Err(Error::InvalidCsvRow(row));
+        }
+
+        rows.push(AccountRow {
+            wallet,
+            threshold,
+            owners,
+        });
+    }
+    Ok(rows)
+}
+
+/// Parses multisig addresses separated by commas or whitespace, as given
+/// to `approve-all --multisigs` or read from its `--file`.
+pub fn parse_multisig_list(content: &str) -> Result<Vec<Pubkey>> {
+    content
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|address| !address.is_empty())
+        .map(|address| {
+            Pubkey::from_str(address).map_err(|_| Error::InvalidMultisig(address.to_string()))
+        })
+        .collect()
+}
+
+pub fn validate_threshold(threshold: u32, owners: usize) -> Result<()> {
+    if threshold == 0 {
+        return Err(Error::InvalidThreshold);
+    }
+    if threshold as usize > owners {
+        return Err(Error::InvalidOwnersNumber);
+    }
+    Ok(())
+}
+
+/// Parses a positive transfer amount given in SOL, or in whole lamports
+/// if `lamports` is set, and returns it in lamports.
+pub fn parse_amount(amount: &str, lamports: bool) -> Result<u64> {
+    let amount = if lamports {
+        u64::from_str(amount).map_err(|_| Error::InvalidAmount)?
+    } else {
+        let sol = f64::from_str(amount).map_err(|_| Error::InvalidAmount)?;
+        // `sol_to_lamports` saturates instead of failing
+        if !sol.is_finite() || sol * LAMPORTS_PER_SOL as f64 >= u64::MAX as f64 {
+            return Err(Error::InvalidAmount);
+        }
+        sol_to_lamports(sol.max(0.0))
+    };
+
+    // Also rejects SOL amounts below one lamport
+    if amount == 0 {
+        return Err(Error::InvalidAmount);
+    }
+    Ok(amount)
+}
+
+/// How long a cached account snapshot is trusted.
+pub const CACHE_TTL: Duration = Duration::from_secs(30);
+
+pub fn get_cache_path() -> Result<PathBuf> {
+    match home::home_dir() {
+        Some(mut path) => {
+            path.push(".cache/solana-multisig/accounts.json");
+            Ok(path)
+        }
+        None => Err(Error::CacheWriteError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "failed to locate homedir and thus can not locate account cache",
+        ))),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    slot: u64,
+    fetched_at: u64,
+    data: Vec<u8>,
+}
+
+/// On-disk cache of account data, keyed by cluster URL, commitment and
+/// pubkey and remembering the slot the snapshot was taken at. Entries older
+/// than the TTL are re-fetched and commands that write to an account must
+/// `invalidate` it.
+pub struct AccountCache {
+    path: Option<PathBuf>,
+    ttl: Duration,
+    cluster: String,
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl AccountCache {
+    /// Loads the cache stored at `path`, starting empty if it is missing or
+    /// unreadable. Only snapshots of `rpc_url` at `commitment` are used.
+    pub fn open(path: PathBuf, ttl: Duration, rpc_url: &str, commitment: CommitmentConfig) -> Self {
+        let entries = std::fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path: Some(path),
+            ttl,
+            cluster: format!("{} {:?}", rpc_url, commitment.commitment),
+            entries,
+        }
+    }
+
+    /// A cache that never stores anything, used by `--no-cache`.
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            ttl: Duration::ZERO,
+            cluster: String::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn key(&self, pubkey: &Pubkey) -> String {
+        format!("{} {}", self.cluster, pubkey)
+    }
+
+    /// Returns the cached data of `pubkey` if it is younger than the TTL,
+    /// otherwise calls `fetch` for `(slot, data)` and caches the result. A
+    /// node behind the cached slot doesn't replace the newer snapshot.
+    pub fn get_or_fetch<F>(&mut self, pubkey: &Pubkey, fetch: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce() -> Result<(u64, Vec<u8>)>,
+    {
+        let now = unix_timestamp();
+        let key = self.key(pubkey);
+
+        if let Some(entry) = self.entries.get(&key) {
+            if now.saturating_sub(entry.fetched_at) < self.ttl.as_secs() {
+                return Ok(entry.data.clone());
+            }
+        }
+
+        let (slot, data) = fetch()?;
+        if let Some(entry) = self.entries.get(&key) {
+            if entry.slot > slot {
+                return Ok(entry.data.clone());
+            }
+        }
+        if self.path.is_some() {
+            self.entries.insert(
+                key,
+                CacheEntry {
+                    slot,
+                    fetched_at: now,
+                    data: data.clone(),
+                },
+            );
+        }
+        Ok(data)
+    }
+
+    pub fn invalidate(&mut self, pubkeys: &[Pubkey]) {
+        for pubkey in pubkeys {
+            let key = self.key(pubkey);
+            self.entries.remove(&key);
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(Error::CacheWriteError)?;
+        }
+        let content = serde_json::to_vec(&self.entries).expect("serialize");
+        std::fs::write(path, content).map_err(Error::CacheWriteError)
+    }
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+const SPINNER_FRAMES: &[char] = &['|', '/', '-', '\\'];
+
+/// Single status line redrawn in place while a command waits on the
+/// cluster. Draws nothing unless the output is interactive, so piped
+/// output stays clean.
+pub struct Spinner<W: Write> {
+    out: W,
+    enabled: bool,
+    frame: usize,
+}
+
+static SPINNER_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Keeps `Spinner::stderr` from drawing for the rest of the process, for
+/// output meant for other programs.
+pub fn disable_spinner() {
+    SPINNER_DISABLED.store(true, Ordering::Relaxed);
+}
+
+impl Spinner<Stderr> {
+    /// Spinner on stderr, enabled only if stderr is a terminal and
+    /// `disable_spinner` wasn't called.
+    pub fn stderr() -> Self {
+        Self::new(
+            std::io::stderr(),
+            console::user_attended_stderr() && !SPINNER_DISABLED.load(Ordering::Relaxed),
+        )
+    }
+}
+
+impl<W: Write> Spinner<W> {
+    pub fn new(out: W, interactive: bool) -> Self {
+        Self {
+            out,
+            enabled: interactive,
+            frame: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Replaces the status line, advancing the spinner.
+    pub fn set_message(&mut self, message: &str) {
+        let frame = SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()];
+        self.frame += 1;
+        self.draw(&format!("{} {}", frame, message), false);
+    }
+
+    /// Replaces the status line with a final message and ends it.
+    pub fn finish(&mut self, message: &str) {
+        self.draw(message, true);
+    }
+
+    /// Removes the status line.
+    pub fn clear(&mut self) {
+        self.draw("", false);
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    // Status output is best effort, write errors are ignored
+    fn draw(&mut self, line: &str, end: bool) {
+        if !self.enabled {
+            return;
+        }
+        let _ = write!(self.out, "\r\x1b[2K{}", line);
+        if end {
+            let _ = writeln!(self.out);
+        }
+        let _ = self.out.flush();
+    }
+}
@@ -0,0 +1,903 @@
+use serde_json::json;
+use solana_client::client_error::ClientError;
+use solana_client::mock_sender::Mocks;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_multisig::{
+    Account, MultisigError, MultisigInstruction, ProposerPolicy, Transaction, TransactionStatus,
+    MAX_SIGNERS, MAX_TRANSACTIONS,
+};
+use solana_program::program_pack::Pack;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::TransactionError;
+
+use solana_multisig_cli::client::*;
+use solana_multisig_cli::error::Error;
+
+#[test]
+fn test_is_ready() {
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let multisig = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: owners.clone(),
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+
+    let transaction = |signed: &[bool]| Transaction {
+        multisig: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        amount: 1,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        memo: String::new(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 0,
+        signers: owners.iter().copied().zip(signed.iter().copied()).collect(),
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+
+    assert!(is_ready(&multisig, &transaction(&[true, true])));
+    assert!(!is_ready(&multisig, &transaction(&[true, false])));
+}
+
+#[test]
+fn test_find_transactions() {
+    let multisig = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let transaction = |recipient: Pubkey, amount: u64, memo: &str| Transaction {
+        multisig,
+        recipient,
+        amount,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        memo: memo.to_string(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 0,
+        signers: vec![],
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+
+    let expected = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let transactions = vec![
+        (Pubkey::new_unique(), transaction(recipient, 5, "")),
+        (expected, transaction(recipient, 10, "rent")),
+        (other, transaction(recipient, 10, "")),
+        (
+            Pubkey::new_unique(),
+            transaction(Pubkey::new_unique(), 10, "rent"),
+        ),
+    ];
+
+    let addresses = |memo| {
+        find_transactions(&transactions, &recipient, 10, memo)
+            .into_iter()
+            .map(|(address, _)| *address)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(addresses(None), vec![expected, other]);
+    assert_eq!(addresses(Some("rent")), vec![expected]);
+    assert!(find_transactions(&transactions, &recipient, 7, None).is_empty());
+}
+
+#[test]
+fn test_create_account_packet_size() {
+    let payer = Pubkey::new_unique();
+    let wallet = Pubkey::new_unique();
+    let create = |owners: usize| {
+        solana_sdk::transaction::Transaction::new_with_payer(
+            &[solana_multisig::create_associated_account(
+                &payer,
+                &wallet,
+                MultisigInstruction::CreateAccount {
+                    threshold: owners as u32,
+                    owners: (0..owners).map(|_| Pubkey::new_unique()).collect(),
+                    max_pending: MAX_TRANSACTIONS as u32,
+                }
+                .pack()
+                .expect("pack"),
+            )],
+            Some(&payer),
+        )
+    };
+
+    assert!(transaction_size(&create(16)) <= PACKET_DATA_SIZE);
+    // The remaining owners of a full multisig are added with `add-owner`
+    assert!(transaction_size(&create(MAX_SIGNERS)) > PACKET_DATA_SIZE);
+}
+
+#[test]
+fn test_batch_instructions() {
+    let payer = Pubkey::new_unique();
+    let create = |owners: usize| {
+        solana_multisig::create_associated_account(
+            &payer,
+            &Pubkey::new_unique(),
+            MultisigInstruction::CreateAccount {
+                threshold: 1,
+                owners: (0..owners).map(|_| Pubkey::new_unique()).collect(),
+                max_pending: MAX_TRANSACTIONS as u32,
+            }
+            .pack()
+            .expect("pack"),
+        )
+    };
+
+    // Three small multisigs fit into a single transaction
+    let instructions = (0..3).map(|_| create(1)).collect::<Vec<_>>();
+    assert_eq!(batch_instructions(&payer, &instructions), vec![0..3]);
+
+    // Large multisigs are split, every batch fits into a transaction
+    let instructions = (0..6).map(|_| create(16)).collect::<Vec<_>>();
+    let batches = batch_instructions(&payer, &instructions);
+    assert!(batches.len() > 1);
+    assert_eq!(batches.first().unwrap().start, 0);
+    assert_eq!(batches.last().unwrap().end, instructions.len());
+    for batch in batches {
+        let transaction = solana_sdk::transaction::Transaction::new_with_payer(
+            &instructions[batch],
+            Some(&payer),
+        );
+        assert!(transaction_size(&transaction) <= PACKET_DATA_SIZE);
+    }
+}
+
+#[test]
+fn test_describe_account() {
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let multisig = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: owners.clone(),
+        pending_transactions: vec![Pubkey::new_unique()],
+        frozen_amount: 300,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 42,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+
+    assert_eq!(
+        describe_account(&multisig, 1_000),
+        format!(
+            "threshold: 2\nowners: 2\n  {}\n  {}\npending transactions: 1\nfrozen amount: 300\nlast updated slot: 42\nbalance: 1000",
+            owners[0], owners[1]
+        )
+    );
+}
+
+#[test]
+fn test_account_display() {
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let multisig = Account {
+        is_initialized: true,
+        threshold: 3,
+        owners: owners.clone(),
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![2, 1],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: true,
+        last_updated_slot: 7,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+
+    assert_eq!(
+        multisig.to_string(),
+        format!(
+            "threshold: 3\nowners: 2\n  {} (weight 2)\n  {} (weight 1)\npending transactions: 0\nfrozen amount: 0\nlast updated slot: 7\nfrozen: no transactions can be created or executed",
+            owners[0], owners[1]
+        )
+    );
+}
+
+#[test]
+fn test_first_healthy_connection() {
+    // A "fails" mock answers every request with null, which `getHealth` rejects
+    let unhealthy = || {
+        (
+            "unhealthy".to_string(),
+            RpcClient::new_mock("fails".to_string()),
+        )
+    };
+    let healthy = || {
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetHealth, json!("ok"));
+        (
+            "healthy".to_string(),
+            RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks),
+        )
+    };
+
+    let connection = first_healthy_connection(vec![unhealthy(), healthy()]).expect("healthy");
+    assert!(connection.get_slot().is_ok());
+
+    assert!(matches!(
+        first_healthy_connection(vec![unhealthy(), unhealthy()]),
+        Err(Error::NoHealthyNode(tried)) if tried == "unhealthy, unhealthy"
+    ));
+}
+
+#[test]
+fn test_establish_connection_with_url() {
+    let connection =
+        establish_connection(Some("http://localhost:8899"), CommitmentConfig::finalized())
+            .expect("connection");
+    assert_eq!(connection.commitment(), CommitmentConfig::finalized());
+}
+
+#[test]
+fn test_fetch_multisig() {
+    let connection = |value| {
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetAccountInfo,
+            json!({ "context": { "slot": 1 }, "value": value }),
+        );
+        RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks)
+    };
+    let account = |owner: Pubkey, data: &[u8]| {
+        json!({
+            "lamports": 1_000,
+            "data": [solana_sdk::bs58::encode(data).into_string(), "base58"],
+            "owner": owner.to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+        })
+    };
+
+    let wallet = Pubkey::new_unique();
+    let multisig = Account {
+        is_initialized: true,
+        threshold: 1,
+        owners: vec![Pubkey::new_unique()],
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+    let owners = multisig.owners.clone();
+    let mut data = vec![0; Account::LEN];
+    Account::pack(multisig, &mut data).expect("pack");
+
+    let fetched = fetch_multisig(&wallet, &connection(account(solana_multisig::id(), &data)))
+        .expect("fetch")
+        .expect("multisig not none");
+    assert_eq!(fetched.threshold, 1);
+    assert_eq!(fetched.owners, owners);
+
+    assert!(matches!(
+        fetch_multisig(&wallet, &connection(json!(null))),
+        Ok(None)
+    ));
+
+    let address = solana_multisig::get_associated_address(&wallet);
+    assert!(matches!(
+        fetch_multisig(&wallet, &connection(account(Pubkey::new_unique(), &data))),
+        Err(Error::InvalidAccountOwner(owner_address)) if owner_address == address
+    ));
+    assert!(matches!(
+        fetch_multisig(&wallet, &connection(account(solana_multisig::id(), &[0; 8]))),
+        Err(Error::AccountDecode(decoded_address)) if decoded_address == address
+    ));
+}
+
+#[test]
+fn test_json_views() {
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let multisig = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: owners.clone(),
+        pending_transactions: vec![transaction_address],
+        frozen_amount: 300,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+
+    assert_eq!(
+        serde_json::to_value(AccountView::new(&address, &multisig, 1_000)).expect("serialize"),
+        json!({
+            "address": address.to_string(),
+            "threshold": 2,
+            "owners": [owners[0].to_string(), owners[1].to_string()],
+            "owner_weights": [],
+            "pending_transactions": [transaction_address.to_string()],
+            "frozen_amount": 300,
+            "balance": 1_000,
+            "is_frozen": false,
+            "last_updated_slot": 0,
+        })
+    );
+
+    let transaction = Transaction {
+        multisig: address,
+        recipient,
+        amount: 300,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        signers: vec![(owners[0], false), (owners[1], true)],
+        memo: "rent".to_string(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 1,
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+
+    assert_eq!(
+        serde_json::to_value(TransactionView::new(
+            &transaction_address,
+            &transaction,
+            &multisig
+        ))
+        .expect("serialize"),
+        json!({
+            "address": transaction_address.to_string(),
+            "recipient": recipient.to_string(),
+            "amount": 300,
+            "memo": "rent",
+            "creator": Pubkey::default().to_string(),
+            "status": "pending",
+            "signed_by": [owners[1].to_string()],
+            "approvals": 1,
+            "owners": 2,
+            "expires_soon": false,
+        })
+    );
+}
+
+#[test]
+fn test_expires_within() {
+    let transaction = |expires_at_slot| solana_multisig::Transaction {
+        multisig: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        amount: 300,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        signers: vec![],
+        memo: String::new(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 1,
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+
+    // A minute is 150 slots at the nominal slot time
+    assert!(expires_within(&transaction(1_100), 1_000, 60));
+    assert!(expires_within(&transaction(1_150), 1_000, 60));
+    assert!(!expires_within(&transaction(1_151), 1_000, 60));
+    assert!(!expires_within(&transaction(10_000), 1_000, 60));
+
+    // Nothing to warn about without an expiry or once it passed
+    assert!(!expires_within(&transaction(0), 1_000, 60));
+    assert!(!expires_within(&transaction(1_000), 1_000, 60));
+}
+
+#[test]
+fn test_send_confirmed() {
+    let payer = Keypair::new();
+    let transaction = || {
+        solana_sdk::transaction::Transaction::new_with_payer(
+            &[solana_sdk::system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                1_000,
+            )],
+            Some(&payer.pubkey()),
+        )
+    };
+
+    let connection = RpcClient::new_mock("succeeds".to_string());
+    let signature = send_confirmed(transaction(), &[&payer], &connection).expect("send");
+    assert_ne!(signature, Signature::default());
+
+    // Failed transactions are not retried
+    let connection = RpcClient::new_mock("instruction_error".to_string());
+    assert!(matches!(
+        send_confirmed(transaction(), &[&payer], &connection),
+        Err(Error::ClientError(e)) if e.get_transaction_error().is_some()
+    ));
+}
+
+#[test]
+fn test_approval_bundle_round_trip() {
+    let owner = Keypair::new();
+    let multisig = Pubkey::new_unique();
+    let transaction = Pubkey::new_unique();
+
+    let connection = RpcClient::new_mock("succeeds".to_string());
+    let bundle =
+        export_approval(&owner, &multisig, &transaction, &connection).expect("export_approval");
+
+    let approval = decode_approval(&bundle).expect("decode_approval");
+    assert_eq!(approval.message.account_keys[0], owner.pubkey());
+    assert!(approval.message.account_keys.contains(&multisig));
+    assert!(approval.message.account_keys.contains(&transaction));
+
+    let (approved, signature) = import_and_submit(&bundle, &connection).expect("import_and_submit");
+    assert_eq!(approved, transaction);
+    assert_eq!(signature, approval.signatures[0]);
+
+    // Anything but a signed approval is refused
+    let mut unsigned = approval.clone();
+    unsigned.signatures[0] = Signature::default();
+    let transfer = solana_sdk::transaction::Transaction::new_signed_with_payer(
+        &[solana_sdk::system_instruction::transfer(
+            &owner.pubkey(),
+            &Pubkey::new_unique(),
+            1_000,
+        )],
+        Some(&owner.pubkey()),
+        &[&owner],
+        approval.message.recent_blockhash,
+    );
+    for bundle in [
+        bincode::serialize(&unsigned).expect("serialize"),
+        bincode::serialize(&transfer).expect("serialize"),
+        bundle[..bundle.len() - 1].to_vec(),
+    ] {
+        assert!(matches!(
+            decode_approval(&bundle),
+            Err(Error::InvalidApprovalBundle(_))
+        ));
+    }
+}
+
+#[test]
+fn test_send_or_simulate() {
+    let payer = Keypair::new();
+    let transaction = || {
+        solana_sdk::transaction::Transaction::new_with_payer(
+            &[solana_sdk::system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                1_000,
+            )],
+            Some(&payer.pubkey()),
+        )
+    };
+
+    let connection = RpcClient::new_mock("succeeds".to_string());
+    let signature =
+        send_or_simulate(transaction(), &[&payer], &connection, true).expect("simulate");
+    assert_ne!(signature, Signature::default());
+
+    // A failed simulation is reported as its transaction error
+    let mut mocks = Mocks::new();
+    mocks.insert(
+        RpcRequest::SimulateTransaction,
+        json!({
+            "context": { "slot": 1 },
+            "value": {
+                "err": "AccountNotFound",
+                "logs": ["Program log: Instruction: ApproveTransaction"],
+                "accounts": null,
+                "unitsConsumed": 1_200,
+            },
+        }),
+    );
+    let connection = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+    assert!(matches!(
+        send_or_simulate(transaction(), &[&payer], &connection, true),
+        Err(Error::ClientError(e))
+            if e.get_transaction_error() == Some(TransactionError::AccountNotFound)
+    ));
+}
+
+#[test]
+fn test_describe_simulation() {
+    let result = RpcSimulateTransactionResult {
+        err: None,
+        logs: Some(vec![
+            "Program log: Instruction: CreateTransaction".to_string(),
+            "Program consumed 1200 compute units".to_string(),
+        ]),
+        accounts: None,
+        units_consumed: Some(1_200),
+    };
+
+    assert_eq!(
+        describe_simulation(&result),
+        "Simulation logs:\n  Program log: Instruction: CreateTransaction\n  Program consumed 1200 compute units\nCompute units consumed: 1200"
+    );
+}
+
+#[test]
+fn test_check_funding() {
+    // The mock rent is 20 lamports and every balance 50
+    let connection = RpcClient::new_mock("succeeds".to_string());
+    assert_eq!(
+        minimum_funding(&connection).expect("funding"),
+        20 + FEE_BUFFER
+    );
+    assert!(matches!(
+        check_funding(&Pubkey::new_unique(), &connection),
+        Err(Error::InsufficientFunds { needed, have: 50 }) if needed == 20 + FEE_BUFFER
+    ));
+
+    let mut mocks = Mocks::new();
+    mocks.insert(
+        RpcRequest::GetBalance,
+        json!({ "context": { "slot": 1 }, "value": 20 + FEE_BUFFER }),
+    );
+    let connection = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+    assert!(check_funding(&Pubkey::new_unique(), &connection).is_ok());
+}
+
+#[test]
+fn test_check_can_approve() {
+    let owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let multisig = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: owners.to_vec(),
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+    let transaction = Transaction {
+        multisig: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        amount: 1_000,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        signers: vec![(owners[0], true), (owners[1], false)],
+        memo: String::new(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 1,
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+
+    assert!(check_can_approve(&multisig, &transaction, &owners[1]).is_ok());
+    assert!(matches!(
+        check_can_approve(&multisig, &transaction, &owners[0]),
+        Err(Error::AlreadyApproved(owner)) if owner == owners[0]
+    ));
+
+    let outsider = Pubkey::new_unique();
+    assert!(matches!(
+        check_can_approve(&multisig, &transaction, &outsider),
+        Err(Error::NotTransactionOwner(owner)) if owner == outsider
+    ));
+
+    // The transaction account is decoded as is
+    let mut data = vec![0; Transaction::LEN];
+    Transaction::pack(transaction, &mut data).expect("pack");
+    let mut mocks = Mocks::new();
+    mocks.insert(
+        RpcRequest::GetAccountInfo,
+        json!({
+            "context": { "slot": 1 },
+            "value": {
+                "lamports": 1_000,
+                "data": [solana_sdk::bs58::encode(&data).into_string(), "base58"],
+                "owner": solana_multisig::id().to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+            },
+        }),
+    );
+    let connection = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+    let fetched = fetch_transaction(&Pubkey::new_unique(), &connection).expect("fetch");
+    assert_eq!(fetched.signers, vec![(owners[0], true), (owners[1], false)]);
+}
+
+#[test]
+fn test_owner_changes() {
+    let owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let multisig = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: owners.to_vec(),
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+    let outsider = Pubkey::new_unique();
+
+    assert_eq!(
+        owners_after_add(&multisig, &owners[0], &outsider).expect("add"),
+        vec![owners[0], owners[1], outsider]
+    );
+    assert!(matches!(
+        owners_after_add(&multisig, &owners[0], &owners[1]),
+        Err(Error::DuplicateOwner(owner)) if owner == owners[1]
+    ));
+    assert!(matches!(
+        owners_after_add(&multisig, &outsider, &Pubkey::new_unique()),
+        Err(Error::NotMultisigOwner(signer)) if signer == outsider
+    ));
+
+    // Two owners can't reach a threshold of two with one removed
+    assert!(matches!(
+        owners_after_removal(&multisig, &owners[0], &owners[1]),
+        Err(Error::ThresholdUnreachable(1, 2))
+    ));
+    assert!(matches!(
+        owners_after_removal(&multisig, &owners[0], &outsider),
+        Err(Error::NotMultisigOwner(owner)) if owner == outsider
+    ));
+
+    let multisig = Account {
+        threshold: 1,
+        ..multisig
+    };
+    assert_eq!(
+        owners_after_removal(&multisig, &owners[0], &owners[1]).expect("remove"),
+        vec![owners[0]]
+    );
+}
+
+#[test]
+fn test_multisigs_with_owner() {
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let pending_transaction = Pubkey::new_unique();
+    let multisig = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: owners.clone(),
+        pending_transactions: vec![pending_transaction],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+    let mut data = vec![0; Account::LEN];
+    Account::pack(multisig, &mut data).expect("pack");
+
+    // Owners are packed at fixed slots
+    let owners_offset = Account::owners_offset(Account::LEN).expect("known layout");
+    for (slot, owner) in owners.iter().enumerate() {
+        let offset = owners_offset + slot * 32;
+        assert_eq!(&data[offset..offset + 32], owner.as_ref());
+    }
+
+    let address = Pubkey::new_unique();
+    let account = solana_sdk::account::Account {
+        lamports: 1,
+        data,
+        owner: solana_multisig::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    let accounts = vec![(address, account.clone()), (address, account)];
+
+    // Returned twice, listed once
+    let multisigs = multisigs_with_owner(&owners[1], accounts.clone());
+    assert_eq!(multisigs.len(), 1);
+    assert_eq!(multisigs[0].0, address);
+    assert_eq!(multisigs[0].1.owners, owners);
+    // Pending transactions share the slots but aren't owners
+    assert!(multisigs_with_owner(&pending_transaction, accounts).is_empty());
+}
+
+#[test]
+fn test_address_view() {
+    let wallet = Pubkey::new_unique();
+    let view = AddressView::new(&wallet);
+
+    assert_eq!(view.wallet, wallet.to_string());
+    assert_eq!(
+        view.address,
+        solana_multisig::get_associated_address(&wallet).to_string()
+    );
+    assert_eq!(
+        Pubkey::create_program_address(
+            &[&wallet.to_bytes(), &[view.bump_seed]],
+            &solana_multisig::id()
+        )
+        .expect("valid seeds")
+        .to_string(),
+        view.address
+    );
+}
+
+#[test]
+fn test_created_account_view() {
+    let wallet = Pubkey::new_unique();
+    let address = solana_multisig::get_associated_address(&wallet).to_string();
+    let signature = Signature::new(&[1; 64]);
+
+    assert_eq!(
+        serde_json::to_value(CreatedAccountView::new(&wallet, &Ok(signature))).expect("serialize"),
+        json!({
+            "wallet": wallet.to_string(),
+            "account": address,
+            "signature": signature.to_string(),
+            "error": null,
+        })
+    );
+
+    let error = Error::AccountNotFound(wallet);
+    let message = error.to_string();
+    assert_eq!(
+        serde_json::to_value(CreatedAccountView::new(&wallet, &Err(error))).expect("serialize"),
+        json!({
+            "wallet": wallet.to_string(),
+            "account": address,
+            "signature": null,
+            "error": message,
+        })
+    );
+}
+
+#[test]
+fn test_is_missing_signature() {
+    let error = Error::ClientError(ClientError::from(TransactionError::InstructionError(
+        0,
+        InstructionError::MissingRequiredSignature,
+    )));
+    assert!(is_missing_signature(&error));
+
+    let error = Error::ClientError(ClientError::from(TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(MultisigError::TransactionExpired as u32),
+    )));
+    assert!(!is_missing_signature(&error));
+    assert!(!is_missing_signature(&Error::InvalidThreshold));
+}
+
+#[test]
+fn test_approval_summary_view() {
+    let multisig = Pubkey::new_unique();
+    let transaction = Pubkey::new_unique();
+    let summary = ApprovalSummary {
+        approved: vec![transaction],
+        ready: 1,
+        executed: 0,
+    };
+
+    assert_eq!(
+        serde_json::to_value(ApprovalSummaryView::new(&multisig, &Ok(summary))).expect("serialize"),
+        json!({
+            "account": multisig.to_string(),
+            "approved": [transaction.to_string()],
+            "executed": 0,
+            "ready": 1,
+            "error": null,
+        })
+    );
+
+    let error = Error::AccountNotFound(multisig);
+    let message = error.to_string();
+    assert_eq!(
+        serde_json::to_value(ApprovalSummaryView::new(&multisig, &Err(error))).expect("serialize"),
+        json!({
+            "account": multisig.to_string(),
+            "approved": [],
+            "executed": 0,
+            "ready": 0,
+            "error": message,
+        })
+    );
+}
@@ -0,0 +1,249 @@
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use solana_multisig_cli::error::Error;
+use solana_multisig_cli::utils::*;
+
+#[test]
+fn test_explorer_url() {
+    assert_eq!(
+        explorer_url("tx/abc", "https://api.devnet.solana.com"),
+        "https://explorer.solana.com/tx/abc?cluster=devnet"
+    );
+    assert_eq!(
+        explorer_url("tx/abc", "https://api.mainnet-beta.solana.com"),
+        "https://explorer.solana.com/tx/abc"
+    );
+    assert_eq!(
+        explorer_url("address/abc", "http://localhost:8899"),
+        "https://explorer.solana.com/address/abc?cluster=custom&customUrl=http%3A%2F%2Flocalhost%3A8899"
+    );
+}
+
+#[test]
+fn test_parse_accounts_csv() {
+    let owner = Pubkey::new_unique();
+    let csv = format!("wallet1.json,1,{0}\n\nwallet2.json, 2, {0}, {0}\n", owner);
+
+    let rows = parse_accounts_csv(&csv).expect("parse");
+    assert_eq!(
+        rows,
+        vec![
+            AccountRow {
+                wallet: "wallet1.json".to_string(),
+                threshold: 1,
+                owners: vec![owner],
+            },
+            AccountRow {
+                wallet: "wallet2.json".to_string(),
+                threshold: 2,
+                owners: vec![owner, owner],
+            },
+        ]
+    );
+
+    assert!(parse_accounts_csv("wallet.json,1,not-a-pubkey").is_err());
+    assert!(parse_accounts_csv("wallet.json,1").is_err());
+}
+
+#[test]
+fn test_validate_threshold() {
+    assert!(validate_threshold(1, 1).is_ok());
+    assert!(validate_threshold(2, 3).is_ok());
+    assert!(validate_threshold(3, 3).is_ok());
+
+    assert!(matches!(
+        validate_threshold(0, 3),
+        Err(solana_multisig_cli::error::Error::InvalidThreshold)
+    ));
+    assert!(matches!(
+        validate_threshold(4, 3),
+        Err(solana_multisig_cli::error::Error::InvalidOwnersNumber)
+    ));
+}
+
+#[test]
+fn test_account_cache_hit_within_ttl() {
+    let path = std::env::temp_dir().join(format!("multisig-cache-{}.json", Pubkey::new_unique()));
+    let pubkey = Pubkey::new_unique();
+    let fetches = std::cell::Cell::new(0);
+    let fetch = || {
+        fetches.set(fetches.get() + 1);
+        Ok((1, vec![1, 2, 3]))
+    };
+
+    let mut cache = AccountCache::open(
+        path.clone(),
+        std::time::Duration::from_secs(60),
+        "http://localhost:8899",
+        CommitmentConfig::confirmed(),
+    );
+    assert_eq!(cache.get_or_fetch(&pubkey, fetch).unwrap(), vec![1, 2, 3]);
+    assert_eq!(cache.get_or_fetch(&pubkey, fetch).unwrap(), vec![1, 2, 3]);
+    assert_eq!(fetches.get(), 1);
+
+    // Snapshots survive between commands
+    cache.save().unwrap();
+    let mut cache = AccountCache::open(
+        path.clone(),
+        std::time::Duration::from_secs(60),
+        "http://localhost:8899",
+        CommitmentConfig::confirmed(),
+    );
+    cache.get_or_fetch(&pubkey, fetch).unwrap();
+    assert_eq!(fetches.get(), 1);
+
+    // Writes drop the snapshot
+    cache.invalidate(&[pubkey]);
+    cache.get_or_fetch(&pubkey, fetch).unwrap();
+    assert_eq!(fetches.get(), 2);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_account_cache_per_cluster() {
+    let path = std::env::temp_dir().join(format!("multisig-cache-{}.json", Pubkey::new_unique()));
+    let pubkey = Pubkey::new_unique();
+    let ttl = std::time::Duration::from_secs(60);
+
+    let mut cache = AccountCache::open(
+        path.clone(),
+        ttl,
+        "https://api.devnet.solana.com",
+        CommitmentConfig::confirmed(),
+    );
+    cache.get_or_fetch(&pubkey, || Ok((1, vec![1]))).unwrap();
+    cache.save().unwrap();
+
+    // Another cluster or commitment never sees the snapshot
+    for (rpc_url, commitment) in [
+        (
+            "https://api.mainnet-beta.solana.com",
+            CommitmentConfig::confirmed(),
+        ),
+        (
+            "https://api.devnet.solana.com",
+            CommitmentConfig::finalized(),
+        ),
+    ] {
+        let mut cache = AccountCache::open(path.clone(), ttl, rpc_url, commitment);
+        assert_eq!(
+            cache.get_or_fetch(&pubkey, || Ok((1, vec![2]))).unwrap(),
+            vec![2]
+        );
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_account_cache_keeps_newer_slot() {
+    let path = std::env::temp_dir().join(format!("multisig-cache-{}.json", Pubkey::new_unique()));
+    let pubkey = Pubkey::new_unique();
+
+    // Every entry is expired right away
+    let mut cache = AccountCache::open(
+        path,
+        std::time::Duration::ZERO,
+        "http://localhost:8899",
+        CommitmentConfig::confirmed(),
+    );
+    cache.get_or_fetch(&pubkey, || Ok((10, vec![1]))).unwrap();
+
+    // A node lagging behind the snapshot doesn't roll it back
+    assert_eq!(
+        cache.get_or_fetch(&pubkey, || Ok((9, vec![2]))).unwrap(),
+        vec![1]
+    );
+    assert_eq!(
+        cache.get_or_fetch(&pubkey, || Ok((11, vec![3]))).unwrap(),
+        vec![3]
+    );
+}
+
+#[test]
+fn test_account_cache_disabled() {
+    let pubkey = Pubkey::new_unique();
+    let fetches = std::cell::Cell::new(0);
+    let fetch = || {
+        fetches.set(fetches.get() + 1);
+        Ok((1, vec![]))
+    };
+
+    let mut cache = AccountCache::disabled();
+    cache.get_or_fetch(&pubkey, fetch).unwrap();
+    cache.get_or_fetch(&pubkey, fetch).unwrap();
+    assert_eq!(fetches.get(), 2);
+}
+
+#[test]
+fn test_spinner_disabled_when_not_interactive() {
+    let mut spinner = Spinner::new(Vec::new(), false);
+    assert!(!spinner.is_enabled());
+    spinner.set_message("Submitting...");
+    spinner.finish("Confirmed in slot 1");
+    assert!(spinner.into_inner().is_empty());
+
+    let mut spinner = Spinner::new(Vec::new(), true);
+    spinner.set_message("Submitting...");
+    spinner.finish("Confirmed in slot 1");
+    let output = String::from_utf8(spinner.into_inner()).unwrap();
+    assert!(output.contains("Submitting..."));
+    assert!(output.ends_with("Confirmed in slot 1\n"));
+}
+
+#[test]
+fn test_parse_amount() {
+    assert_eq!(parse_amount("1.5", false).unwrap(), 1_500_000_000);
+    assert_eq!(parse_amount("0.000000001", false).unwrap(), 1);
+    assert_eq!(parse_amount("1500", true).unwrap(), 1_500);
+    assert_eq!(parse_amount(&u64::MAX.to_string(), true).unwrap(), u64::MAX);
+
+    for (amount, lamports) in [
+        ("0", false),
+        ("0", true),
+        ("-1", false),
+        ("-1", true),
+        ("0.0000000001", false),
+        ("1.5", true),
+        ("inf", false),
+        ("NaN", false),
+        ("18446744073.709551616", false),
+    ] {
+        assert!(
+            matches!(
+                parse_amount(amount, lamports),
+                Err(solana_multisig_cli::error::Error::InvalidAmount)
+            ),
+            "{} accepted",
+            amount
+        );
+    }
+}
+
+#[test]
+fn test_parse_multisig_list() {
+    let multisigs = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    assert_eq!(
+        parse_multisig_list(&format!(
+            "{},{}, {}",
+            multisigs[0], multisigs[1], multisigs[2]
+        ))
+        .expect("parse"),
+        multisigs
+    );
+    assert_eq!(
+        parse_multisig_list(&format!("{}\n\n{}\n", multisigs[0], multisigs[1])).expect("parse"),
+        multisigs[..2]
+    );
+    assert!(matches!(
+        parse_multisig_list("not-a-pubkey"),
+        Err(Error::InvalidMultisig(address)) if address == "not-a-pubkey"
+    ));
+}
@@ -0,0 +1,160 @@
+//! Drives the multisig program as a library through its whole lifecycle:
+//! create a 2-of-3 multisig, fund it, create a transaction, approve and
+//! execute it and check the recipient balance.
+//!
+//! Program-test can not resize accounts for native processors, so run it
+//! against the BPF build:
+//!
+//! ```bash
+//! cargo build-bpf && BPF_OUT_DIR=target/deploy cargo run --example lifecycle
+//! ```
+
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program_test::{processor, tokio, BanksClient, ProgramTest};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+use solana_multisig::{MultisigInstruction, Processor, MAX_TRANSACTIONS};
+
+const AMOUNT: u64 = 1_000_000;
+
+async fn process(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    recent_blockhash: Hash,
+    instruction: Instruction,
+) {
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+
+    let mut keypairs = vec![payer];
+    keypairs.extend_from_slice(signers);
+    transaction.sign(&keypairs, recent_blockhash);
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("process_transaction");
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let (mut banks_client, funder, recent_blockhash) = ProgramTest::new(
+        "solana_multisig",
+        solana_multisig::id(),
+        processor!(Processor::process),
+    )
+    .start()
+    .await;
+
+    let wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let recipient = Pubkey::new_unique();
+    let multisig = solana_multisig::get_associated_address(&wallet.pubkey());
+
+    // Create a 2-of-3 multisig account
+    process(
+        &mut banks_client,
+        &funder,
+        &[&wallet],
+        recent_blockhash,
+        solana_multisig::create_associated_account(
+            &funder.pubkey(),
+            &wallet.pubkey(),
+            MultisigInstruction::CreateAccount {
+                threshold: 2,
+                owners: owners.iter().map(|owner| owner.pubkey()).collect(),
+                max_pending: MAX_TRANSACTIONS as u32,
+            }
+            .pack()
+            .expect("pack"),
+        ),
+    )
+    .await;
+
+    // Fund the multisig and the wallet, which pays for transaction accounts
+    for address in [multisig, wallet.pubkey()] {
+        process(
+            &mut banks_client,
+            &funder,
+            &[],
+            recent_blockhash,
+            system_instruction::transfer(&funder.pubkey(), &address, 10 * AMOUNT),
+        )
+        .await;
+    }
+
+    // Create a transaction
+    let transaction = Keypair::new();
+    process(
+        &mut banks_client,
+        &funder,
+        &[&wallet, &transaction],
+        recent_blockhash,
+        solana_multisig::create_transaction(
+            &wallet.pubkey(),
+            &wallet.pubkey(),
+            &transaction.pubkey(),
+            &recipient,
+            MultisigInstruction::CreateTransaction {
+                amount: AMOUNT,
+                memo: "Lifecycle example".to_string(),
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                soft: false,
+                auto_approve: false,
+            }
+            .pack()
+            .expect("pack"),
+        ),
+    )
+    .await;
+
+    // Approve by two of three owners
+    for owner in &owners[..2] {
+        process(
+            &mut banks_client,
+            &funder,
+            &[owner],
+            recent_blockhash,
+            solana_multisig::approve_transaction(
+                &owner.pubkey(),
+                &multisig,
+                &transaction.pubkey(),
+                MultisigInstruction::ApproveTransaction
+                    .pack()
+                    .expect("pack"),
+            ),
+        )
+        .await;
+    }
+
+    // Execute the transfer
+    process(
+        &mut banks_client,
+        &funder,
+        &[&owners[0]],
+        recent_blockhash,
+        solana_multisig::execute_transaction(
+            &owners[0].pubkey(),
+            &multisig,
+            &transaction.pubkey(),
+            &recipient,
+            MultisigInstruction::ExecuteTransaction
+                .pack()
+                .expect("pack"),
+        ),
+    )
+    .await;
+
+    let balance = banks_client
+        .get_balance(recipient)
+        .await
+        .expect("get_balance");
+    assert_eq!(balance, AMOUNT);
+
+    println!("Recipient {} received {} lamports", recipient, balance);
+}
@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_multisig::Account;
+use solana_program::program_pack::Pack;
+
+fuzz_target!(|data: &[u8]| {
+    // Must never panic, whatever the input length
+    let account = match Account::unpack_unchecked(data) {
+        Ok(account) => account,
+        Err(_) => return,
+    };
+
+    // Unused bytes are dropped, so compare the canonical encoding
+    let mut packed = vec![0; Account::LEN];
+    Account::pack_into_slice(&account, &mut packed);
+
+    let repacked_account = Account::unpack_unchecked(&packed).expect("unpack packed account");
+    let mut repacked = vec![0; Account::LEN];
+    Account::pack_into_slice(&repacked_account, &mut repacked);
+
+    assert_eq!(packed, repacked);
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_multisig::Transaction;
+use solana_program::program_pack::Pack;
+
+fuzz_target!(|data: &[u8]| {
+    // Must never panic, whatever the input length
+    let transaction = match Transaction::unpack_unchecked(data) {
+        Ok(transaction) => transaction,
+        Err(_) => return,
+    };
+
+    // Unused bytes are dropped, so compare the canonical encoding
+    let mut packed = vec![0; Transaction::LEN];
+    Transaction::pack_into_slice(&transaction, &mut packed);
+
+    let repacked_transaction =
+        Transaction::unpack_unchecked(&packed).expect("unpack packed transaction");
+    let mut repacked = vec![0; Transaction::LEN];
+    Transaction::pack_into_slice(&repacked_transaction, &mut repacked);
+
+    assert_eq!(packed, repacked);
+});
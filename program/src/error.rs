@@ -10,7 +10,7 @@ pub enum MultisigError {
     CustodianLimit,
     #[error("Multisig transaction doesn't belong to multisig account")]
     UndefinedTransaction,
-    #[error("Pending transaction limit exceeded")]
+    #[error("Transaction already executed")]
     TransactionAlreadyExecuted,
     #[error("Signer is not custodian of multisig account")]
     InvalidCustodian,
@@ -18,6 +18,54 @@ pub enum MultisigError {
     InsufficientBalance,
     #[error("Amount Overflow")]
     AmountOverflow,
+    #[error("Multisig owners must be unique")]
+    DuplicateOwner,
+    #[error("Recipient doesn't match multisig transaction")]
+    InvalidRecipient,
+    #[error("Multisig transaction doesn't have enough approvals")]
+    NotEnoughApprovals,
+    #[error("Signer is not allowed to propose multisig transactions")]
+    InvalidProposer,
+    #[error("Multisig account has pending transactions")]
+    PendingTransactionsExist,
+    #[error("Multisig transaction is not executed")]
+    TransactionNotExecuted,
+    #[error("Multisig transaction outputs limit exceeded")]
+    OutputLimit,
+    #[error("Multisig transaction is time-locked")]
+    TimelockActive,
+    #[error("Multisig transaction is expired")]
+    TransactionExpired,
+    #[error("Multisig transaction is not expired")]
+    TransactionNotExpired,
+    #[error("Instruction accounts must be distinct")]
+    DuplicateAccount,
+    #[error("Multisig transaction is a soft proposal")]
+    SoftProposal,
+    #[error("Multisig transaction nonce was never issued")]
+    InvalidNonce,
+    #[error("Multisig threshold must be positive")]
+    InvalidThreshold,
+    #[error("Multisig transaction amount exceeds the proposer spend limit")]
+    SpendLimitExceeded,
+    #[error("Multisig transaction instruction is invalid")]
+    InvalidInstruction,
+    #[error("Multisig account is frozen")]
+    WalletFrozen,
+    #[error("Multisig transaction recipient is the multisig itself")]
+    SelfTransfer,
+    #[error("Multisig transaction amount must be positive")]
+    ZeroAmount,
+    #[error("Multisig transaction is already approved by this owner")]
+    AlreadyApproved,
+    #[error("Multisig transaction is cancelled")]
+    TransactionCancelled,
+    #[error("Multisig pending transaction cap must be between 1 and the program maximum")]
+    InvalidPendingLimit,
+    #[error("Multisig transaction execution delay is still running")]
+    ExecutionDelayActive,
+    #[error("Owner was added too recently to approve")]
+    OwnerInGrace,
 }
 impl From<MultisigError> for ProgramError {
     fn from(e: MultisigError) -> Self {
@@ -0,0 +1,195 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use solana_program::entrypoint::ProgramResult;
+use solana_program::log::sol_log_data;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+use crate::state::Account;
+
+/// Version of the event envelope and payload layouts
+pub const EVENT_VERSION: u8 = 1;
+
+/// Event type discriminant stored in `EventHeader::kind`
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventKind {
+    PendingSigners = 0,
+    MultisigCreated = 1,
+    TransactionCreated = 2,
+    TransactionExecuted = 3,
+    MultisigUpdated = 4,
+    ApprovalRecorded = 5,
+}
+
+impl EventKind {
+    pub fn from_u8(kind: u8) -> Option<Self> {
+        Some(match kind {
+            0 => Self::PendingSigners,
+            1 => Self::MultisigCreated,
+            2 => Self::TransactionCreated,
+            3 => Self::TransactionExecuted,
+            4 => Self::MultisigUpdated,
+            5 => Self::ApprovalRecorded,
+            _ => return None,
+        })
+    }
+}
+
+/// Envelope shared by all events.
+///
+/// Every event is emitted via `sol_log_data` as two borsh-encoded fields:
+/// the 2 byte header followed by the payload of the given kind. Indexers
+/// dispatch on `kind` and should skip versions they don't know.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct EventHeader {
+    pub version: u8,
+    pub kind: u8,
+}
+
+impl EventHeader {
+    /// Decodes the header field and checks that the kind is known.
+    pub fn decode(data: &[u8]) -> Result<(Self, EventKind), ProgramError> {
+        let header =
+            Self::try_from_slice(data).map_err(|_| ProgramError::InvalidInstructionData)?;
+        let kind = EventKind::from_u8(header.kind).ok_or(ProgramError::InvalidInstructionData)?;
+        Ok((header, kind))
+    }
+}
+
+pub trait Event: BorshSerialize {
+    const KIND: EventKind;
+
+    /// Returns the `sol_log_data` fields, header first.
+    fn encode(&self) -> Result<Vec<Vec<u8>>, ProgramError> {
+        let header = EventHeader {
+            version: EVENT_VERSION,
+            kind: Self::KIND as u8,
+        };
+        Ok(vec![header.try_to_vec()?, self.try_to_vec()?])
+    }
+
+    fn emit(&self) -> ProgramResult {
+        let fields = self.encode()?;
+        sol_log_data(&fields.iter().map(Vec::as_slice).collect::<Vec<_>>());
+        Ok(())
+    }
+}
+
+/// Owners that still have to approve a multisig transaction.
+///
+/// Emitted when a transaction is created (all owners) and after every
+/// approval or revocation (owners that have not signed yet). Layout:
+///
+/// * `transaction` - 32 bytes, address of the transaction account
+/// * `pending_signers` - u32 little-endian length followed by 32 bytes per owner
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct PendingSignersEvent {
+    pub transaction: Pubkey,
+    pub pending_signers: Vec<Pubkey>,
+}
+
+impl PendingSignersEvent {
+    pub fn new(transaction: Pubkey, signers: &[(Pubkey, bool)]) -> Self {
+        Self {
+            transaction,
+            pending_signers: signers
+                .iter()
+                .filter(|(_, is_signed)| !*is_signed)
+                .map(|(signer, _)| *signer)
+                .collect(),
+        }
+    }
+}
+
+impl Event for PendingSignersEvent {
+    const KIND: EventKind = EventKind::PendingSigners;
+}
+
+/// Emitted when a multisig account is created.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct MultisigCreatedEvent {
+    pub multisig: Pubkey,
+    pub threshold: u32,
+    pub owners: Vec<Pubkey>,
+}
+
+impl Event for MultisigCreatedEvent {
+    const KIND: EventKind = EventKind::MultisigCreated;
+}
+
+/// Emitted when a multisig transaction is created, `amount` is zero for
+/// sweeps until execution.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct TransactionCreatedEvent {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub is_sweep: bool,
+}
+
+impl Event for TransactionCreatedEvent {
+    const KIND: EventKind = EventKind::TransactionCreated;
+}
+
+/// Emitted when a multisig transaction is executed with the transferred amount.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct TransactionExecutedEvent {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+impl Event for TransactionExecutedEvent {
+    const KIND: EventKind = EventKind::TransactionExecuted;
+}
+
+/// Emitted after a governance change (threshold, owners or proposer
+/// policy) with the resulting threshold and owners.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct MultisigUpdatedEvent {
+    pub multisig: Pubkey,
+    pub threshold: u32,
+    pub owners: Vec<Pubkey>,
+}
+
+impl MultisigUpdatedEvent {
+    pub fn new(multisig: Pubkey, account: &Account) -> Self {
+        Self {
+            multisig,
+            threshold: account.threshold,
+            owners: account.owners.clone(),
+        }
+    }
+}
+
+impl Event for MultisigUpdatedEvent {
+    const KIND: EventKind = EventKind::MultisigUpdated;
+}
+
+/// Emitted when an owner approves a multisig transaction, `approvals` is the
+/// number of owners that have signed it so far.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct ApprovalRecordedEvent {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub signer: Pubkey,
+    pub approvals: u32,
+}
+
+impl ApprovalRecordedEvent {
+    pub fn new(multisig: Pubkey, transaction: Pubkey, signer: Pubkey, approvals: u32) -> Self {
+        Self {
+            multisig,
+            transaction,
+            signer,
+            approvals,
+        }
+    }
+}
+
+impl Event for ApprovalRecordedEvent {
+    const KIND: EventKind = EventKind::ApprovalRecorded;
+}
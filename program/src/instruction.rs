@@ -1,40 +1,137 @@
 use std::convert::TryInto;
 
 use arrayref::array_ref;
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 
+use solana_program::msg;
 use solana_program::program_error::ProgramError;
-use solana_program::pubkey::Pubkey;
+use solana_program::pubkey::{Pubkey, PUBKEY_BYTES};
+
+use crate::state::{InnerInstruction, ProposerPolicy, MAX_MEMO_LEN, MAX_SIGNERS, MAX_TRANSACTIONS};
+use crate::utils::unpack_bool;
 
 pub enum MultisigInstruction {
-    CreateAccount { threshold: u32, owners: Vec<Pubkey> },
-    CreateTransaction { amount: u64 },
+    CreateAccount {
+        threshold: u32,
+        owners: Vec<Pubkey>,
+        max_pending: u32,
+    },
+    CreateTransaction {
+        amount: u64,
+        memo: String,
+        not_before_slot: u64,
+        expires_at_slot: u64,
+        soft: bool,
+        auto_approve: bool,
+    },
     ApproveTransaction,
+    SetReserve {
+        reserved_balance: u64,
+    },
+    RevokeApproval,
+    CreateSweepTransaction,
+    ExecuteTransaction,
+    ChangeThreshold {
+        new_threshold: u32,
+    },
+    SetProposerPolicy {
+        proposer_policy: ProposerPolicy,
+    },
+    AddOwner {
+        owner: Pubkey,
+    },
+    RemoveOwner {
+        owner: Pubkey,
+    },
+    CloseAccount,
+    CloseTransaction,
+    CreateMultiTransfer {
+        outputs: Vec<(Pubkey, u64)>,
+        memo: String,
+    },
+    ReapExpired,
+    PromoteTransaction,
+    CreateWeightedAccount {
+        threshold: u32,
+        owners: Vec<(Pubkey, u16)>,
+    },
+    SetStrictOwnerMatch {
+        enabled: bool,
+    },
+    SetSpendLimit {
+        owner: Pubkey,
+        limit: Option<u64>,
+    },
+    CreateInstructionTransaction {
+        instruction: InnerInstruction,
+        memo: String,
+    },
+    SetFrozen {
+        frozen: bool,
+    },
+    Reconcile,
+    SetExecutionDelay {
+        slots: u64,
+    },
+    CancelTransaction,
+    SetOwnerGrace {
+        secs: i64,
+    },
 }
 
+/// Current instruction format version
+pub const INSTRUCTION_VERSION: u8 = 1;
+
+// Set on the version byte so versioned data never starts with a legacy tag
+const VERSION_FLAG: u8 = 0x80;
+
 impl MultisigInstruction {
+    /// Unpacks instruction data starting with the format version byte
+    /// followed by the instruction tag. Legacy data starting right at the
+    /// tag is still accepted until the next format change.
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (version, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        if *version & VERSION_FLAG == 0 {
+            Self::unpack_tagged(input)
+        } else if *version == VERSION_FLAG | INSTRUCTION_VERSION {
+            Self::unpack_tagged(rest)
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    fn unpack_tagged(input: &[u8]) -> Result<Self, ProgramError> {
         let (tag, rest) = input
             .split_first()
             .ok_or(ProgramError::InvalidInstructionData)?;
 
         Ok(match tag {
             1 => {
-                let (threshold, rest) = rest.split_at(4);
+                let (threshold, rest) = rest.split_at(rest.len().min(4));
                 let threshold = threshold
                     .try_into()
                     .ok()
                     .map(u32::from_le_bytes)
                     .ok_or(ProgramError::InvalidInstructionData)?;
 
-                let (owners_len, rest) = rest.split_at(4);
+                let (owners_len, rest) = rest.split_at(rest.len().min(4));
                 let owners_len = owners_len
                     .try_into()
                     .ok()
                     .map(u32::from_le_bytes)
                     .ok_or(ProgramError::InvalidInstructionData)?;
 
-                let mut owners = Vec::with_capacity((32 * owners_len) as usize);
+                if owners_len as usize > MAX_SIGNERS {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                if rest.len() < owners_len as usize * PUBKEY_BYTES {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let mut owners = Vec::with_capacity(owners_len as usize);
 
                 let mut offset = 0;
                 for _ in 0..owners_len {
@@ -43,26 +140,216 @@ impl MultisigInstruction {
                     offset += 32;
                 }
 
-                Self::CreateAccount { threshold, owners }
+                // A missing cap means the program-wide maximum
+                let max_pending = match &rest[offset..] {
+                    [] => MAX_TRANSACTIONS as u32,
+                    max_pending => max_pending
+                        .try_into()
+                        .ok()
+                        .map(u32::from_le_bytes)
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                };
+
+                Self::CreateAccount {
+                    threshold,
+                    owners,
+                    max_pending,
+                }
             }
             2 => {
-                let amount = rest
+                if rest.len() < 8 {
+                    msg!("Error: CreateTransaction amount must be 8 bytes");
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let (amount, rest) = rest.split_at(8);
+                let amount = amount
                     .try_into()
                     .ok()
                     .map(u64::from_le_bytes)
                     .ok_or(ProgramError::InvalidInstructionData)?;
 
-                Self::CreateTransaction { amount }
+                let (memo, rest) = unpack_memo(rest)?;
+
+                // Timelock and expiry are optional as well, zero means none
+                let (not_before_slot, rest) = unpack_optional_slot(rest)?;
+                let (expires_at_slot, rest) = unpack_optional_slot(rest)?;
+
+                // Missing flags mean a funded transaction the proposer
+                // hasn't approved
+                let unpack_flag = |flag: &u8| {
+                    unpack_bool(&[*flag]).map_err(|_| ProgramError::InvalidInstructionData)
+                };
+                let (soft, auto_approve) = match rest {
+                    [] => (false, false),
+                    [soft] => (unpack_flag(soft)?, false),
+                    [soft, auto_approve] => (unpack_flag(soft)?, unpack_flag(auto_approve)?),
+                    _ => {
+                        msg!("Error: CreateTransaction has trailing data");
+                        return Err(ProgramError::InvalidInstructionData);
+                    }
+                };
+
+                Self::CreateTransaction {
+                    amount,
+                    memo,
+                    not_before_slot,
+                    expires_at_slot,
+                    soft,
+                    auto_approve,
+                }
             }
             3 => Self::ApproveTransaction,
+            4 => {
+                let reserved_balance = rest
+                    .try_into()
+                    .ok()
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetReserve { reserved_balance }
+            }
+            5 => Self::RevokeApproval,
+            6 => Self::CreateSweepTransaction,
+            7 => Self::ExecuteTransaction,
+            8 => {
+                let new_threshold = rest
+                    .try_into()
+                    .ok()
+                    .map(u32::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::ChangeThreshold { new_threshold }
+            }
+            9 => {
+                let proposer_policy = ProposerPolicy::try_from_slice(rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+                Self::SetProposerPolicy { proposer_policy }
+            }
+            10 | 11 => {
+                let owner = rest
+                    .try_into()
+                    .ok()
+                    .map(Pubkey::new_from_array)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                if *tag == 10 {
+                    Self::AddOwner { owner }
+                } else {
+                    Self::RemoveOwner { owner }
+                }
+            }
+            12 => Self::CloseAccount,
+            13 => Self::CloseTransaction,
+            14 => {
+                let mut rest = rest;
+                // Oversized output lists are rejected by the processor
+                let outputs = Vec::<(Pubkey, u64)>::deserialize(&mut rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                let (memo, rest) = unpack_memo(rest)?;
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                Self::CreateMultiTransfer { outputs, memo }
+            }
+            15 => Self::ReapExpired,
+            16 => Self::PromoteTransaction,
+            17 => {
+                let (threshold, mut rest) = rest.split_at(rest.len().min(4));
+                let threshold = threshold
+                    .try_into()
+                    .ok()
+                    .map(u32::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                // Oversized owner sets are rejected by the processor
+                let owners = Vec::<(Pubkey, u16)>::deserialize(&mut rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                Self::CreateWeightedAccount { threshold, owners }
+            }
+            18 => {
+                let enabled = rest
+                    .try_into()
+                    .ok()
+                    .and_then(|enabled| unpack_bool(enabled).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetStrictOwnerMatch { enabled }
+            }
+            19 => {
+                // The limit is left out to clear it
+                let (owner, limit) = rest.split_at(rest.len().min(PUBKEY_BYTES));
+                let owner = owner
+                    .try_into()
+                    .ok()
+                    .map(Pubkey::new_from_array)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let limit = match limit.len() {
+                    0 => None,
+                    8 => Some(u64::from_le_bytes(*array_ref![limit, 0, 8])),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+
+                Self::SetSpendLimit { owner, limit }
+            }
+            20 => {
+                let mut rest = rest;
+                // Oversized instructions are rejected by the processor
+                let instruction = InnerInstruction::deserialize(&mut rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                let (memo, rest) = unpack_memo(rest)?;
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                Self::CreateInstructionTransaction { instruction, memo }
+            }
+            21 => {
+                let frozen = rest
+                    .try_into()
+                    .ok()
+                    .and_then(|frozen| unpack_bool(frozen).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetFrozen { frozen }
+            }
+            22 => Self::Reconcile,
+            23 => {
+                let slots = rest
+                    .try_into()
+                    .ok()
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetExecutionDelay { slots }
+            }
+            24 => Self::CancelTransaction,
+            25 => {
+                let secs = rest
+                    .try_into()
+                    .ok()
+                    .map(i64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetOwnerGrace { secs }
+            }
             _ => return Err(ProgramError::InvalidInstructionData),
         })
     }
 
     pub fn pack(&self) -> Result<Vec<u8>, ProgramError> {
-        let mut buf = Vec::new();
+        let mut buf = vec![VERSION_FLAG | INSTRUCTION_VERSION];
         match self {
-            Self::CreateAccount { threshold, owners } => {
+            Self::CreateAccount {
+                threshold,
+                owners,
+                max_pending,
+            } => {
                 buf.push(1);
                 buf.extend_from_slice(&threshold.to_le_bytes());
                 buf.extend_from_slice(
@@ -70,15 +357,180 @@ impl MultisigInstruction {
                         .try_to_vec()
                         .map_err(|_| ProgramError::InvalidInstructionData)?,
                 );
+                if *max_pending != MAX_TRANSACTIONS as u32 {
+                    buf.extend_from_slice(&max_pending.to_le_bytes());
+                }
             }
-            Self::CreateTransaction { amount } => {
+            Self::CreateTransaction {
+                amount,
+                memo,
+                not_before_slot,
+                expires_at_slot,
+                soft,
+                auto_approve,
+            } => {
                 buf.push(2);
                 buf.extend_from_slice(&amount.to_le_bytes());
+                pack_memo(memo, &mut buf)?;
+                buf.extend_from_slice(&not_before_slot.to_le_bytes());
+                buf.extend_from_slice(&expires_at_slot.to_le_bytes());
+                buf.push(*soft as u8);
+                if *auto_approve {
+                    buf.push(1);
+                }
             }
             Self::ApproveTransaction => {
                 buf.push(3);
             }
+            Self::SetReserve { reserved_balance } => {
+                buf.push(4);
+                buf.extend_from_slice(&reserved_balance.to_le_bytes());
+            }
+            Self::RevokeApproval => {
+                buf.push(5);
+            }
+            Self::CreateSweepTransaction => {
+                buf.push(6);
+            }
+            Self::ExecuteTransaction => {
+                buf.push(7);
+            }
+            Self::ChangeThreshold { new_threshold } => {
+                buf.push(8);
+                buf.extend_from_slice(&new_threshold.to_le_bytes());
+            }
+            Self::SetProposerPolicy { proposer_policy } => {
+                buf.push(9);
+                buf.extend_from_slice(
+                    &proposer_policy
+                        .try_to_vec()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+            }
+            Self::AddOwner { owner } => {
+                buf.push(10);
+                buf.extend_from_slice(owner.as_ref());
+            }
+            Self::RemoveOwner { owner } => {
+                buf.push(11);
+                buf.extend_from_slice(owner.as_ref());
+            }
+            Self::CloseAccount => {
+                buf.push(12);
+            }
+            Self::CloseTransaction => {
+                buf.push(13);
+            }
+            Self::CreateMultiTransfer { outputs, memo } => {
+                buf.push(14);
+                buf.extend_from_slice(
+                    &outputs
+                        .try_to_vec()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                pack_memo(memo, &mut buf)?;
+            }
+            Self::ReapExpired => {
+                buf.push(15);
+            }
+            Self::PromoteTransaction => {
+                buf.push(16);
+            }
+            Self::CreateWeightedAccount { threshold, owners } => {
+                buf.push(17);
+                buf.extend_from_slice(&threshold.to_le_bytes());
+                buf.extend_from_slice(
+                    &owners
+                        .try_to_vec()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+            }
+            Self::SetStrictOwnerMatch { enabled } => {
+                buf.push(18);
+                buf.push(*enabled as u8);
+            }
+            Self::SetSpendLimit { owner, limit } => {
+                buf.push(19);
+                buf.extend_from_slice(owner.as_ref());
+                if let Some(limit) = limit {
+                    buf.extend_from_slice(&limit.to_le_bytes());
+                }
+            }
+            Self::CreateInstructionTransaction { instruction, memo } => {
+                buf.push(20);
+                buf.extend_from_slice(
+                    &instruction
+                        .try_to_vec()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                pack_memo(memo, &mut buf)?;
+            }
+            Self::SetFrozen { frozen } => {
+                buf.push(21);
+                buf.push(*frozen as u8);
+            }
+            Self::Reconcile => {
+                buf.push(22);
+            }
+            Self::SetExecutionDelay { slots } => {
+                buf.push(23);
+                buf.extend_from_slice(&slots.to_le_bytes());
+            }
+            Self::CancelTransaction => {
+                buf.push(24);
+            }
+            Self::SetOwnerGrace { secs } => {
+                buf.push(25);
+                buf.extend_from_slice(&secs.to_le_bytes());
+            }
         };
         Ok(buf)
     }
 }
+
+// Memo is optional, `u8` length followed by UTF-8 bytes
+fn unpack_memo(input: &[u8]) -> Result<(String, &[u8]), ProgramError> {
+    let (memo_len, rest) = match input.split_first() {
+        None => return Ok((String::new(), input)),
+        Some((memo_len, rest)) => (*memo_len as usize, rest),
+    };
+
+    if memo_len > MAX_MEMO_LEN || rest.len() < memo_len {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (memo, rest) = rest.split_at(memo_len);
+    let memo = std::str::from_utf8(memo)
+        .map_err(|_| ProgramError::InvalidInstructionData)?
+        .to_string();
+
+    Ok((memo, rest))
+}
+
+// Missing slot means none, a present one must be a full `u64`
+fn unpack_optional_slot(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    if input.is_empty() {
+        return Ok((0, input));
+    }
+    if input.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (slot, rest) = input.split_at(8);
+    let slot = slot
+        .try_into()
+        .ok()
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    Ok((slot, rest))
+}
+
+fn pack_memo(memo: &str, buf: &mut Vec<u8>) -> Result<(), ProgramError> {
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    buf.push(memo.len() as u8);
+    buf.extend_from_slice(memo.as_bytes());
+    Ok(())
+}
@@ -5,14 +5,18 @@ use solana_program::pubkey::Pubkey;
 use solana_program::sysvar;
 
 mod error;
+mod event;
 mod instruction;
 mod processor;
+mod squads;
 mod state;
 mod utils;
 
 pub use self::error::*;
+pub use self::event::*;
 pub use self::instruction::*;
 pub use self::processor::*;
+pub use self::squads::*;
 pub use self::state::*;
 pub use self::utils::*;
 
@@ -39,6 +43,45 @@ pub fn get_associated_address(wallet_address: &Pubkey) -> Pubkey {
     get_associated_address_and_bump_seed(wallet_address, &id()).0
 }
 
+/// Seed of the authority address that signs instructions executed by a
+/// multisig
+pub const AUTHORITY_SEED: &[u8] = b"authority";
+
+pub fn get_authority_address_and_bump_seed(
+    multisig_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AUTHORITY_SEED, &multisig_address.to_bytes()], program_id)
+}
+
+pub fn get_authority_address(multisig_address: &Pubkey) -> Pubkey {
+    get_authority_address_and_bump_seed(multisig_address, &id()).0
+}
+
+/// Seed of the deterministic transaction addresses of a multisig
+pub const TRANSACTION_SEED: &[u8] = b"transaction";
+
+/// Derives the address of the transaction created with `nonce`, which is
+/// one more than the multisig nonce at creation.
+pub fn get_transaction_address_and_bump_seed(
+    multisig_address: &Pubkey,
+    nonce: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            TRANSACTION_SEED,
+            &multisig_address.to_bytes(),
+            &nonce.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+pub fn get_transaction_address(multisig_address: &Pubkey, nonce: u64) -> Pubkey {
+    get_transaction_address_and_bump_seed(multisig_address, nonce, &id()).0
+}
+
 pub fn create_associated_account(
     funding_address: &Pubkey,
     wallet_address: &Pubkey,
@@ -59,6 +102,10 @@ pub fn create_associated_account(
     }
 }
 
+/// Creates a `CreateTransaction`, `CreateSweepTransaction`,
+/// `CreateMultiTransfer` or `CreateInstructionTransaction` instruction,
+/// depending on the packed `data`. For a multi transfer `recipient_address`
+/// is the first output recipient, for an instruction it is its program.
 pub fn create_transaction(
     funding_address: &Pubkey,
     wallet_address: &Pubkey,
@@ -81,7 +128,70 @@ pub fn create_transaction(
     }
 }
 
+/// Creates a transaction instruction like `create_transaction`, but at the
+/// address derived from `nonce`, so no transaction keypair signs.
+pub fn create_derived_transaction(
+    funding_address: &Pubkey,
+    wallet_address: &Pubkey,
+    nonce: u64,
+    recipient_address: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    let transaction_address =
+        get_transaction_address(&get_associated_address(wallet_address), nonce);
+    let mut instruction = create_transaction(
+        funding_address,
+        wallet_address,
+        &transaction_address,
+        recipient_address,
+        data,
+    );
+    instruction.accounts[1].is_signer = false;
+    instruction
+}
+
+/// Creates a `CreateTransaction` instruction with `auto_approve` set. The
+/// recipient is writable, since the transfer may be executed right away.
+pub fn create_approved_transaction(
+    funding_address: &Pubkey,
+    wallet_address: &Pubkey,
+    transaction_address: &Pubkey,
+    recipient_address: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    let mut instruction = create_transaction(
+        funding_address,
+        wallet_address,
+        transaction_address,
+        recipient_address,
+        data,
+    );
+    instruction.accounts[3].is_writable = true;
+    instruction
+}
+
+/// Creates an `ApproveTransaction` instruction, only the transaction
+/// account is written.
 pub fn approve_transaction(
+    funding_address: &Pubkey,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*funding_address, true),
+            AccountMeta::new_readonly(*multisig_address, false),
+            AccountMeta::new(*transaction_address, false),
+        ],
+        data,
+    }
+}
+
+/// Creates an `ExecuteTransaction` instruction. Any owner can execute a
+/// transaction once it collected threshold approvals.
+pub fn execute_transaction(
     funding_address: &Pubkey,
     multisig_address: &Pubkey,
     transaction_address: &Pubkey,
@@ -99,3 +209,300 @@ pub fn approve_transaction(
         data,
     }
 }
+
+/// Creates an `ExecuteTransaction` instruction for a multi transfer,
+/// `recipient_addresses` are the output recipients in order.
+pub fn execute_multi_transfer(
+    funding_address: &Pubkey,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    recipient_addresses: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*funding_address, true),
+        AccountMeta::new(*multisig_address, false),
+        AccountMeta::new(*transaction_address, false),
+    ];
+    accounts.extend(
+        recipient_addresses
+            .iter()
+            .map(|recipient_address| AccountMeta::new(*recipient_address, false)),
+    );
+
+    Instruction {
+        program_id: id(),
+        accounts,
+        data,
+    }
+}
+
+/// Creates an `ExecuteTransaction` instruction for an instruction
+/// transaction, passing the program and accounts `instruction` invokes.
+pub fn execute_instruction(
+    funding_address: &Pubkey,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    instruction: &InnerInstruction,
+    data: Vec<u8>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*funding_address, true),
+        AccountMeta::new(*multisig_address, false),
+        AccountMeta::new(*transaction_address, false),
+        AccountMeta::new_readonly(instruction.program_id, false),
+    ];
+    accounts.extend(instruction.accounts.iter().map(|meta| {
+        if meta.is_writable {
+            AccountMeta::new(meta.pubkey, false)
+        } else {
+            AccountMeta::new_readonly(meta.pubkey, false)
+        }
+    }));
+
+    Instruction {
+        program_id: id(),
+        accounts,
+        data,
+    }
+}
+
+pub fn close_transaction(
+    owner_address: &Pubkey,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    destination_address: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*owner_address, true),
+            AccountMeta::new_readonly(*multisig_address, false),
+            AccountMeta::new(*transaction_address, false),
+            AccountMeta::new(*destination_address, false),
+        ],
+        data,
+    }
+}
+
+/// Creates a `ReapExpired` instruction unfreezing an expired transaction
+/// and sending its rent to `destination_address`.
+pub fn reap_expired(
+    owner_address: &Pubkey,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    destination_address: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*owner_address, true),
+            AccountMeta::new(*multisig_address, false),
+            AccountMeta::new(*transaction_address, false),
+            AccountMeta::new(*destination_address, false),
+        ],
+        data,
+    }
+}
+
+/// Creates a `PromoteTransaction` instruction freezing the amount of an
+/// approved soft proposal so it can be executed.
+pub fn promote_transaction(
+    owner_address: &Pubkey,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*owner_address, true),
+            AccountMeta::new(*multisig_address, false),
+            AccountMeta::new(*transaction_address, false),
+        ],
+        data,
+    }
+}
+
+pub fn set_reserve(wallet_address: &Pubkey, data: Vec<u8>) -> Instruction {
+    let associated_account_address = get_associated_address(wallet_address);
+
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*wallet_address, true),
+            AccountMeta::new(associated_account_address, false),
+        ],
+        data,
+    }
+}
+
+pub fn revoke_approval(
+    funding_address: &Pubkey,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*funding_address, true),
+            AccountMeta::new_readonly(*multisig_address, false),
+            AccountMeta::new(*transaction_address, false),
+        ],
+        data,
+    }
+}
+
+/// Creates a `ChangeThreshold` instruction signed by `owners`, which must
+/// include at least the current threshold number of multisig owners.
+pub fn change_threshold(
+    multisig_address: &Pubkey,
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    owners_signed_instruction(multisig_address, owners, data)
+}
+
+/// Creates a `SetProposerPolicy` instruction, see `change_threshold`.
+pub fn set_proposer_policy(
+    multisig_address: &Pubkey,
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    owners_signed_instruction(multisig_address, owners, data)
+}
+
+/// Creates a `SetStrictOwnerMatch` instruction, see `change_threshold`.
+pub fn set_strict_owner_match(
+    multisig_address: &Pubkey,
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    owners_signed_instruction(multisig_address, owners, data)
+}
+
+/// Creates a `SetSpendLimit` instruction, see `change_threshold`.
+pub fn set_spend_limit(multisig_address: &Pubkey, owners: &[Pubkey], data: Vec<u8>) -> Instruction {
+    owners_signed_instruction(multisig_address, owners, data)
+}
+
+/// Creates a `SetFrozen` instruction, see `change_threshold`. A single
+/// owner is enough to freeze, unfreezing takes the threshold.
+pub fn set_frozen(multisig_address: &Pubkey, owners: &[Pubkey], data: Vec<u8>) -> Instruction {
+    owners_signed_instruction(multisig_address, owners, data)
+}
+
+/// Creates a `Reconcile` instruction, see `change_threshold`. Every pending
+/// transaction of the multisig is passed, in the order it is listed.
+pub fn reconcile(
+    multisig_address: &Pubkey,
+    transaction_addresses: &[Pubkey],
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut instruction = owners_signed_instruction(multisig_address, owners, data);
+    for (index, transaction_address) in transaction_addresses.iter().enumerate() {
+        instruction.accounts.insert(
+            1 + index,
+            AccountMeta::new_readonly(*transaction_address, false),
+        );
+    }
+    instruction
+}
+
+/// Creates a `SetExecutionDelay` instruction, see `change_threshold`.
+pub fn set_execution_delay(
+    multisig_address: &Pubkey,
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    owners_signed_instruction(multisig_address, owners, data)
+}
+
+/// Creates a `SetOwnerGrace` instruction, see `change_threshold`.
+pub fn set_owner_grace(multisig_address: &Pubkey, owners: &[Pubkey], data: Vec<u8>) -> Instruction {
+    owners_signed_instruction(multisig_address, owners, data)
+}
+
+/// Creates an `AddOwner` instruction, see `change_threshold`. A full
+/// multisig of the first release is grown to the current layout, with
+/// `funder_address` paying the rent of the added space.
+pub fn add_owner(
+    multisig_address: &Pubkey,
+    funder_address: &Pubkey,
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut instruction = owners_signed_instruction(multisig_address, owners, data);
+    instruction.accounts.splice(
+        1..1,
+        [
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    );
+    instruction
+}
+
+/// Creates a `RemoveOwner` instruction, see `change_threshold`.
+pub fn remove_owner(multisig_address: &Pubkey, owners: &[Pubkey], data: Vec<u8>) -> Instruction {
+    owners_signed_instruction(multisig_address, owners, data)
+}
+
+/// Creates a `CloseAccount` instruction sending the multisig lamports to
+/// `destination_address`, see `change_threshold`.
+pub fn close_account(
+    multisig_address: &Pubkey,
+    destination_address: &Pubkey,
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut instruction = owners_signed_instruction(multisig_address, owners, data);
+    instruction
+        .accounts
+        .insert(1, AccountMeta::new(*destination_address, false));
+    instruction
+}
+
+/// Creates a `CancelTransaction` instruction withdrawing a pending
+/// transaction, unfreezing its amount and sending its rent to
+/// `destination_address`, see `change_threshold`.
+pub fn cancel_transaction(
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    destination_address: &Pubkey,
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut instruction = owners_signed_instruction(multisig_address, owners, data);
+    instruction.accounts.splice(
+        1..1,
+        [
+            AccountMeta::new(*transaction_address, false),
+            AccountMeta::new(*destination_address, false),
+        ],
+    );
+    instruction
+}
+
+fn owners_signed_instruction(
+    multisig_address: &Pubkey,
+    owners: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*multisig_address, false)];
+    accounts.extend(
+        owners
+            .iter()
+            .map(|owner| AccountMeta::new_readonly(*owner, true)),
+    );
+
+    Instruction {
+        program_id: id(),
+        accounts,
+        data,
+    }
+}
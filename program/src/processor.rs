@@ -1,5 +1,7 @@
 use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::clock::Clock;
 use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::program::{invoke, invoke_signed};
 use solana_program::program_error::ProgramError;
 use solana_program::program_pack::Pack;
@@ -8,9 +10,17 @@ use solana_program::rent::Rent;
 use solana_program::sysvar::Sysvar;
 use solana_program::{msg, system_instruction};
 
+use crate::event::{
+    ApprovalRecordedEvent, Event, MultisigCreatedEvent, MultisigUpdatedEvent, PendingSignersEvent,
+    TransactionCreatedEvent, TransactionExecutedEvent,
+};
 use crate::instruction::MultisigInstruction;
-use crate::state::Account;
-use crate::{MultisigError, Transaction, MAX_SIGNERS, MAX_TRANSACTIONS, MIN_SIGNERS};
+use crate::state::{Account, InnerInstruction, ProposerPolicy, TransactionStatus};
+use crate::{
+    get_authority_address_and_bump_seed, get_transaction_address_and_bump_seed, MultisigError,
+    Transaction, AUTHORITY_SEED, MAX_OUTPUTS, MAX_SIGNERS, MAX_TRANSACTIONS, MIN_SIGNERS,
+    TRANSACTION_SEED,
+};
 
 pub struct Processor;
 impl Processor {
@@ -22,18 +32,161 @@ impl Processor {
         let instruction = MultisigInstruction::unpack(instruction_data)?;
 
         match instruction {
-            MultisigInstruction::CreateAccount { threshold, owners } => {
+            MultisigInstruction::CreateAccount {
+                threshold,
+                owners,
+                max_pending,
+            } => {
                 msg!("Instruction: CreateAccount");
-                Self::process_create_account(program_id, accounts, threshold, owners)?;
+                Self::process_create_account(
+                    program_id,
+                    accounts,
+                    threshold,
+                    owners,
+                    vec![],
+                    max_pending,
+                )?;
             }
-            MultisigInstruction::CreateTransaction { amount } => {
+            MultisigInstruction::CreateTransaction {
+                amount,
+                memo,
+                not_before_slot,
+                expires_at_slot,
+                soft,
+                auto_approve,
+            } => {
                 msg!("Instruction: CreateTransaction");
-                Self::process_create_transaction(program_id, accounts, amount)?;
+                Self::process_create_transaction(
+                    program_id,
+                    accounts,
+                    amount,
+                    memo,
+                    false,
+                    vec![],
+                    not_before_slot,
+                    expires_at_slot,
+                    soft,
+                    auto_approve,
+                    None,
+                )?;
             }
             MultisigInstruction::ApproveTransaction => {
                 msg!("Instruction: ApproveTransaction");
                 Self::process_approve_transaction(accounts)?;
             }
+            MultisigInstruction::SetReserve { reserved_balance } => {
+                msg!("Instruction: SetReserve");
+                Self::process_set_reserve(program_id, accounts, reserved_balance)?;
+            }
+            MultisigInstruction::RevokeApproval => {
+                msg!("Instruction: RevokeApproval");
+                Self::process_revoke_approval(accounts)?;
+            }
+            MultisigInstruction::CreateSweepTransaction => {
+                msg!("Instruction: CreateSweepTransaction");
+                Self::process_create_transaction(
+                    program_id,
+                    accounts,
+                    0,
+                    String::new(),
+                    true,
+                    vec![],
+                    0,
+                    0,
+                    false,
+                    false,
+                    None,
+                )?;
+            }
+            MultisigInstruction::ExecuteTransaction => {
+                msg!("Instruction: ExecuteTransaction");
+                Self::process_execute_transaction(program_id, accounts)?;
+            }
+            MultisigInstruction::ChangeThreshold { new_threshold } => {
+                msg!("Instruction: ChangeThreshold");
+                Self::process_change_threshold(accounts, new_threshold)?;
+            }
+            MultisigInstruction::SetProposerPolicy { proposer_policy } => {
+                msg!("Instruction: SetProposerPolicy");
+                Self::process_set_proposer_policy(accounts, proposer_policy)?;
+            }
+            MultisigInstruction::AddOwner { owner } => {
+                msg!("Instruction: AddOwner");
+                Self::process_add_owner(accounts, owner)?;
+            }
+            MultisigInstruction::RemoveOwner { owner } => {
+                msg!("Instruction: RemoveOwner");
+                Self::process_remove_owner(accounts, owner)?;
+            }
+            MultisigInstruction::CloseAccount => {
+                msg!("Instruction: CloseAccount");
+                Self::process_close_account(accounts)?;
+            }
+            MultisigInstruction::CloseTransaction => {
+                msg!("Instruction: CloseTransaction");
+                Self::process_close_transaction(accounts)?;
+            }
+            MultisigInstruction::CreateMultiTransfer { outputs, memo } => {
+                msg!("Instruction: CreateMultiTransfer");
+                Self::process_create_multi_transfer(program_id, accounts, outputs, memo)?;
+            }
+            MultisigInstruction::ReapExpired => {
+                msg!("Instruction: ReapExpired");
+                Self::process_reap_expired(accounts)?;
+            }
+            MultisigInstruction::PromoteTransaction => {
+                msg!("Instruction: PromoteTransaction");
+                Self::process_promote_transaction(accounts)?;
+            }
+            MultisigInstruction::CreateWeightedAccount { threshold, owners } => {
+                msg!("Instruction: CreateWeightedAccount");
+                let (owners, owner_weights) = owners.into_iter().unzip();
+                Self::process_create_account(
+                    program_id,
+                    accounts,
+                    threshold,
+                    owners,
+                    owner_weights,
+                    MAX_TRANSACTIONS as u32,
+                )?;
+            }
+            MultisigInstruction::SetStrictOwnerMatch { enabled } => {
+                msg!("Instruction: SetStrictOwnerMatch");
+                Self::process_set_strict_owner_match(accounts, enabled)?;
+            }
+            MultisigInstruction::SetSpendLimit { owner, limit } => {
+                msg!("Instruction: SetSpendLimit");
+                Self::process_set_spend_limit(accounts, owner, limit)?;
+            }
+            MultisigInstruction::SetFrozen { frozen } => {
+                msg!("Instruction: SetFrozen");
+                Self::process_set_frozen(accounts, frozen)?;
+            }
+            MultisigInstruction::Reconcile => {
+                msg!("Instruction: Reconcile");
+                Self::process_reconcile(program_id, accounts)?;
+            }
+            MultisigInstruction::SetExecutionDelay { slots } => {
+                msg!("Instruction: SetExecutionDelay");
+                Self::process_set_execution_delay(accounts, slots)?;
+            }
+            MultisigInstruction::CancelTransaction => {
+                msg!("Instruction: CancelTransaction");
+                Self::process_cancel_transaction(accounts)?;
+            }
+            MultisigInstruction::SetOwnerGrace { secs } => {
+                msg!("Instruction: SetOwnerGrace");
+                Self::process_set_owner_grace(accounts, secs)?;
+            }
+            MultisigInstruction::CreateInstructionTransaction { instruction, memo } => {
+                msg!("Instruction: CreateInstructionTransaction");
+                Self::process_create_instruction_transaction(
+                    program_id,
+                    accounts,
+                    instruction,
+                    memo,
+                )?;
+            }
         };
 
         Ok(())
@@ -44,6 +197,8 @@ impl Processor {
         accounts: &[AccountInfo],
         threshold: u32,
         owners: Vec<Pubkey>,
+        owner_weights: Vec<u16>,
+        max_pending: u32,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
 
@@ -65,18 +220,56 @@ impl Processor {
             return Err(ProgramError::InvalidSeeds);
         }
 
+        // A zero threshold would let anyone execute transactions
+        if threshold == 0 {
+            return Err(MultisigError::InvalidThreshold.into());
+        }
+
         if owners.len() > MAX_SIGNERS || owners.len() < MIN_SIGNERS {
             return Err(MultisigError::CustodianLimit.into());
         }
 
+        let mut unique_owners = owners.clone();
+        unique_owners.sort();
+        unique_owners.dedup();
+        if unique_owners.len() != owners.len() {
+            return Err(MultisigError::DuplicateOwner.into());
+        }
+
+        if owner_weights.contains(&0) {
+            msg!("Error: Owner weights must be positive");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if max_pending == 0 || max_pending as usize > MAX_TRANSACTIONS {
+            return Err(MultisigError::InvalidPendingLimit.into());
+        }
+
         let multisig_account_data = Account {
             is_initialized: true,
             threshold,
             owners,
             pending_transactions: vec![],
             frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights,
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: Clock::get()?.slot,
+            max_pending,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
         };
 
+        if multisig_account_data.threshold > multisig_account_data.total_weight() {
+            return Err(MultisigError::CustodianLimit.into());
+        }
+
         let rent = &Rent::from_account_info(rent_sysvar_info)?;
         let required_lamports = rent
             .minimum_balance(Account::LEN)
@@ -116,22 +309,44 @@ impl Processor {
             &[&[&wallet_account_info.key.to_bytes()[..], &[nonce]]],
         )?;
 
+        if !rent.is_exempt(multisig_account_info.lamports(), Account::LEN) {
+            return Err(ProgramError::AccountNotRentExempt);
+        }
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigCreatedEvent {
+            multisig: *multisig_account_info.key,
+            threshold: multisig_account_data.threshold,
+            owners: multisig_account_data.owners.clone(),
+        };
+
         Account::pack(
             multisig_account_data,
             &mut multisig_account_info.data.borrow_mut(),
         )?;
+        event.emit()?;
 
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn process_create_transaction(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
         amount: u64,
+        memo: String,
+        is_sweep: bool,
+        outputs: Vec<(Pubkey, u64)>,
+        not_before_slot: u64,
+        expires_at_slot: u64,
+        soft: bool,
+        auto_approve: bool,
+        instruction: Option<InnerInstruction>,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
 
-        let wallet_account_info = next_account_info(account_info_iter)?;
+        let proposer_account_info = next_account_info(account_info_iter)?;
         let transaction_account_info = next_account_info(account_info_iter)?;
         let multisig_account_info = next_account_info(account_info_iter)?;
         let recipient_account_info = next_account_info(account_info_iter)?;
@@ -140,7 +355,7 @@ impl Processor {
         // Get the rent sysvar
         let rent = Rent::get()?;
 
-        if !(wallet_account_info.is_signer && transaction_account_info.is_signer) {
+        if !proposer_account_info.is_signer {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
@@ -151,66 +366,275 @@ impl Processor {
             return Err(ProgramError::UninitializedAccount);
         }
 
+        // Multisigs created before nonces have no room to store one and keep
+        // handing out nonce zero
+        let has_nonce = Account::has_nonce(multisig_account_info.data_len());
+        let nonce = if has_nonce {
+            multisig_account_data
+                .nonce
+                .checked_add(1)
+                .ok_or(MultisigError::AmountOverflow)?
+        } else {
+            0
+        };
+
+        // The transaction account is either derived from its nonce or a
+        // fresh keypair signing for itself
+        let (transaction_address, bump_seed) =
+            get_transaction_address_and_bump_seed(multisig_account_info.key, nonce, program_id);
+        let is_derived = has_nonce && transaction_address == *transaction_account_info.key;
+        if !is_derived && !transaction_account_info.is_signer {
+            msg!("Error: Transaction account must sign or be derived from the next nonce");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // The wallet can always propose, owners only if the policy allows
         let (pda, _nonce) =
-            Pubkey::find_program_address(&[&wallet_account_info.key.to_bytes()], program_id);
+            Pubkey::find_program_address(&[&proposer_account_info.key.to_bytes()], program_id);
 
-        if pda != *multisig_account_info.key {
-            return Err(MultisigError::UndefinedTransaction.into());
+        let is_proposer = pda == *multisig_account_info.key
+            || match &multisig_account_data.proposer_policy {
+                ProposerPolicy::Wallet => false,
+                ProposerPolicy::AnyOwner => multisig_account_data
+                    .owners
+                    .contains(proposer_account_info.key),
+                ProposerPolicy::Allowlist(proposers) => {
+                    proposers.contains(proposer_account_info.key)
+                }
+            };
+
+        if !is_proposer {
+            return Err(MultisigError::InvalidProposer.into());
+        }
+
+        if multisig_account_data.is_frozen {
+            return Err(MultisigError::WalletFrozen.into());
+        }
+
+        // Sweeps and instructions have no upper bound, so limited owners
+        // can't propose them
+        if let Some(limit) = multisig_account_data.spend_limit(proposer_account_info.key) {
+            if is_sweep || instruction.is_some() || amount > limit {
+                return Err(MultisigError::SpendLimitExceeded.into());
+            }
+        }
+
+        // Sending lamports back to the multisig only freezes them
+        if *recipient_account_info.key == *multisig_account_info.key
+            || outputs
+                .iter()
+                .any(|(recipient, _)| recipient == multisig_account_info.key)
+        {
+            return Err(MultisigError::SelfTransfer.into());
+        }
+
+        // Sweeps and instructions carry no amount of their own
+        if amount == 0 && !is_sweep && instruction.is_none() {
+            return Err(MultisigError::ZeroAmount.into());
+        }
+
+        // The recipient account of a multi transfer is its first output
+        if matches!(outputs.first(), Some((recipient, _)) if recipient != recipient_account_info.key)
+        {
+            return Err(MultisigError::InvalidRecipient.into());
+        }
+
+        // The recipient account of an instruction is its program
+        if matches!(&instruction, Some(instruction) if instruction.program_id != *recipient_account_info.key)
+        {
+            return Err(MultisigError::InvalidRecipient.into());
         }
 
-        if multisig_account_data.pending_transactions.len() >= MAX_TRANSACTIONS {
+        if multisig_account_data.pending_transactions.len()
+            >= multisig_account_data.max_pending as usize
+        {
             return Err(MultisigError::PendingTransactionLimit.into());
         }
 
-        if multisig_account_data.frozen_amount + amount > multisig_account_info.lamports() {
+        // A transaction must be executable for at least one slot
+        let slot = Clock::get()?.slot;
+        if expires_at_slot != 0 && expires_at_slot <= not_before_slot.max(slot) {
+            return Err(MultisigError::TransactionExpired.into());
+        }
+
+        let available =
+            Self::available_balance(multisig_account_info, &multisig_account_data, &rent);
+
+        // Soft proposals freeze nothing until they are promoted
+        let freeze_amount = if soft { 0 } else { amount };
+
+        // Checked before the balance so an overflowing amount is reported as such
+        let frozen_amount = multisig_account_data
+            .frozen_amount
+            .checked_add(freeze_amount)
+            .ok_or(MultisigError::AmountOverflow)?;
+
+        if freeze_amount > multisig_account_data.spendable(available) {
             return Err(MultisigError::InsufficientBalance.into());
         }
 
-        let transaction_account_data = Transaction {
+        let mut transaction_account_data = Transaction {
             multisig: *multisig_account_info.key,
             recipient: *recipient_account_info.key,
             amount,
-            is_executed: false,
-            signers: multisig_account_data
-                .owners
-                .clone()
-                .into_iter()
-                .map(|owner| (owner, false))
-                .collect(),
+            status: TransactionStatus::Pending,
+            is_sweep,
+            // Strict multisigs track approvals in a bitmap over the live owners
+            signers: if multisig_account_data.strict_owner_match {
+                vec![]
+            } else {
+                multisig_account_data
+                    .owners
+                    .clone()
+                    .into_iter()
+                    .map(|owner| (owner, false))
+                    .collect()
+            },
+            memo,
+            outputs,
+            not_before_slot,
+            expires_at_slot,
+            is_soft: soft,
+            approvals: 0,
+            owners_version: multisig_account_data.owners_version,
+            nonce,
+            instruction,
+            creator: *proposer_account_info.key,
+            reached_threshold_slot: None,
+        };
+
+        // Only an owner's own approval can be recorded up front
+        let proposer_index = multisig_account_data
+            .owners
+            .iter()
+            .position(|owner| owner == proposer_account_info.key);
+        if let (true, Some(index)) = (auto_approve, proposer_index) {
+            if multisig_account_data
+                .in_grace(proposer_account_info.key, Clock::get()?.unix_timestamp)
+            {
+                return Err(MultisigError::OwnerInGrace.into());
+            }
+            if multisig_account_data.strict_owner_match {
+                transaction_account_data.set_approval(&multisig_account_data, index, true);
+            } else {
+                transaction_account_data.signers[index].1 = true;
+            }
+        }
+        transaction_account_data.record_threshold(&multisig_account_data, slot);
+
+        // A single transfer the proposer alone can approve is executed
+        // right away instead of being left pending
+        let execute_now = auto_approve
+            && multisig_account_data.execution_delay_slots == 0
+            && !soft
+            && !is_sweep
+            && transaction_account_data.outputs.is_empty()
+            && transaction_account_data.instruction.is_none()
+            && slot >= not_before_slot
+            && multisig_account_data
+                .approval_weight(&transaction_account_data.signer_status(&multisig_account_data))
+                >= multisig_account_data.threshold;
+
+        if execute_now && !recipient_account_info.is_writable {
+            msg!("Error: Recipient account must be writable");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Lamports sent to a derived address in advance must not block its
+        // creation, so the account is topped up instead of created
+        let data_len = transaction_account_data.data_len();
+        let required_lamports = rent
+            .minimum_balance(data_len)
+            .saturating_sub(transaction_account_info.lamports());
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    proposer_account_info.key,
+                    transaction_account_info.key,
+                    required_lamports,
+                ),
+                &[
+                    proposer_account_info.clone(),
+                    transaction_account_info.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+
+        let nonce_bytes = nonce.to_le_bytes();
+        let transaction_seeds: &[&[u8]] = &[
+            TRANSACTION_SEED,
+            &multisig_account_info.key.to_bytes(),
+            &nonce_bytes,
+            &[bump_seed],
+        ];
+        let signer_seeds: &[&[&[u8]]] = if is_derived {
+            &[transaction_seeds]
+        } else {
+            &[]
         };
 
-        invoke(
-            &system_instruction::create_account(
-                wallet_account_info.key,
-                transaction_account_info.key,
-                rent.minimum_balance(Transaction::LEN),
-                Transaction::LEN as u64,
-                program_id,
-            ),
+        invoke_signed(
+            &system_instruction::allocate(transaction_account_info.key, data_len as u64),
             &[
-                wallet_account_info.clone(),
                 transaction_account_info.clone(),
                 system_program_account.clone(),
             ],
+            signer_seeds,
         )?;
 
-        invoke(
+        invoke_signed(
             &system_instruction::assign(transaction_account_info.key, program_id),
             &[
                 transaction_account_info.clone(),
                 system_program_account.clone(),
             ],
+            signer_seeds,
         )?;
 
-        multisig_account_data.frozen_amount += amount;
-        multisig_account_data
-            .pending_transactions
-            .push(*transaction_account_info.key);
+        multisig_account_data.nonce = nonce;
+        if execute_now {
+            **multisig_account_info.try_borrow_mut_lamports()? -= amount;
+            **recipient_account_info.try_borrow_mut_lamports()? += amount;
+            transaction_account_data.status = TransactionStatus::Executed;
+        } else {
+            multisig_account_data.frozen_amount = frozen_amount;
+            multisig_account_data
+                .pending_transactions
+                .push(*transaction_account_info.key);
+        }
+
+        Self::check_frozen_covered(multisig_account_info, &multisig_account_data)?;
 
+        multisig_account_data.debug_assert_invariants();
+        transaction_account_data.debug_assert_invariants(&multisig_account_data);
+
+        let signer_status = transaction_account_data.signer_status(&multisig_account_data);
+
+        multisig_account_data.last_updated_slot = slot;
         Account::pack(
             multisig_account_data,
             &mut multisig_account_info.data.borrow_mut(),
         )?;
+        TransactionCreatedEvent {
+            multisig: *multisig_account_info.key,
+            transaction: *transaction_account_info.key,
+            recipient: transaction_account_data.recipient,
+            amount: transaction_account_data.amount,
+            is_sweep: transaction_account_data.is_sweep,
+        }
+        .emit()?;
+        PendingSignersEvent::new(*transaction_account_info.key, &signer_status).emit()?;
+        if execute_now {
+            TransactionExecutedEvent {
+                multisig: *multisig_account_info.key,
+                transaction: *transaction_account_info.key,
+                recipient: transaction_account_data.recipient,
+                amount: transaction_account_data.amount,
+            }
+            .emit()?;
+        }
+
         Transaction::pack(
             transaction_account_data,
             &mut transaction_account_info.data.borrow_mut(),
@@ -219,9 +643,176 @@ impl Processor {
         Ok(())
     }
 
+    fn process_create_multi_transfer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        outputs: Vec<(Pubkey, u64)>,
+        memo: String,
+    ) -> ProgramResult {
+        if outputs.is_empty() || outputs.len() > MAX_OUTPUTS {
+            return Err(MultisigError::OutputLimit.into());
+        }
+
+        // Outputs are frozen as a unit
+        let amount = outputs.iter().try_fold(0u64, |total, (_, amount)| {
+            total
+                .checked_add(*amount)
+                .ok_or(MultisigError::AmountOverflow)
+        })?;
+
+        Self::process_create_transaction(
+            program_id, accounts, amount, memo, false, outputs, 0, 0, false, false, None,
+        )
+    }
+
+    fn process_create_instruction_transaction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction: InnerInstruction,
+        memo: String,
+    ) -> ProgramResult {
+        // The multisig must not be able to call back into itself
+        if !instruction.is_bounded() || instruction.program_id == *program_id {
+            return Err(MultisigError::InvalidInstruction.into());
+        }
+
+        // Only the authority may sign, the signature of any other account
+        // would be lent by whoever passes it on execution
+        let multisig_account_info = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let (authority_address, _) =
+            get_authority_address_and_bump_seed(multisig_account_info.key, program_id);
+        if instruction
+            .accounts
+            .iter()
+            .any(|meta| meta.is_signer && meta.pubkey != authority_address)
+        {
+            return Err(MultisigError::InvalidInstruction.into());
+        }
+
+        Self::process_create_transaction(
+            program_id,
+            accounts,
+            0,
+            memo,
+            false,
+            vec![],
+            0,
+            0,
+            false,
+            false,
+            Some(instruction),
+        )
+    }
+
     fn process_approve_transaction(accounts: &[AccountInfo]) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
 
+        let wallet_account_info = next_account_info(account_info_iter)?;
+        let multisig_account_info = next_account_info(account_info_iter)?;
+        let transaction_account_info = next_account_info(account_info_iter)?;
+
+        if !wallet_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        Self::check_distinct_accounts(&[
+            wallet_account_info,
+            multisig_account_info,
+            transaction_account_info,
+        ])?;
+
+        if !transaction_account_info.is_writable {
+            msg!("Error: Transaction account must be writable");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut transaction_info =
+            Transaction::unpack_unchecked(&transaction_account_info.data.borrow())?;
+
+        // Executed transaction can never be approved again,
+        // even if it is still referenced by the multisig
+        Self::check_pending(&transaction_info)?;
+
+        // Expired transactions can only be reaped
+        let slot = Clock::get()?.slot;
+        if transaction_info.is_expired(slot) {
+            return Err(MultisigError::TransactionExpired.into());
+        }
+
+        let multisig_info = Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+        if !multisig_info
+            .pending_transactions
+            .contains(transaction_account_info.key)
+        {
+            return Err(MultisigError::UndefinedTransaction.into());
+        }
+
+        // A stale multisig may still list the transaction
+        if transaction_info.multisig != *multisig_account_info.key {
+            return Err(MultisigError::UndefinedTransaction.into());
+        }
+
+        // Only nonces the multisig handed out can be approved
+        if transaction_info.nonce > multisig_info.nonce {
+            return Err(MultisigError::InvalidNonce.into());
+        }
+
+        // A repeated approval changes nothing but still costs a fee
+        if transaction_info.signed_by(&multisig_info, wallet_account_info.key) == Some(true) {
+            return Err(MultisigError::AlreadyApproved.into());
+        }
+
+        // Owners added recently can't approve until their grace window ends
+        if multisig_info.in_grace(wallet_account_info.key, Clock::get()?.unix_timestamp) {
+            return Err(MultisigError::OwnerInGrace.into());
+        }
+
+        if multisig_info.strict_owner_match {
+            let index = multisig_info
+                .owners
+                .iter()
+                .position(|owner| owner == wallet_account_info.key)
+                .ok_or(MultisigError::InvalidCustodian)?;
+            transaction_info.set_approval(&multisig_info, index, true);
+        } else {
+            transaction_info
+                .signers
+                .iter_mut()
+                .position(|(key, is_signed)| {
+                    if key == wallet_account_info.key {
+                        *is_signed = true;
+                        true
+                    } else {
+                        false
+                    }
+                })
+                .ok_or(MultisigError::InvalidCustodian)?;
+        }
+        transaction_info.record_threshold(&multisig_info, slot);
+
+        transaction_info.debug_assert_invariants(&multisig_info);
+
+        let signer_status = transaction_info.signer_status(&multisig_info);
+        ApprovalRecordedEvent::new(
+            *multisig_account_info.key,
+            *transaction_account_info.key,
+            *wallet_account_info.key,
+            transaction_info.approval_count(&multisig_info),
+        )
+        .emit()?;
+        PendingSignersEvent::new(*transaction_account_info.key, &signer_status).emit()?;
+
+        Transaction::pack(
+            transaction_info,
+            &mut transaction_account_info.data.borrow_mut(),
+        )?;
+
+        Ok(())
+    }
+
+    fn process_execute_transaction(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
         let wallet_account_info = next_account_info(account_info_iter)?;
         let multisig_account_info = next_account_info(account_info_iter)?;
         let transaction_account_info = next_account_info(account_info_iter)?;
@@ -231,60 +822,1142 @@ impl Processor {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
+        // Recipients may be owners, but never the multisig or transaction
+        Self::check_distinct_accounts(&[
+            wallet_account_info,
+            multisig_account_info,
+            transaction_account_info,
+        ])?;
+        for account_info in &accounts[3..] {
+            Self::check_distinct_accounts(&[
+                multisig_account_info,
+                transaction_account_info,
+                account_info,
+            ])?;
+        }
+
+        if !(multisig_account_info.is_writable && transaction_account_info.is_writable) {
+            msg!("Error: Multisig and transaction accounts must be writable");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut transaction_info =
+            Transaction::unpack_unchecked(&transaction_account_info.data.borrow())?;
+
+        Self::check_pending(&transaction_info)?;
+
+        if transaction_info.is_soft {
+            return Err(MultisigError::SoftProposal.into());
+        }
+
+        if *recipient_account_info.key != transaction_info.recipient {
+            return Err(MultisigError::InvalidRecipient.into());
+        }
+
+        // Multi transfers pass every output recipient in order, starting
+        // with `recipient`. All are checked before any lamports move.
+        let output_account_infos = &accounts[3..];
+        if !transaction_info.outputs.is_empty()
+            && (output_account_infos.len() < transaction_info.outputs.len()
+                || output_account_infos
+                    .iter()
+                    .zip(&transaction_info.outputs)
+                    .any(|(account_info, (recipient, _))| account_info.key != recipient))
+        {
+            return Err(MultisigError::InvalidRecipient.into());
+        }
+
+        // Instructions pass their program as `recipient`, followed by
+        // every instruction account in order
+        if let Some(instruction) = &transaction_info.instruction {
+            let meta_account_infos = &accounts[4..];
+            if meta_account_infos.len() < instruction.accounts.len()
+                || meta_account_infos
+                    .iter()
+                    .zip(&instruction.accounts)
+                    .any(|(account_info, meta)| *account_info.key != meta.pubkey)
+            {
+                return Err(MultisigError::InvalidRecipient.into());
+            }
+        }
+
         let mut multisig_info = Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+        if !multisig_info.owners.contains(wallet_account_info.key) {
+            return Err(MultisigError::InvalidCustodian.into());
+        }
+
+        // Approvals still count while frozen, only execution waits
+        if multisig_info.is_frozen {
+            return Err(MultisigError::WalletFrozen.into());
+        }
+
         let transaction_index = multisig_info
             .pending_transactions
             .iter()
             .position(|x| x == transaction_account_info.key)
             .ok_or(MultisigError::UndefinedTransaction)?;
 
-        let mut transaction_info =
-            Transaction::unpack_unchecked(&transaction_account_info.data.borrow())?;
+        if multisig_info.approval_weight(&transaction_info.signer_status(&multisig_info))
+            < multisig_info.threshold
+        {
+            return Err(MultisigError::NotEnoughApprovals.into());
+        }
 
-        if transaction_info.is_executed {
-            return Err(MultisigError::TransactionAlreadyExecuted.into());
+        let slot = Clock::get()?.slot;
+        if slot < transaction_info.not_before_slot {
+            return Err(MultisigError::TimelockActive.into());
+        }
+        if transaction_info.is_expired(slot) {
+            return Err(MultisigError::TransactionExpired.into());
         }
 
-        transaction_info
-            .signers
-            .iter_mut()
-            .position(|(key, is_signed)| {
-                if key == wallet_account_info.key {
-                    *is_signed = true;
-                    true
-                } else {
-                    false
-                }
-            })
-            .ok_or(MultisigError::InvalidCustodian)?;
+        // Owners get a window to react once the threshold is reached
+        if let Some(reached_threshold_slot) = transaction_info.reached_threshold_slot {
+            if slot < reached_threshold_slot.saturating_add(multisig_info.execution_delay_slots) {
+                return Err(MultisigError::ExecutionDelayActive.into());
+            }
+        }
 
-        let signers_count = transaction_info
-            .signers
-            .iter()
-            .filter(|(_, is_signed)| *is_signed)
-            .count() as u32;
+        Self::unfreeze(&mut multisig_info, &transaction_info)?;
 
-        if multisig_info.threshold >= signers_count {
-            // Make lamports transfer
+        if transaction_info.is_sweep {
+            // Sweep everything that is not frozen by other transactions
+            let rent = Rent::get()?;
+            transaction_info.amount = multisig_info.spendable(Self::available_balance(
+                multisig_account_info,
+                &multisig_info,
+                &rent,
+            ));
+        }
+
+        // Make lamports transfer
+        let instruction = transaction_info.instruction.clone();
+        if instruction.is_some() {
+            // Invoked once the transaction is marked as executed
+        } else if transaction_info.outputs.is_empty() {
             **multisig_account_info.try_borrow_mut_lamports()? -= transaction_info.amount;
             **recipient_account_info.try_borrow_mut_lamports()? += transaction_info.amount;
+        } else {
+            for (output_account_info, (_, amount)) in
+                output_account_infos.iter().zip(&transaction_info.outputs)
+            {
+                **multisig_account_info.try_borrow_mut_lamports()? -= amount;
+                **output_account_info.try_borrow_mut_lamports()? += amount;
+            }
+        }
 
-            // Mark as executable
-            transaction_info.is_executed = true;
+        // Mark as executed
+        transaction_info.status = TransactionStatus::Executed;
 
-            // Unlock frozen lamports
-            multisig_info.frozen_amount -= transaction_info.amount;
+        // Remove from pending list
+        multisig_info.pending_transactions.remove(transaction_index);
 
-            // Remove from pending list
-            multisig_info.pending_transactions.remove(transaction_index);
-        }
+        Self::check_frozen_covered(multisig_account_info, &multisig_info)?;
+
+        multisig_info.debug_assert_invariants();
+        transaction_info.debug_assert_invariants(&multisig_info);
+
+        let event = TransactionExecutedEvent {
+            multisig: *multisig_account_info.key,
+            transaction: *transaction_account_info.key,
+            recipient: transaction_info.recipient,
+            amount: transaction_info.amount,
+        };
 
+        multisig_info.last_updated_slot = slot;
         Account::pack(multisig_info, &mut multisig_account_info.data.borrow_mut())?;
         Transaction::pack(
             transaction_info,
             &mut transaction_account_info.data.borrow_mut(),
         )?;
 
+        // The transaction is already marked as executed, so the invoked
+        // program can't execute it again
+        if let Some(instruction) = instruction {
+            let (_, bump_seed) =
+                get_authority_address_and_bump_seed(multisig_account_info.key, program_id);
+            invoke_signed(
+                &Instruction {
+                    program_id: instruction.program_id,
+                    accounts: instruction
+                        .accounts
+                        .iter()
+                        .map(|meta| AccountMeta {
+                            pubkey: meta.pubkey,
+                            is_signer: meta.is_signer,
+                            is_writable: meta.is_writable,
+                        })
+                        .collect(),
+                    data: instruction.data,
+                },
+                &accounts[3..],
+                &[&[
+                    AUTHORITY_SEED,
+                    &multisig_account_info.key.to_bytes(),
+                    &[bump_seed],
+                ]],
+            )?;
+        }
+
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_set_reserve(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        reserved_balance: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let wallet_account_info = next_account_info(account_info_iter)?;
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        if !wallet_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (pda, _nonce) =
+            Pubkey::find_program_address(&[&wallet_account_info.key.to_bytes()], program_id);
+
+        if pda != *multisig_account_info.key {
+            msg!("Error: Associated address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        multisig_account_data.reserved_balance = reserved_balance;
+
+        multisig_account_data.debug_assert_invariants();
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+
         Ok(())
     }
+
+    fn process_revoke_approval(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let wallet_account_info = next_account_info(account_info_iter)?;
+        let multisig_account_info = next_account_info(account_info_iter)?;
+        let transaction_account_info = next_account_info(account_info_iter)?;
+
+        if !wallet_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        Self::check_distinct_accounts(&[
+            wallet_account_info,
+            multisig_account_info,
+            transaction_account_info,
+        ])?;
+
+        let mut transaction_info =
+            Transaction::unpack_unchecked(&transaction_account_info.data.borrow())?;
+
+        Self::check_pending(&transaction_info)?;
+
+        let multisig_info = Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+        if !multisig_info
+            .pending_transactions
+            .contains(transaction_account_info.key)
+        {
+            return Err(MultisigError::UndefinedTransaction.into());
+        }
+
+        if multisig_info.strict_owner_match {
+            let index = multisig_info
+                .owners
+                .iter()
+                .position(|owner| owner == wallet_account_info.key)
+                .ok_or(MultisigError::InvalidCustodian)?;
+            transaction_info.set_approval(&multisig_info, index, false);
+        } else {
+            transaction_info
+                .signers
+                .iter_mut()
+                .position(|(key, is_signed)| {
+                    if key == wallet_account_info.key {
+                        *is_signed = false;
+                        true
+                    } else {
+                        false
+                    }
+                })
+                .ok_or(MultisigError::InvalidCustodian)?;
+        }
+
+        transaction_info.debug_assert_invariants(&multisig_info);
+
+        PendingSignersEvent::new(
+            *transaction_account_info.key,
+            &transaction_info.signer_status(&multisig_info),
+        )
+        .emit()?;
+
+        Transaction::pack(
+            transaction_info,
+            &mut transaction_account_info.data.borrow_mut(),
+        )?;
+
+        Ok(())
+    }
+
+    fn process_change_threshold(accounts: &[AccountInfo], new_threshold: u32) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+
+        if new_threshold == 0 || new_threshold > multisig_account_data.total_weight() {
+            return Err(MultisigError::CustodianLimit.into());
+        }
+
+        // Pending transactions aren't re-evaluated here, that would need their
+        // accounts and recipients. Those reaching a lowered threshold with their
+        // existing approvals wait for an explicit `ExecuteTransaction`
+        multisig_account_data.threshold = new_threshold;
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_set_proposer_policy(
+        accounts: &[AccountInfo],
+        proposer_policy: ProposerPolicy,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+
+        if let ProposerPolicy::Allowlist(proposers) = &proposer_policy {
+            if !proposers
+                .iter()
+                .all(|proposer| multisig_account_data.owners.contains(proposer))
+            {
+                return Err(MultisigError::InvalidCustodian.into());
+            }
+
+            let mut unique_proposers = proposers.clone();
+            unique_proposers.sort();
+            unique_proposers.dedup();
+            if unique_proposers.len() != proposers.len() {
+                return Err(MultisigError::DuplicateOwner.into());
+            }
+        }
+
+        multisig_account_data.proposer_policy = proposer_policy;
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_set_spend_limit(
+        accounts: &[AccountInfo],
+        owner: Pubkey,
+        limit: Option<u64>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+
+        if !multisig_account_data.owners.contains(&owner) {
+            return Err(MultisigError::InvalidCustodian.into());
+        }
+
+        multisig_account_data
+            .spend_limits
+            .retain(|(limited_owner, _)| *limited_owner != owner);
+        if let Some(limit) = limit {
+            multisig_account_data.spend_limits.push((owner, limit));
+        }
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_set_strict_owner_match(accounts: &[AccountInfo], enabled: bool) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+
+        // Pending transactions keep the signer tracking they were created with
+        if !multisig_account_data.pending_transactions.is_empty() {
+            return Err(MultisigError::PendingTransactionsExist.into());
+        }
+
+        multisig_account_data.strict_owner_match = enabled;
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_set_frozen(accounts: &[AccountInfo], frozen: bool) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        // Any owner can pull the emergency brake, releasing it takes the threshold
+        if frozen {
+            if !account_info_iter
+                .any(|signer| signer.is_signer && multisig_account_data.owners.contains(signer.key))
+            {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        } else {
+            Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+        }
+
+        multisig_account_data.is_frozen = frozen;
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_reconcile(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        // Every pending transaction is passed in order, ahead of the owners
+        let mut pending_transactions = Vec::with_capacity(MAX_TRANSACTIONS);
+        let mut frozen_amount = 0u64;
+        for pending_transaction in &multisig_account_data.pending_transactions {
+            let transaction_account_info = next_account_info(account_info_iter)?;
+            if transaction_account_info.key != pending_transaction {
+                return Err(MultisigError::UndefinedTransaction.into());
+            }
+
+            // A closed or foreign account no longer holds a transaction
+            // of this multisig and is dropped from the pending list
+            let transaction_info = if transaction_account_info.owner == program_id {
+                Transaction::unpack_unchecked(&transaction_account_info.data.borrow()).ok()
+            } else {
+                None
+            };
+            let transaction_info = match transaction_info {
+                Some(transaction_info)
+                    if transaction_info.multisig == *multisig_account_info.key
+                        && transaction_info.status == TransactionStatus::Pending =>
+                {
+                    transaction_info
+                }
+                _ => {
+                    msg!("Dropping closed transaction {}", pending_transaction);
+                    continue;
+                }
+            };
+
+            // Soft proposals freeze nothing until they are promoted
+            if !transaction_info.is_soft {
+                frozen_amount = frozen_amount
+                    .checked_add(transaction_info.amount)
+                    .ok_or(MultisigError::AmountOverflow)?;
+            }
+            pending_transactions.push(*pending_transaction);
+        }
+
+        Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+
+        if frozen_amount != multisig_account_data.frozen_amount {
+            msg!(
+                "Frozen amount corrected from {} to {}",
+                multisig_account_data.frozen_amount,
+                frozen_amount
+            );
+        }
+        multisig_account_data.frozen_amount = frozen_amount;
+        multisig_account_data.pending_transactions = pending_transactions;
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_set_execution_delay(accounts: &[AccountInfo], slots: u64) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+
+        // Transactions that reached the threshold before a delay was set
+        // have no slot recorded and aren't held back
+        multisig_account_data.execution_delay_slots = slots;
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_set_owner_grace(accounts: &[AccountInfo], secs: i64) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+
+        if secs < 0 {
+            msg!("Error: Grace period can't be negative");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Applies to owners added before as well, measured from their addition
+        multisig_account_data.new_owner_grace_secs = secs;
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_add_owner(accounts: &[AccountInfo], owner: Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+        let funder_account_info = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        Self::check_owners_change(&multisig_account_data, account_info_iter.as_slice())?;
+
+        if multisig_account_data.owners.len() >= MAX_SIGNERS {
+            return Err(MultisigError::CustodianLimit.into());
+        }
+
+        if multisig_account_data.owners.contains(&owner) {
+            return Err(MultisigError::DuplicateOwner.into());
+        }
+
+        if multisig_account_data.is_weighted() {
+            msg!("Error: Weighted multisig owners can't be added without a weight");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // First release accounts have less room and are grown once full
+        let data_len = multisig_account_info.data_len();
+        if multisig_account_data.owners.len() >= Account::max_owners(data_len) {
+            let rent = Rent::get()?;
+            let required_lamports = rent
+                .minimum_balance(Account::LEN)
+                .saturating_sub(rent.minimum_balance(data_len));
+            invoke(
+                &system_instruction::transfer(
+                    funder_account_info.key,
+                    multisig_account_info.key,
+                    required_lamports,
+                ),
+                &[
+                    funder_account_info.clone(),
+                    multisig_account_info.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+
+            multisig_account_info.realloc(Account::LEN, true)?;
+        }
+
+        let owners_len = multisig_account_data.owners.len();
+        multisig_account_data.owner_added_at.resize(owners_len, 0);
+        multisig_account_data
+            .owner_added_at
+            .push(Clock::get()?.unix_timestamp);
+        multisig_account_data.owners.push(owner);
+        multisig_account_data.owners_version = multisig_account_data.owners_version.wrapping_add(1);
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_remove_owner(accounts: &[AccountInfo], owner: Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        Self::check_owners_change(&multisig_account_data, account_info_iter.as_slice())?;
+
+        let index = multisig_account_data
+            .owners
+            .iter()
+            .position(|x| *x == owner)
+            .ok_or(MultisigError::InvalidCustodian)?;
+
+        let remaining_weight =
+            multisig_account_data.total_weight() - multisig_account_data.owner_weight(&owner);
+        if remaining_weight < multisig_account_data.threshold {
+            return Err(MultisigError::CustodianLimit.into());
+        }
+
+        multisig_account_data.owners.remove(index);
+        if multisig_account_data.is_weighted() {
+            multisig_account_data.owner_weights.remove(index);
+        }
+        if index < multisig_account_data.owner_added_at.len() {
+            multisig_account_data.owner_added_at.remove(index);
+        }
+        multisig_account_data.owners_version = multisig_account_data.owners_version.wrapping_add(1);
+
+        // Removed owner can't propose anymore
+        if let ProposerPolicy::Allowlist(proposers) = &mut multisig_account_data.proposer_policy {
+            proposers.retain(|proposer| *proposer != owner);
+        }
+        multisig_account_data
+            .spend_limits
+            .retain(|(limited_owner, _)| *limited_owner != owner);
+
+        multisig_account_data.debug_assert_invariants();
+
+        let event = MultisigUpdatedEvent::new(*multisig_account_info.key, &multisig_account_data);
+
+        multisig_account_data.last_updated_slot = Clock::get()?.slot;
+        Account::pack(
+            multisig_account_data,
+            &mut multisig_account_info.data.borrow_mut(),
+        )?;
+        event.emit()?;
+
+        Ok(())
+    }
+
+    fn process_close_account(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+        let destination_account_info = next_account_info(account_info_iter)?;
+
+        let multisig_account_data =
+            Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_account_data.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(&multisig_account_data, account_info_iter.as_slice())?;
+
+        if !multisig_account_data.pending_transactions.is_empty()
+            || multisig_account_data.frozen_amount != 0
+        {
+            return Err(MultisigError::PendingTransactionsExist.into());
+        }
+
+        // Move all lamports out, the runtime purges the emptied account
+        let lamports = multisig_account_info.lamports();
+        **multisig_account_info.try_borrow_mut_lamports()? = 0;
+        **destination_account_info.try_borrow_mut_lamports()? = destination_account_info
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(MultisigError::AmountOverflow)?;
+
+        multisig_account_info.data.borrow_mut().fill(0);
+
+        Ok(())
+    }
+
+    fn process_close_transaction(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let owner_account_info = next_account_info(account_info_iter)?;
+        let multisig_account_info = next_account_info(account_info_iter)?;
+        let transaction_account_info = next_account_info(account_info_iter)?;
+        let destination_account_info = next_account_info(account_info_iter)?;
+
+        if !owner_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let transaction_info =
+            Transaction::unpack_unchecked(&transaction_account_info.data.borrow())?;
+        if transaction_info.multisig != *multisig_account_info.key {
+            return Err(MultisigError::UndefinedTransaction.into());
+        }
+
+        let multisig_info = Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+        if !multisig_info.owners.contains(owner_account_info.key) {
+            return Err(MultisigError::InvalidCustodian.into());
+        }
+
+        if transaction_info.status != TransactionStatus::Executed
+            || multisig_info
+                .pending_transactions
+                .contains(transaction_account_info.key)
+        {
+            return Err(MultisigError::TransactionNotExecuted.into());
+        }
+
+        let lamports = transaction_account_info.lamports();
+        **transaction_account_info.try_borrow_mut_lamports()? = 0;
+        **destination_account_info.try_borrow_mut_lamports()? = destination_account_info
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(MultisigError::AmountOverflow)?;
+
+        transaction_account_info.data.borrow_mut().fill(0);
+
+        Ok(())
+    }
+
+    fn process_reap_expired(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let owner_account_info = next_account_info(account_info_iter)?;
+        let multisig_account_info = next_account_info(account_info_iter)?;
+        let transaction_account_info = next_account_info(account_info_iter)?;
+        let destination_account_info = next_account_info(account_info_iter)?;
+
+        if !owner_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let mut transaction_info =
+            Transaction::unpack_unchecked(&transaction_account_info.data.borrow())?;
+        if transaction_info.multisig != *multisig_account_info.key {
+            return Err(MultisigError::UndefinedTransaction.into());
+        }
+
+        let mut multisig_info = Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+        if !multisig_info.owners.contains(owner_account_info.key) {
+            return Err(MultisigError::InvalidCustodian.into());
+        }
+
+        let transaction_index = multisig_info
+            .pending_transactions
+            .iter()
+            .position(|x| x == transaction_account_info.key)
+            .ok_or(MultisigError::UndefinedTransaction)?;
+
+        Self::check_pending(&transaction_info)?;
+
+        if !transaction_info.is_expired(Clock::get()?.slot) {
+            return Err(MultisigError::TransactionNotExpired.into());
+        }
+
+        Self::unfreeze(&mut multisig_info, &transaction_info)?;
+
+        multisig_info.pending_transactions.remove(transaction_index);
+
+        multisig_info.debug_assert_invariants();
+
+        multisig_info.last_updated_slot = Clock::get()?.slot;
+        Account::pack(multisig_info, &mut multisig_account_info.data.borrow_mut())?;
+
+        let lamports = transaction_account_info.lamports();
+        **transaction_account_info.try_borrow_mut_lamports()? = 0;
+        **destination_account_info.try_borrow_mut_lamports()? = destination_account_info
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(MultisigError::AmountOverflow)?;
+
+        // Left marked as expired rather than zeroed, the drained account is
+        // purged once the transaction completes
+        transaction_info.status = TransactionStatus::Expired;
+        Transaction::pack(
+            transaction_info,
+            &mut transaction_account_info.data.borrow_mut(),
+        )?;
+
+        Ok(())
+    }
+
+    fn process_cancel_transaction(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_account_info = next_account_info(account_info_iter)?;
+        let transaction_account_info = next_account_info(account_info_iter)?;
+        let destination_account_info = next_account_info(account_info_iter)?;
+
+        let mut multisig_info = Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+
+        if !multisig_info.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(&multisig_info, account_info_iter.as_slice())?;
+
+        let mut transaction_info =
+            Transaction::unpack_unchecked(&transaction_account_info.data.borrow())?;
+        if transaction_info.multisig != *multisig_account_info.key {
+            return Err(MultisigError::UndefinedTransaction.into());
+        }
+
+        let transaction_index = multisig_info
+            .pending_transactions
+            .iter()
+            .position(|x| x == transaction_account_info.key)
+            .ok_or(MultisigError::UndefinedTransaction)?;
+
+        Self::check_pending(&transaction_info)?;
+
+        Self::unfreeze(&mut multisig_info, &transaction_info)?;
+
+        multisig_info.pending_transactions.remove(transaction_index);
+
+        multisig_info.debug_assert_invariants();
+
+        multisig_info.last_updated_slot = Clock::get()?.slot;
+        Account::pack(multisig_info, &mut multisig_account_info.data.borrow_mut())?;
+
+        let lamports = transaction_account_info.lamports();
+        **transaction_account_info.try_borrow_mut_lamports()? = 0;
+        **destination_account_info.try_borrow_mut_lamports()? = destination_account_info
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(MultisigError::AmountOverflow)?;
+
+        // Marked like a reaped transaction, see `process_reap_expired`
+        transaction_info.status = TransactionStatus::Cancelled;
+        Transaction::pack(
+            transaction_info,
+            &mut transaction_account_info.data.borrow_mut(),
+        )?;
+
+        Ok(())
+    }
+
+    fn process_promote_transaction(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let owner_account_info = next_account_info(account_info_iter)?;
+        let multisig_account_info = next_account_info(account_info_iter)?;
+        let transaction_account_info = next_account_info(account_info_iter)?;
+
+        if !owner_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        Self::check_distinct_accounts(&[
+            owner_account_info,
+            multisig_account_info,
+            transaction_account_info,
+        ])?;
+
+        let mut transaction_info =
+            Transaction::unpack_unchecked(&transaction_account_info.data.borrow())?;
+        if transaction_info.multisig != *multisig_account_info.key {
+            return Err(MultisigError::UndefinedTransaction.into());
+        }
+
+        let mut multisig_info = Account::unpack_unchecked(&multisig_account_info.data.borrow())?;
+        if !multisig_info.owners.contains(owner_account_info.key) {
+            return Err(MultisigError::InvalidCustodian.into());
+        }
+
+        if !multisig_info
+            .pending_transactions
+            .contains(transaction_account_info.key)
+        {
+            return Err(MultisigError::UndefinedTransaction.into());
+        }
+
+        Self::check_pending(&transaction_info)?;
+
+        if !transaction_info.is_soft {
+            msg!("Error: Transaction is already funded");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Consensus on a soft proposal is the same as for executing it
+        if multisig_info.approval_weight(&transaction_info.signer_status(&multisig_info))
+            < multisig_info.threshold
+        {
+            return Err(MultisigError::NotEnoughApprovals.into());
+        }
+
+        let rent = Rent::get()?;
+        let available = Self::available_balance(multisig_account_info, &multisig_info, &rent);
+
+        if transaction_info.amount > multisig_info.spendable(available) {
+            return Err(MultisigError::InsufficientBalance.into());
+        }
+
+        let frozen_amount = multisig_info
+            .frozen_amount
+            .checked_add(transaction_info.amount)
+            .ok_or(MultisigError::AmountOverflow)?;
+
+        multisig_info.frozen_amount = frozen_amount;
+        transaction_info.is_soft = false;
+
+        multisig_info.debug_assert_invariants();
+        transaction_info.debug_assert_invariants(&multisig_info);
+
+        multisig_info.last_updated_slot = Clock::get()?.slot;
+        Account::pack(multisig_info, &mut multisig_account_info.data.borrow_mut())?;
+        Transaction::pack(
+            transaction_info,
+            &mut transaction_account_info.data.borrow_mut(),
+        )?;
+
+        Ok(())
+    }
+
+    // Pending transactions track a signature per owner, so owners can only
+    // be changed when there are none, unless approvals follow the live owners
+    fn check_owners_change(multisig: &Account, signers: &[AccountInfo]) -> ProgramResult {
+        if !multisig.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Self::check_owner_signatures(multisig, signers)?;
+
+        if !multisig.strict_owner_match && !multisig.pending_transactions.is_empty() {
+            return Err(MultisigError::PendingTransactionsExist.into());
+        }
+
+        Ok(())
+    }
+
+    // The runtime passes a repeated account as the same `AccountInfo`,
+    // aliased accounts would overwrite each other's data and lamports
+    fn check_distinct_accounts(accounts: &[&AccountInfo]) -> ProgramResult {
+        for (index, account_info) in accounts.iter().enumerate() {
+            if accounts[..index]
+                .iter()
+                .any(|other| other.key == account_info.key)
+            {
+                msg!(
+                    "Error: Account {} is passed more than once",
+                    account_info.key
+                );
+                return Err(MultisigError::DuplicateAccount.into());
+            }
+        }
+        Ok(())
+    }
+
+    // Configuration changes must be signed by distinct owners reaching the threshold
+    fn check_owner_signatures(multisig: &Account, signers: &[AccountInfo]) -> ProgramResult {
+        let mut owner_signers = signers
+            .iter()
+            .filter(|signer| signer.is_signer && multisig.owners.contains(signer.key))
+            .map(|signer| signer.key)
+            .collect::<Vec<_>>();
+        owner_signers.sort();
+        owner_signers.dedup();
+
+        let signed_weight = owner_signers
+            .iter()
+            .map(|owner| multisig.owner_weight(owner))
+            .sum::<u32>();
+        if signed_weight < multisig.threshold {
+            msg!(
+                "Error: {} of {} owner signature weight",
+                signed_weight,
+                multisig.threshold
+            );
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        Ok(())
+    }
+
+    // Only pending transactions can be approved, revoked, promoted, executed
+    // or reaped
+    fn check_pending(transaction: &Transaction) -> ProgramResult {
+        match transaction.status {
+            TransactionStatus::Pending => Ok(()),
+            TransactionStatus::Executed => Err(MultisigError::TransactionAlreadyExecuted.into()),
+            TransactionStatus::Cancelled => Err(MultisigError::TransactionCancelled.into()),
+            TransactionStatus::Expired => Err(MultisigError::TransactionExpired.into()),
+        }
+    }
+
+    // Unlocks the lamports frozen by a transaction leaving the pending list,
+    // soft proposals never froze any
+    fn unfreeze(multisig: &mut Account, transaction: &Transaction) -> ProgramResult {
+        if !transaction.is_soft {
+            multisig.frozen_amount = multisig
+                .frozen_amount
+                .checked_sub(transaction.amount)
+                .ok_or(MultisigError::AmountOverflow)?;
+        }
+        Ok(())
+    }
+
+    // Frozen lamports must always be covered by the multisig balance
+    fn check_frozen_covered(
+        multisig_account_info: &AccountInfo,
+        multisig: &Account,
+    ) -> ProgramResult {
+        if multisig.frozen_amount > multisig_account_info.lamports() {
+            return Err(MultisigError::InsufficientBalance.into());
+        }
+        Ok(())
+    }
+
+    // Reserved balance and rent-exempt minimum are never spendable
+    fn available_balance(
+        multisig_account_info: &AccountInfo,
+        multisig: &Account,
+        rent: &Rent,
+    ) -> u64 {
+        multisig_account_info
+            .lamports()
+            .saturating_sub(multisig.reserved_balance)
+            .saturating_sub(rent.minimum_balance(multisig_account_info.data_len()))
+    }
 }
@@ -0,0 +1,94 @@
+//! Adapter for Squads-style (v3, Anchor encoded) multisig instructions.
+//!
+//! Squads instruction data starts with the 8 byte Anchor discriminator
+//! `sha256("global:<name>")[..8]` followed by borsh encoded arguments.
+//! The adapter understands a subset of them and maps it onto
+//! `MultisigInstruction`:
+//!
+//! | Squads                                         | Multisig                                 |
+//! |------------------------------------------------|------------------------------------------|
+//! | `create(threshold: u16, create_key, members)`  | `CreateAccount { threshold, owners }`    |
+//! | `approve_transaction()`                        | `ApproveTransaction`                     |
+//! | `execute_transaction(account_list: Vec<u8>)`   | `ExecuteTransaction`                     |
+//!
+//! Limits:
+//! * `create_key` is dropped, the multisig address is derived from the wallet;
+//! * created multisigs take the program-wide `MAX_TRANSACTIONS` pending cap;
+//! * `create_transaction`/`add_instruction` are not supported, multisig
+//!   transactions only transfer lamports and carry the amount up front;
+//! * `execute_transaction` account list is ignored, the recipient is stored
+//!   on the transaction;
+//! * only `approve_transaction` accounts can be remapped, other instructions
+//!   need this program's account layout (see the builders in the crate root).
+
+use borsh::BorshDeserialize;
+
+use solana_program::hash::hash;
+use solana_program::instruction::AccountMeta;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+use crate::instruction::MultisigInstruction;
+use crate::state::MAX_TRANSACTIONS;
+
+#[derive(BorshDeserialize)]
+struct SquadsCreateArgs {
+    threshold: u16,
+    _create_key: Pubkey,
+    members: Vec<Pubkey>,
+}
+
+#[derive(BorshDeserialize)]
+struct SquadsExecuteArgs {
+    _account_list: Vec<u8>,
+}
+
+/// Returns the Anchor discriminator of a Squads instruction.
+pub fn squads_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+/// Maps Squads instruction data onto this program's instruction.
+pub fn unpack_squads_instruction(input: &[u8]) -> Result<MultisigInstruction, ProgramError> {
+    if input.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (discriminator, mut rest) = input.split_at(8);
+
+    Ok(if discriminator == squads_discriminator("create") {
+        let args = SquadsCreateArgs::deserialize(&mut rest)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        MultisigInstruction::CreateAccount {
+            threshold: args.threshold as u32,
+            owners: args.members,
+            max_pending: MAX_TRANSACTIONS as u32,
+        }
+    } else if discriminator == squads_discriminator("approve_transaction") {
+        MultisigInstruction::ApproveTransaction
+    } else if discriminator == squads_discriminator("execute_transaction") {
+        SquadsExecuteArgs::deserialize(&mut rest)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        MultisigInstruction::ExecuteTransaction
+    } else {
+        return Err(ProgramError::InvalidInstructionData);
+    })
+}
+
+/// Reorders Squads `approve_transaction` accounts
+/// `[multisig, transaction, member]` into `[member, multisig, transaction]`.
+pub fn map_squads_approve_accounts(
+    accounts: &[AccountMeta],
+) -> Result<Vec<AccountMeta>, ProgramError> {
+    match accounts {
+        [multisig, transaction, member, ..] => Ok(vec![
+            AccountMeta::new(member.pubkey, true),
+            AccountMeta::new_readonly(multisig.pubkey, false),
+            AccountMeta::new(transaction.pubkey, false),
+        ]),
+        _ => Err(ProgramError::NotEnoughAccountKeys),
+    }
+}
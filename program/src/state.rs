@@ -1,4 +1,5 @@
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
 
 use solana_program::program_error::ProgramError;
 use solana_program::program_pack::{IsInitialized, Pack, Sealed};
@@ -7,17 +8,54 @@ use solana_program::pubkey::{Pubkey, PUBKEY_BYTES};
 /// Minimum number of multisignature signers
 pub const MIN_SIGNERS: usize = 1;
 /// Maximum number of multisignature signers
-pub const MAX_SIGNERS: usize = 8;
-/// Maximum number of simultaneous pending transactions
+pub const MAX_SIGNERS: usize = 32;
+/// Maximum number of signers of accounts created by the first release,
+/// their layout keeps room for this many
+pub const LEGACY_MAX_SIGNERS: usize = 8;
+/// Maximum number of simultaneous pending transactions, the hard cap of
+/// `Account::max_pending`
 pub const MAX_TRANSACTIONS: usize = 10;
+/// Maximum length of a transaction memo in bytes
+pub const MAX_MEMO_LEN: usize = 64;
+/// Maximum number of recipients of a multi transfer
+pub const MAX_OUTPUTS: usize = 4;
+/// Maximum number of accounts of an instruction executed by the multisig
+pub const MAX_INSTRUCTION_ACCOUNTS: usize = 8;
+/// Maximum data length of an instruction executed by the multisig
+pub const MAX_INSTRUCTION_DATA_LEN: usize = 128;
 
 use crate::utils::*;
 
-#[derive(Debug)]
+/// Who may create multisig transactions besides the wallet
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum ProposerPolicy {
+    // Only the wallet the multisig is derived from
+    Wallet,
+    // Any of the multisig owners
+    AnyOwner,
+    // Listed owners only
+    Allowlist(Vec<Pubkey>),
+}
+
+/// Lifecycle of a multisig transaction. Stored in the byte that used to hold
+/// `is_executed`, so accounts written before read as `Pending` or `Executed`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransactionStatus {
+    // Collecting approvals or waiting for execution
+    Pending,
+    // Transferred or invoked, can only be closed
+    Executed,
+    // Withdrawn by the owners before execution
+    Cancelled,
+    // Reaped once `expires_at_slot` passed
+    Expired,
+}
+
+#[derive(Clone, Debug)]
 pub struct Account {
     // Init status
     pub is_initialized: bool,
-    // Required number of signers
+    // Required number of signers, or total weight of signers if weighted
     pub threshold: u32,
     // Custodians of multisig account
     pub owners: Vec<Pubkey>,
@@ -25,6 +63,35 @@ pub struct Account {
     pub pending_transactions: Vec<Pubkey>,
     // Frozen lamports by pending transactions
     pub frozen_amount: u64,
+    // Lamports that can never be spent through the multisig
+    pub reserved_balance: u64,
+    // Who may create transactions
+    pub proposer_policy: ProposerPolicy,
+    // Voting weight of each owner, empty when every owner counts as one
+    pub owner_weights: Vec<u16>,
+    // Approvals are tracked against the live owners, see `Transaction::approvals`
+    pub strict_owner_match: bool,
+    // Bumped on every owner change, voiding strict approvals given before
+    pub owners_version: u32,
+    // Nonce of the last created transaction, zero before the first one
+    pub nonce: u64,
+    // Highest amount an owner may propose, owners without an entry are unlimited
+    pub spend_limits: Vec<(Pubkey, u64)>,
+    // Emergency freeze, no transactions are created or executed while set
+    pub is_frozen: bool,
+    // Slot of the last instruction that changed the account
+    pub last_updated_slot: u64,
+    // Most transactions pending at once, between 1 and MAX_TRANSACTIONS
+    pub max_pending: u32,
+    // Slots a transaction must wait after reaching the threshold before it
+    // can be executed, zero for none
+    pub execution_delay_slots: u64,
+    // Seconds an owner added with `AddOwner` must wait before approving,
+    // zero for none
+    pub new_owner_grace_secs: i64,
+    // Unix timestamp each owner was added at, zero for the owners the
+    // multisig was created with. Empty when no owner was added since.
+    pub owner_added_at: Vec<i64>,
 }
 
 impl Sealed for Account {}
@@ -35,33 +102,370 @@ impl IsInitialized for Account {
     }
 }
 
-const ACCOUNT_LEN: usize = 597;
+impl Account {
+    /// Checks account invariants in debug builds, compiled out in release.
+    pub fn debug_assert_invariants(&self) {
+        debug_assert!(
+            self.owner_weights.is_empty() || self.owner_weights.len() == self.owners.len(),
+            "{} weights for {} owners",
+            self.owner_weights.len(),
+            self.owners.len()
+        );
+        if self.is_weighted() {
+            debug_assert!(
+                self.total_weight() >= self.threshold,
+                "threshold {} exceeds total owner weight {}",
+                self.threshold,
+                self.total_weight()
+            );
+        } else {
+            debug_assert!(
+                self.owners.len() >= self.threshold as usize,
+                "threshold {} exceeds {} owners",
+                self.threshold,
+                self.owners.len()
+            );
+        }
+        debug_assert!(
+            self.owner_added_at.is_empty() || self.owner_added_at.len() == self.owners.len(),
+            "{} addition times for {} owners",
+            self.owner_added_at.len(),
+            self.owners.len()
+        );
+        debug_assert!(
+            self.pending_transactions.len() <= MAX_TRANSACTIONS,
+            "{} pending transactions exceed the limit",
+            self.pending_transactions.len()
+        );
+        debug_assert!(
+            self.pending_transactions.len() <= self.max_pending as usize,
+            "{} pending transactions exceed the cap of {}",
+            self.pending_transactions.len(),
+            self.max_pending
+        );
+        debug_assert!(
+            self.spend_limits
+                .iter()
+                .all(|(owner, _)| self.owners.contains(owner)),
+            "spend limit set for a non-owner"
+        );
+    }
+
+    /// Checks in debug builds that `frozen_amount` is the sum of the pending
+    /// funded transaction amounts. Processors only see one transaction account,
+    /// so this is meant for callers holding all of `pending_transactions`.
+    pub fn debug_assert_frozen_amount(&self, pending_transactions: &[Transaction]) {
+        let pending_amount = pending_transactions
+            .iter()
+            .filter(|transaction| !transaction.is_soft)
+            .map(|transaction| transaction.amount)
+            .sum::<u64>();
+        debug_assert_eq!(
+            self.frozen_amount, pending_amount,
+            "frozen amount doesn't match pending transactions"
+        );
+    }
+
+    /// Returns true if owners vote with individual weights.
+    pub fn is_weighted(&self) -> bool {
+        !self.owner_weights.is_empty()
+    }
+
+    /// Returns the voting weight of `owner`, zero for non-owners.
+    pub fn owner_weight(&self, owner: &Pubkey) -> u32 {
+        match self.owners.iter().position(|x| x == owner) {
+            Some(index) => self
+                .owner_weights
+                .get(index)
+                .map_or(1, |weight| *weight as u32),
+            None => 0,
+        }
+    }
+
+    /// Returns the sum of all owner weights, the highest reachable threshold.
+    pub fn total_weight(&self) -> u32 {
+        self.owners
+            .iter()
+            .map(|owner| self.owner_weight(owner))
+            .sum()
+    }
+
+    /// Returns the part of `lamports` not frozen by pending transactions,
+    /// zero if the frozen amount exceeds it.
+    pub fn spendable(&self, lamports: u64) -> u64 {
+        lamports.saturating_sub(self.frozen_amount)
+    }
+
+    /// Returns the highest amount `proposer` may propose, `None` if unlimited.
+    pub fn spend_limit(&self, proposer: &Pubkey) -> Option<u64> {
+        self.spend_limits
+            .iter()
+            .find(|(owner, _)| owner == proposer)
+            .map(|(_, limit)| *limit)
+    }
+
+    /// Returns true if `owner` was added less than `new_owner_grace_secs`
+    /// before `unix_timestamp` and can't approve yet.
+    pub fn in_grace(&self, owner: &Pubkey, unix_timestamp: i64) -> bool {
+        let added_at = self
+            .owners
+            .iter()
+            .position(|x| x == owner)
+            .and_then(|index| self.owner_added_at.get(index))
+            .copied()
+            .unwrap_or(0);
+        added_at != 0 && unix_timestamp < added_at.saturating_add(self.new_owner_grace_secs)
+    }
+
+    /// Returns the summed weight of the owners in `signers` that signed,
+    /// compared against `threshold` to decide if a transaction is approved.
+    pub fn approval_weight(&self, signers: &[(Pubkey, bool)]) -> u32 {
+        signers
+            .iter()
+            .filter(|(_, is_signed)| *is_signed)
+            .map(|(owner, _)| self.owner_weight(owner))
+            .sum()
+    }
+
+    /// Length of accounts created by the first release
+    pub const LEGACY_LEN: usize = LEGACY_ACCOUNT_LEN;
+
+    /// Returns how many owners fit into account data of `data_len` bytes.
+    pub fn max_owners(data_len: usize) -> usize {
+        if data_len == LEGACY_ACCOUNT_LEN {
+            LEGACY_MAX_SIGNERS
+        } else {
+            MAX_SIGNERS
+        }
+    }
+
+    /// Returns the lengths of every account layout `unpack` accepts, the
+    /// current one first.
+    pub fn layout_lens() -> impl Iterator<Item = usize> {
+        [ACCOUNT_LEN, LEGACY_ACCOUNT_LEN].into_iter()
+    }
+
+    /// Returns the offset of the first owner in account data of `data_len`
+    /// bytes, if it is a known layout.
+    pub fn owners_offset(data_len: usize) -> Option<usize> {
+        find_account_layout(data_len).map(|layout| layout[..6].iter().sum())
+    }
+
+    /// Returns true if account data of `data_len` bytes has room for the
+    /// transaction nonce. First release accounts only take transaction
+    /// keypairs.
+    pub fn has_nonce(data_len: usize) -> bool {
+        data_len == ACCOUNT_LEN
+    }
+
+    /// Unpacks an initialized account, rejecting any encoding but the
+    /// canonical one: unused owner, transaction and proposer slots must be
+    /// zeroed. `unpack` ignores them, so different bytes can decode to the
+    /// same account.
+    pub fn unpack_strict(src: &[u8]) -> Result<Self, ProgramError> {
+        let account = Self::unpack(src)?;
+
+        let mut canonical = vec![0; src.len()];
+        Self::pack(account.clone(), &mut canonical)?;
+        if canonical[..] != *src {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(account)
+    }
+}
+
+/// Renders the account as a stable block of `name: value` lines, one
+/// indented line per owner.
+#[cfg(feature = "client")]
+impl std::fmt::Display for Account {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "threshold: {}", self.threshold)?;
+        writeln!(f, "owners: {}", self.owners.len())?;
+        for owner in &self.owners {
+            if self.is_weighted() {
+                writeln!(f, "  {} (weight {})", owner, self.owner_weight(owner))?;
+            } else {
+                writeln!(f, "  {}", owner)?;
+            }
+        }
+        writeln!(
+            f,
+            "pending transactions: {}",
+            self.pending_transactions.len()
+        )?;
+        writeln!(f, "frozen amount: {}", self.frozen_amount)?;
+        write!(f, "last updated slot: {}", self.last_updated_slot)?;
+        if self.execution_delay_slots != 0 {
+            write!(f, "\nexecution delay: {} slots", self.execution_delay_slots)?;
+        }
+        if self.new_owner_grace_secs != 0 {
+            write!(
+                f,
+                "\nnew owner grace: {} seconds",
+                self.new_owner_grace_secs
+            )?;
+        }
+        if self.is_frozen {
+            write!(f, "\nfrozen: no transactions can be created or executed")?;
+        }
+        Ok(())
+    }
+}
+
+const ACCOUNT_LEN: usize = 4049;
+const LEGACY_ACCOUNT_LEN: usize = 597;
+
+// Field lengths in packing order for a layout with room for `signers` owners.
+// The first release's layout is a prefix of it with the fields it predates
+// cut to no length.
+fn account_layout(signers: usize) -> [usize; 23] {
+    [
+        1,
+        4,
+        8,
+        8,
+        4,
+        4,
+        PUBKEY_BYTES * signers + PUBKEY_BYTES * MAX_TRANSACTIONS,
+        1,
+        4,
+        PUBKEY_BYTES * signers,
+        1,
+        2 * signers,
+        1,
+        4,
+        8,
+        4,
+        (PUBKEY_BYTES + 8) * signers,
+        1,
+        8,
+        4,
+        8,
+        8,
+        8 * signers,
+    ]
+}
+
+// Fields of `account_layout` the first release has. It predates
+// `reserved_balance`, which was inserted after `frozen_amount` instead of
+// appended.
+const LEGACY_ACCOUNT_FIELDS: usize = 7;
+const RESERVED_BALANCE_FIELD: usize = 3;
+
+// Returns the field lengths of the layout accounts of `data_len` bytes use
+fn find_account_layout(data_len: usize) -> Option<[usize; 23]> {
+    match data_len {
+        ACCOUNT_LEN => Some(account_layout(MAX_SIGNERS)),
+        LEGACY_ACCOUNT_LEN => {
+            let mut layout = account_layout(LEGACY_MAX_SIGNERS);
+            layout[LEGACY_ACCOUNT_FIELDS..].fill(0);
+            layout[RESERVED_BALANCE_FIELD] = 0;
+            Some(layout)
+        }
+        _ => None,
+    }
+}
 
 impl Pack for Account {
     const LEN: usize = ACCOUNT_LEN;
+
+    // First release accounts are widened to the current layout on unpack
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() == ACCOUNT_LEN {
+            return Self::unpack_from_slice(input);
+        }
+        let layout = find_account_layout(input.len()).ok_or(ProgramError::InvalidAccountData)?;
+
+        let mut data = vec![0; ACCOUNT_LEN];
+        relayout(input, &layout, &mut data, &account_layout(MAX_SIGNERS))?;
+        let mut account = Self::unpack_from_slice(&data)?;
+
+        // They predate the cap and could fill every transaction slot
+        account.max_pending = MAX_TRANSACTIONS as u32;
+        Ok(account)
+    }
+
+    // and narrowed back on pack, failing if they outgrew it. Bookkeeping they
+    // have no room for is dropped.
+    fn pack(mut src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() == ACCOUNT_LEN {
+            src.pack_into_slice(dst);
+            return Ok(());
+        }
+        let layout = find_account_layout(dst.len()).ok_or(ProgramError::InvalidAccountData)?;
+
+        src.owners_version = 0;
+        src.last_updated_slot = 0;
+        if src.max_pending == MAX_TRANSACTIONS as u32 {
+            src.max_pending = 0;
+        }
+
+        let mut data = vec![0; ACCOUNT_LEN];
+        src.pack_into_slice(&mut data);
+        relayout(&data, &account_layout(MAX_SIGNERS), dst, &layout)
+    }
+
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, ACCOUNT_LEN];
+        // Clear slots left over from removed owners and transactions
+        dst.fill(0);
         #[allow(clippy::ptr_offset_with_cast)]
         let (
             is_initialized,
             threshold,
             frozen_amount,
+            reserved_balance,
             owners_len,
             pending_transactions_len,
             data_flat,
+            proposer_policy,
+            proposers_len,
+            proposers_flat,
+            is_weighted,
+            owner_weights_flat,
+            strict_owner_match,
+            owners_version,
+            nonce,
+            spend_limits_len,
+            spend_limits_flat,
+            is_frozen,
+            last_updated_slot,
+            max_pending,
+            execution_delay_slots,
+            new_owner_grace_secs,
+            owner_added_at_flat,
         ) = mut_array_refs![
             dst,
             1,
             4,
             8,
+            8,
+            4,
+            4,
+            PUBKEY_BYTES * MAX_SIGNERS + PUBKEY_BYTES * MAX_TRANSACTIONS,
+            1,
+            4,
+            PUBKEY_BYTES * MAX_SIGNERS,
+            1,
+            2 * MAX_SIGNERS,
+            1,
+            4,
+            8,
             4,
+            (PUBKEY_BYTES + 8) * MAX_SIGNERS,
+            1,
+            8,
             4,
-            PUBKEY_BYTES * MAX_SIGNERS + PUBKEY_BYTES * MAX_TRANSACTIONS
+            8,
+            8,
+            8 * MAX_SIGNERS
         ];
 
         pack_bool(self.is_initialized, is_initialized);
         *threshold = self.threshold.to_le_bytes();
         *frozen_amount = self.frozen_amount.to_le_bytes();
+        *reserved_balance = self.reserved_balance.to_le_bytes();
         *owners_len = (self.owners.len() as u32).to_le_bytes();
         *pending_transactions_len = (self.pending_transactions.len() as u32).to_le_bytes();
 
@@ -76,6 +480,59 @@ impl Pack for Account {
             pending_transactions_flat.copy_from_slice(pending_transaction.as_ref());
             offset += PUBKEY_BYTES;
         }
+
+        let proposers: &[Pubkey] = match &self.proposer_policy {
+            ProposerPolicy::Wallet => {
+                *proposer_policy = [0];
+                &[]
+            }
+            ProposerPolicy::AnyOwner => {
+                *proposer_policy = [1];
+                &[]
+            }
+            ProposerPolicy::Allowlist(proposers) => {
+                *proposer_policy = [2];
+                proposers
+            }
+        };
+        *proposers_len = (proposers.len() as u32).to_le_bytes();
+
+        let mut offset = 0;
+        for proposer in proposers {
+            let proposer_flat = array_mut_ref![proposers_flat, offset, PUBKEY_BYTES];
+            proposer_flat.copy_from_slice(proposer.as_ref());
+            offset += PUBKEY_BYTES;
+        }
+
+        pack_bool(self.is_weighted(), is_weighted);
+        for (index, weight) in self.owner_weights.iter().enumerate() {
+            let weight_flat = array_mut_ref![owner_weights_flat, 2 * index, 2];
+            *weight_flat = weight.to_le_bytes();
+        }
+
+        pack_bool(self.strict_owner_match, strict_owner_match);
+        *owners_version = self.owners_version.to_le_bytes();
+        *nonce = self.nonce.to_le_bytes();
+
+        *spend_limits_len = (self.spend_limits.len() as u32).to_le_bytes();
+        let mut offset = 0;
+        for (owner, limit) in &self.spend_limits {
+            let spend_limit_flat = array_mut_ref![spend_limits_flat, offset, PUBKEY_BYTES + 8];
+            let (owner_flat, limit_flat) = mut_array_refs![spend_limit_flat, PUBKEY_BYTES, 8];
+            owner_flat.copy_from_slice(owner.as_ref());
+            *limit_flat = limit.to_le_bytes();
+            offset += PUBKEY_BYTES + 8;
+        }
+
+        pack_bool(self.is_frozen, is_frozen);
+        *last_updated_slot = self.last_updated_slot.to_le_bytes();
+        *max_pending = self.max_pending.to_le_bytes();
+        *execution_delay_slots = self.execution_delay_slots.to_le_bytes();
+        *new_owner_grace_secs = self.new_owner_grace_secs.to_le_bytes();
+        for (index, added_at) in self.owner_added_at.iter().enumerate() {
+            let added_at_flat = array_mut_ref![owner_added_at_flat, 8 * index, 8];
+            *added_at_flat = added_at.to_le_bytes();
+        }
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
@@ -85,25 +542,65 @@ impl Pack for Account {
             is_initialized,
             threshold,
             frozen_amount,
+            reserved_balance,
             owners_len,
             pending_transactions_len,
             data_flat,
+            proposer_policy,
+            proposers_len,
+            proposers_flat,
+            is_weighted,
+            owner_weights_flat,
+            strict_owner_match,
+            owners_version,
+            nonce,
+            spend_limits_len,
+            spend_limits_flat,
+            is_frozen,
+            last_updated_slot,
+            max_pending,
+            execution_delay_slots,
+            new_owner_grace_secs,
+            owner_added_at_flat,
         ) = array_refs![
             input,
             1,
             4,
             8,
+            8,
             4,
             4,
-            PUBKEY_BYTES * MAX_SIGNERS + PUBKEY_BYTES * MAX_TRANSACTIONS
+            PUBKEY_BYTES * MAX_SIGNERS + PUBKEY_BYTES * MAX_TRANSACTIONS,
+            1,
+            4,
+            PUBKEY_BYTES * MAX_SIGNERS,
+            1,
+            2 * MAX_SIGNERS,
+            1,
+            4,
+            8,
+            4,
+            (PUBKEY_BYTES + 8) * MAX_SIGNERS,
+            1,
+            8,
+            4,
+            8,
+            8,
+            8 * MAX_SIGNERS
         ];
 
         let is_initialized = unpack_bool(is_initialized)?;
         let threshold = u32::from_le_bytes(*threshold);
         let frozen_amount = u64::from_le_bytes(*frozen_amount);
+        let reserved_balance = u64::from_le_bytes(*reserved_balance);
         let owners_len = u32::from_le_bytes(*owners_len);
         let pending_transactions_len = u32::from_le_bytes(*pending_transactions_len);
 
+        if owners_len as usize > MAX_SIGNERS || pending_transactions_len as usize > MAX_TRANSACTIONS
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
         let mut owners = Vec::with_capacity(owners_len as usize);
         let mut pending_transactions = Vec::with_capacity(pending_transactions_len as usize);
 
@@ -119,16 +616,101 @@ impl Pack for Account {
             offset += PUBKEY_BYTES;
         }
 
+        let proposer_policy = match proposer_policy {
+            [0] => ProposerPolicy::Wallet,
+            [1] => ProposerPolicy::AnyOwner,
+            [2] => {
+                let proposers_len = u32::from_le_bytes(*proposers_len);
+                if proposers_len as usize > MAX_SIGNERS {
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                let mut proposers = Vec::with_capacity(proposers_len as usize);
+                let mut offset = 0;
+                for _ in 0..proposers_len {
+                    let proposer_flat = array_ref![proposers_flat, offset, PUBKEY_BYTES];
+                    proposers.push(Pubkey::new(proposer_flat));
+                    offset += PUBKEY_BYTES;
+                }
+                ProposerPolicy::Allowlist(proposers)
+            }
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let owner_weights = if unpack_bool(is_weighted)? {
+            (0..owners_len as usize)
+                .map(|index| u16::from_le_bytes(*array_ref![owner_weights_flat, 2 * index, 2]))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let owner_added_at = (0..owners_len as usize)
+            .map(|index| i64::from_le_bytes(*array_ref![owner_added_at_flat, 8 * index, 8]))
+            .collect();
+
+        let spend_limits_len = u32::from_le_bytes(*spend_limits_len);
+        if spend_limits_len as usize > MAX_SIGNERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut spend_limits = Vec::with_capacity(spend_limits_len as usize);
+        let mut offset = 0;
+        for _ in 0..spend_limits_len {
+            let spend_limit_flat = array_ref![spend_limits_flat, offset, PUBKEY_BYTES + 8];
+            let (owner_flat, limit_flat) = array_refs![spend_limit_flat, PUBKEY_BYTES, 8];
+            spend_limits.push((Pubkey::new(owner_flat), u64::from_le_bytes(*limit_flat)));
+            offset += PUBKEY_BYTES + 8;
+        }
+
         Ok(Self {
             is_initialized,
             threshold,
             owners,
             pending_transactions,
             frozen_amount,
+            reserved_balance,
+            proposer_policy,
+            owner_weights,
+            strict_owner_match: unpack_bool(strict_owner_match)?,
+            owners_version: u32::from_le_bytes(*owners_version),
+            nonce: u64::from_le_bytes(*nonce),
+            spend_limits,
+            is_frozen: unpack_bool(is_frozen)?,
+            last_updated_slot: u64::from_le_bytes(*last_updated_slot),
+            max_pending: u32::from_le_bytes(*max_pending),
+            execution_delay_slots: u64::from_le_bytes(*execution_delay_slots),
+            new_owner_grace_secs: i64::from_le_bytes(*new_owner_grace_secs),
+            owner_added_at,
         })
     }
 }
 
+/// Account of an instruction executed by the multisig, see `InnerInstruction`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct InnerAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Instruction the multisig invokes on execution instead of a transfer,
+/// signed by its authority address (see `get_authority_address`).
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct InnerInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<InnerAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl InnerInstruction {
+    /// Returns true if the instruction fits the transaction layout.
+    pub fn is_bounded(&self) -> bool {
+        self.accounts.len() <= MAX_INSTRUCTION_ACCOUNTS
+            && self.data.len() <= MAX_INSTRUCTION_DATA_LEN
+    }
+}
+
 #[derive(Debug)]
 pub struct Transaction {
     // The multisig account this transaction belongs to
@@ -137,35 +719,330 @@ pub struct Transaction {
     pub recipient: Pubkey,
     // Amount of lamports to send to recipient
     pub amount: u64,
-    // Boolean ensuring one time execution.
-    pub is_executed: bool,
+    // Ensures one time execution, only pending transactions are approved
+    // or executed
+    pub status: TransactionStatus,
+    // Amount is resolved on execution to the whole available balance
+    pub is_sweep: bool,
     // Owners with status of transaction signature
     pub signers: Vec<(Pubkey, bool)>,
+    // UTF-8 description shown to signers, up to MAX_MEMO_LEN bytes
+    pub memo: String,
+    // Recipients and amounts of a multi transfer, empty for a single
+    // transfer. `recipient` is the first output and `amount` the total.
+    pub outputs: Vec<(Pubkey, u64)>,
+    // Slot before which the transaction can't be executed, zero for none
+    pub not_before_slot: u64,
+    // Slot from which the transaction can only be reaped, zero for never
+    pub expires_at_slot: u64,
+    // Draft that freezes no lamports and can't be executed until promoted
+    pub is_soft: bool,
+    // Bit `i` is set when `owners[i]` approved, used instead of `signers`
+    // by multisigs with `strict_owner_match`
+    pub approvals: u32,
+    // `Account::owners_version` the approvals were given under
+    pub owners_version: u32,
+    // Nonce assigned on creation, see `Account::nonce`
+    pub nonce: u64,
+    // Instruction invoked on execution instead of a transfer, `recipient`
+    // is its program and `amount` zero
+    pub instruction: Option<InnerInstruction>,
+    // Wallet or owner that proposed the transaction
+    pub creator: Pubkey,
+    // Slot the approvals first reached the threshold, only recorded while
+    // the multisig has an execution delay
+    pub reached_threshold_slot: Option<u64>,
 }
 
 impl Sealed for Transaction {}
 
-const TRANSACTION_LEN: usize = 341; // 32 + 32 + 8 + 1 + 4 + (32 + 1)*MAX_OWNERS
+impl Transaction {
+    /// Recipient accounts to pass on execution, in order. For an
+    /// instruction these are its program followed by its accounts.
+    pub fn recipients(&self) -> Vec<Pubkey> {
+        if let Some(instruction) = &self.instruction {
+            std::iter::once(instruction.program_id)
+                .chain(instruction.accounts.iter().map(|meta| meta.pubkey))
+                .collect()
+        } else if self.outputs.is_empty() {
+            vec![self.recipient]
+        } else {
+            self.outputs
+                .iter()
+                .map(|(recipient, _)| *recipient)
+                .collect()
+        }
+    }
+
+    /// Checks in debug builds that the transaction tracks a signature
+    /// status for every owner of its multisig.
+    pub fn debug_assert_invariants(&self, multisig: &Account) {
+        if multisig.strict_owner_match {
+            debug_assert!(
+                self.signers.is_empty(),
+                "strict transaction tracks a signer list"
+            );
+        } else {
+            debug_assert_eq!(
+                self.signers.len(),
+                multisig.owners.len(),
+                "transaction signers don't match multisig owners"
+            );
+        }
+
+        if self.instruction.is_some() {
+            debug_assert!(
+                self.amount == 0 && self.outputs.is_empty() && !self.is_sweep,
+                "instruction transaction moves multisig lamports"
+            );
+        }
+    }
+
+    /// Returns the signature status of every owner. With
+    /// `strict_owner_match` it is read from `approvals` against the live
+    /// owners, approvals given before an owner change don't count.
+    pub fn signer_status(&self, multisig: &Account) -> Vec<(Pubkey, bool)> {
+        if !multisig.strict_owner_match {
+            return self.signers.clone();
+        }
+
+        let is_current = self.owners_version == multisig.owners_version;
+        multisig
+            .owners
+            .iter()
+            .enumerate()
+            .map(|(index, owner)| (*owner, is_current && self.approvals & (1 << index) != 0))
+            .collect()
+    }
+
+    /// Returns the number of owners that approved the transaction, see
+    /// `signer_status`.
+    pub fn approval_count(&self, multisig: &Account) -> u32 {
+        self.signer_status(multisig)
+            .iter()
+            .filter(|(_, is_signed)| *is_signed)
+            .count() as u32
+    }
+
+    /// Returns whether `key` approved the transaction, `None` if it is not
+    /// one of its signers.
+    pub fn signed_by(&self, multisig: &Account, key: &Pubkey) -> Option<bool> {
+        self.signer_status(multisig)
+            .iter()
+            .find(|(signer, _)| signer == key)
+            .map(|(_, is_signed)| *is_signed)
+    }
+
+    /// Records whether `owners[index]` approves under the current owners,
+    /// dropping approvals given before the last owner change.
+    pub fn set_approval(&mut self, multisig: &Account, index: usize, is_signed: bool) {
+        if self.owners_version != multisig.owners_version {
+            self.approvals = 0;
+            self.owners_version = multisig.owners_version;
+        }
+
+        if is_signed {
+            self.approvals |= 1 << index;
+        } else {
+            self.approvals &= !(1 << index);
+        }
+    }
+
+    /// Records `slot` as the slot the approvals first reached the threshold,
+    /// only while the multisig has an execution delay to count it from.
+    pub fn record_threshold(&mut self, multisig: &Account, slot: u64) {
+        if multisig.execution_delay_slots != 0
+            && self.reached_threshold_slot.is_none()
+            && multisig.approval_weight(&self.signer_status(multisig)) >= multisig.threshold
+        {
+            self.reached_threshold_slot = Some(slot);
+        }
+    }
+
+    /// Returns true if the transaction expired at `slot`.
+    pub fn is_expired(&self, slot: u64) -> bool {
+        self.expires_at_slot != 0 && slot >= self.expires_at_slot
+    }
+}
+
+// 32 + 32 + 8 + 1 + 1 + 4 + (32 + 1)*MAX_OWNERS + 1 + MAX_MEMO_LEN + 1 + (32 + 8)*MAX_OUTPUTS + 8 + 8 + 1 + 4 + 4 + 8
+// + 1 + 32 + 1 + (32 + 1 + 1)*MAX_INSTRUCTION_ACCOUNTS + 2 + MAX_INSTRUCTION_DATA_LEN + 32 + 1 + 8
+const TRANSACTION_LEN: usize = 1870;
+const LEGACY_TRANSACTION_LEN: usize = 341;
+// Length of transactions without an inner instruction, which have no room
+// for it
+const TRANSFER_TRANSACTION_LEN: usize = 1434;
+
+// Field lengths in packing order for a layout with room for `signers` signers,
+// see `account_layout`
+fn transaction_layout(signers: usize) -> [usize; 26] {
+    [
+        PUBKEY_BYTES,
+        PUBKEY_BYTES,
+        8,
+        1,
+        1,
+        4,
+        (32 + 1) * signers,
+        1,
+        MAX_MEMO_LEN,
+        1,
+        (PUBKEY_BYTES + 8) * MAX_OUTPUTS,
+        8,
+        8,
+        1,
+        4,
+        4,
+        8,
+        1,
+        PUBKEY_BYTES,
+        1,
+        (PUBKEY_BYTES + 1 + 1) * MAX_INSTRUCTION_ACCOUNTS,
+        2,
+        MAX_INSTRUCTION_DATA_LEN,
+        PUBKEY_BYTES,
+        1,
+        8,
+    ]
+}
+
+// Fields of `transaction_layout` the first release has, it predates
+// `is_sweep`, see `LEGACY_ACCOUNT_FIELDS`
+const LEGACY_TRANSACTION_FIELDS: usize = 7;
+const IS_SWEEP_FIELD: usize = 4;
+// Fields of `transaction_layout` holding the inner instruction
+const INSTRUCTION_FIELDS: std::ops::Range<usize> = 17..23;
+
+// Returns the field lengths of the layout transactions of `data_len` bytes use
+fn find_transaction_layout(data_len: usize) -> Option<[usize; 26]> {
+    match data_len {
+        TRANSACTION_LEN => Some(transaction_layout(MAX_SIGNERS)),
+        TRANSFER_TRANSACTION_LEN => {
+            let mut layout = transaction_layout(MAX_SIGNERS);
+            layout[INSTRUCTION_FIELDS].fill(0);
+            Some(layout)
+        }
+        LEGACY_TRANSACTION_LEN => {
+            let mut layout = transaction_layout(LEGACY_MAX_SIGNERS);
+            layout[LEGACY_TRANSACTION_FIELDS..].fill(0);
+            layout[IS_SWEEP_FIELD] = 0;
+            Some(layout)
+        }
+        _ => None,
+    }
+}
+
+impl Transaction {
+    /// Length of transactions created by the first release
+    pub const LEGACY_LEN: usize = LEGACY_TRANSACTION_LEN;
+
+    /// Length of transactions without an inner instruction
+    pub const TRANSFER_LEN: usize = TRANSFER_TRANSACTION_LEN;
+
+    /// Returns the length to allocate for the transaction. Only instruction
+    /// transactions take the full `LEN`.
+    pub fn data_len(&self) -> usize {
+        if self.instruction.is_some() {
+            TRANSACTION_LEN
+        } else {
+            TRANSFER_TRANSACTION_LEN
+        }
+    }
+}
 
 impl Pack for Transaction {
     const LEN: usize = TRANSACTION_LEN;
+
+    // First release transactions are widened and narrowed like `Account`
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() == TRANSACTION_LEN {
+            return Self::unpack_from_slice(input);
+        }
+        let layout =
+            find_transaction_layout(input.len()).ok_or(ProgramError::InvalidAccountData)?;
+
+        let mut data = vec![0; TRANSACTION_LEN];
+        relayout(input, &layout, &mut data, &transaction_layout(MAX_SIGNERS))?;
+        Self::unpack_from_slice(&data)
+    }
+
+    fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() == TRANSACTION_LEN {
+            src.pack_into_slice(dst);
+            return Ok(());
+        }
+        let layout = find_transaction_layout(dst.len()).ok_or(ProgramError::InvalidAccountData)?;
+
+        let mut data = vec![0; TRANSACTION_LEN];
+        src.pack_into_slice(&mut data);
+        relayout(&data, &transaction_layout(MAX_SIGNERS), dst, &layout)
+    }
+
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, TRANSACTION_LEN];
         #[allow(clippy::ptr_offset_with_cast)]
-        let (multisig, recipient, amount, is_executed, signers_len, signers_flat) = mut_array_refs![
+        let (
+            multisig,
+            recipient,
+            amount,
+            status,
+            is_sweep,
+            signers_len,
+            signers_flat,
+            memo_len,
+            memo_flat,
+            outputs_len,
+            outputs_flat,
+            not_before_slot,
+            expires_at_slot,
+            is_soft,
+            approvals,
+            owners_version,
+            nonce,
+            has_instruction,
+            program_id,
+            instruction_accounts_len,
+            instruction_accounts_flat,
+            instruction_data_len,
+            instruction_data_flat,
+            creator,
+            has_reached_threshold,
+            reached_threshold_slot,
+        ) = mut_array_refs![
             dst,
             PUBKEY_BYTES,
             PUBKEY_BYTES,
             8,
             1,
+            1,
             4,
-            (32 + 1) * MAX_SIGNERS
+            (32 + 1) * MAX_SIGNERS,
+            1,
+            MAX_MEMO_LEN,
+            1,
+            (PUBKEY_BYTES + 8) * MAX_OUTPUTS,
+            8,
+            8,
+            1,
+            4,
+            4,
+            8,
+            1,
+            PUBKEY_BYTES,
+            1,
+            (PUBKEY_BYTES + 1 + 1) * MAX_INSTRUCTION_ACCOUNTS,
+            2,
+            MAX_INSTRUCTION_DATA_LEN,
+            PUBKEY_BYTES,
+            1,
+            8
         ];
 
         *amount = self.amount.to_le_bytes();
         multisig.copy_from_slice(self.multisig.as_ref());
         recipient.copy_from_slice(self.recipient.as_ref());
-        pack_bool(self.is_executed, is_executed);
+        pack_status(self.status, status);
+        pack_bool(self.is_sweep, is_sweep);
 
         *signers_len = (self.signers.len() as u32).to_le_bytes();
 
@@ -179,27 +1056,136 @@ impl Pack for Transaction {
             pack_bool(*is_signed, is_signed_flat);
             offset += 1;
         }
+
+        let memo = self.memo.as_bytes();
+        *memo_len = [memo.len() as u8];
+        memo_flat[..memo.len()].copy_from_slice(memo);
+
+        *outputs_len = [self.outputs.len() as u8];
+
+        let mut offset = 0;
+        for (recipient, amount) in &self.outputs {
+            let recipient_flat = array_mut_ref![outputs_flat, offset, PUBKEY_BYTES];
+            recipient_flat.copy_from_slice(recipient.as_ref());
+            offset += PUBKEY_BYTES;
+
+            let amount_flat = array_mut_ref![outputs_flat, offset, 8];
+            *amount_flat = amount.to_le_bytes();
+            offset += 8;
+        }
+
+        *not_before_slot = self.not_before_slot.to_le_bytes();
+        *expires_at_slot = self.expires_at_slot.to_le_bytes();
+        pack_bool(self.is_soft, is_soft);
+        *approvals = self.approvals.to_le_bytes();
+        *owners_version = self.owners_version.to_le_bytes();
+        *nonce = self.nonce.to_le_bytes();
+
+        // Unused instruction slots are left zeroed
+        program_id.fill(0);
+        *instruction_accounts_len = [0];
+        instruction_accounts_flat.fill(0);
+        *instruction_data_len = [0; 2];
+        instruction_data_flat.fill(0);
+
+        pack_bool(self.instruction.is_some(), has_instruction);
+        if let Some(instruction) = &self.instruction {
+            program_id.copy_from_slice(instruction.program_id.as_ref());
+            *instruction_accounts_len = [instruction.accounts.len() as u8];
+
+            let mut offset = 0;
+            for meta in &instruction.accounts {
+                let meta_flat =
+                    array_mut_ref![instruction_accounts_flat, offset, PUBKEY_BYTES + 1 + 1];
+                let (pubkey_flat, is_signer_flat, is_writable_flat) =
+                    mut_array_refs![meta_flat, PUBKEY_BYTES, 1, 1];
+                pubkey_flat.copy_from_slice(meta.pubkey.as_ref());
+                pack_bool(meta.is_signer, is_signer_flat);
+                pack_bool(meta.is_writable, is_writable_flat);
+                offset += PUBKEY_BYTES + 1 + 1;
+            }
+
+            *instruction_data_len = (instruction.data.len() as u16).to_le_bytes();
+            instruction_data_flat[..instruction.data.len()].copy_from_slice(&instruction.data);
+        }
+
+        creator.copy_from_slice(self.creator.as_ref());
+
+        pack_bool(self.reached_threshold_slot.is_some(), has_reached_threshold);
+        *reached_threshold_slot = self.reached_threshold_slot.unwrap_or(0).to_le_bytes();
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         let input = array_ref![src, 0, TRANSACTION_LEN];
         #[allow(clippy::ptr_offset_with_cast)]
-        let (multisig, recipient, amount, is_executed, signers_len, signers_flat) = array_refs![
+        let (
+            multisig,
+            recipient,
+            amount,
+            status,
+            is_sweep,
+            signers_len,
+            signers_flat,
+            memo_len,
+            memo_flat,
+            outputs_len,
+            outputs_flat,
+            not_before_slot,
+            expires_at_slot,
+            is_soft,
+            approvals,
+            owners_version,
+            nonce,
+            has_instruction,
+            program_id,
+            instruction_accounts_len,
+            instruction_accounts_flat,
+            instruction_data_len,
+            instruction_data_flat,
+            creator,
+            has_reached_threshold,
+            reached_threshold_slot,
+        ) = array_refs![
             input,
             PUBKEY_BYTES,
             PUBKEY_BYTES,
             8,
             1,
+            1,
             4,
-            (32 + 1) * MAX_SIGNERS
+            (32 + 1) * MAX_SIGNERS,
+            1,
+            MAX_MEMO_LEN,
+            1,
+            (PUBKEY_BYTES + 8) * MAX_OUTPUTS,
+            8,
+            8,
+            1,
+            4,
+            4,
+            8,
+            1,
+            PUBKEY_BYTES,
+            1,
+            (PUBKEY_BYTES + 1 + 1) * MAX_INSTRUCTION_ACCOUNTS,
+            2,
+            MAX_INSTRUCTION_DATA_LEN,
+            PUBKEY_BYTES,
+            1,
+            8
         ];
 
-        let is_executed = unpack_bool(is_executed)?;
+        let status = unpack_status(status)?;
+        let is_sweep = unpack_bool(is_sweep)?;
+        let is_soft = unpack_bool(is_soft)?;
         let multisig = Pubkey::new(multisig);
         let recipient = Pubkey::new(recipient);
         let amount = u64::from_le_bytes(*amount);
 
         let signers_len = u32::from_le_bytes(*signers_len);
+        if signers_len as usize > MAX_SIGNERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
 
         let mut signers = Vec::with_capacity(signers_len as usize);
 
@@ -213,12 +1199,86 @@ impl Pack for Transaction {
             signers.push((Pubkey::new(signer_flat), unpack_bool(is_signed)?));
         }
 
+        let memo_len = memo_len[0] as usize;
+        if memo_len > MAX_MEMO_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let memo = std::str::from_utf8(&memo_flat[..memo_len])
+            .map_err(|_| ProgramError::InvalidAccountData)?
+            .to_string();
+
+        let outputs_len = outputs_len[0] as usize;
+        if outputs_len > MAX_OUTPUTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut outputs = Vec::with_capacity(outputs_len);
+
+        let mut offset = 0;
+        for _ in 0..outputs_len {
+            let recipient_flat = array_ref![outputs_flat, offset, PUBKEY_BYTES];
+            offset += PUBKEY_BYTES;
+            let amount_flat = array_ref![outputs_flat, offset, 8];
+            offset += 8;
+
+            outputs.push((
+                Pubkey::new(recipient_flat),
+                u64::from_le_bytes(*amount_flat),
+            ));
+        }
+
+        let instruction = if unpack_bool(has_instruction)? {
+            let accounts_len = instruction_accounts_len[0] as usize;
+            let data_len = u16::from_le_bytes(*instruction_data_len) as usize;
+            if accounts_len > MAX_INSTRUCTION_ACCOUNTS || data_len > MAX_INSTRUCTION_DATA_LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut accounts = Vec::with_capacity(accounts_len);
+            let mut offset = 0;
+            for _ in 0..accounts_len {
+                let meta_flat = array_ref![instruction_accounts_flat, offset, PUBKEY_BYTES + 1 + 1];
+                let (pubkey_flat, is_signer_flat, is_writable_flat) =
+                    array_refs![meta_flat, PUBKEY_BYTES, 1, 1];
+                accounts.push(InnerAccountMeta {
+                    pubkey: Pubkey::new(pubkey_flat),
+                    is_signer: unpack_bool(is_signer_flat)?,
+                    is_writable: unpack_bool(is_writable_flat)?,
+                });
+                offset += PUBKEY_BYTES + 1 + 1;
+            }
+
+            Some(InnerInstruction {
+                program_id: Pubkey::new(program_id),
+                accounts,
+                data: instruction_data_flat[..data_len].to_vec(),
+            })
+        } else {
+            None
+        };
+
         Ok(Self {
             multisig,
             recipient,
             amount,
-            is_executed,
+            status,
+            is_sweep,
             signers,
+            memo,
+            outputs,
+            not_before_slot: u64::from_le_bytes(*not_before_slot),
+            expires_at_slot: u64::from_le_bytes(*expires_at_slot),
+            is_soft,
+            approvals: u32::from_le_bytes(*approvals),
+            owners_version: u32::from_le_bytes(*owners_version),
+            nonce: u64::from_le_bytes(*nonce),
+            instruction,
+            creator: Pubkey::new(creator),
+            reached_threshold_slot: if unpack_bool(has_reached_threshold)? {
+                Some(u64::from_le_bytes(*reached_threshold_slot))
+            } else {
+                None
+            },
         })
     }
 }
@@ -1,5 +1,7 @@
 use solana_program::program_error::ProgramError;
 
+use crate::state::TransactionStatus;
+
 pub fn pack_bool(boolean: bool, dst: &mut [u8; 1]) {
     *dst = (boolean as u8).to_le_bytes()
 }
@@ -11,3 +13,46 @@ pub fn unpack_bool(src: &[u8; 1]) -> Result<bool, ProgramError> {
         _ => Err(ProgramError::InvalidAccountData),
     }
 }
+
+pub fn pack_status(status: TransactionStatus, dst: &mut [u8; 1]) {
+    *dst = (status as u8).to_le_bytes()
+}
+
+pub fn unpack_status(src: &[u8; 1]) -> Result<TransactionStatus, ProgramError> {
+    match u8::from_le_bytes(*src) {
+        0 => Ok(TransactionStatus::Pending),
+        1 => Ok(TransactionStatus::Executed),
+        2 => Ok(TransactionStatus::Cancelled),
+        3 => Ok(TransactionStatus::Expired),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Copies a packed struct between two layouts given as field lengths in
+/// packing order, a field one of them lacks has no length. Widened fields
+/// are zero padded, narrowed ones must only lose zeroed bytes, i.e. unused
+/// slots or fields that were never set.
+pub fn relayout(
+    src: &[u8],
+    src_layout: &[usize],
+    dst: &mut [u8],
+    dst_layout: &[usize],
+) -> Result<(), ProgramError> {
+    let mut src_offset = 0;
+    let mut dst_offset = 0;
+    for (src_len, dst_len) in src_layout.iter().zip(dst_layout) {
+        let field = &src[src_offset..src_offset + src_len];
+        let (kept, cut) = field.split_at(*src_len.min(dst_len));
+        if cut.iter().any(|byte| *byte != 0) {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        let dst_field = &mut dst[dst_offset..dst_offset + dst_len];
+        dst_field.fill(0);
+        dst_field[..kept.len()].copy_from_slice(kept);
+
+        src_offset += src_len;
+        dst_offset += dst_len;
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use solana_multisig::*;
+
+#[test]
+fn test_error_messages_are_distinct() {
+    assert_eq!(
+        MultisigError::TransactionAlreadyExecuted.to_string(),
+        "Transaction already executed"
+    );
+    assert_ne!(
+        MultisigError::TransactionAlreadyExecuted.to_string(),
+        MultisigError::PendingTransactionLimit.to_string()
+    );
+}
@@ -0,0 +1,111 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use solana_program::pubkey::Pubkey;
+
+use solana_multisig::*;
+
+#[test]
+fn test_decode_pending_signers_from_create_event() {
+    let transaction = Pubkey::new_unique();
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let signers = owners
+        .iter()
+        .map(|owner| (*owner, false))
+        .collect::<Vec<_>>();
+
+    let data = PendingSignersEvent::new(transaction, &signers)
+        .try_to_vec()
+        .expect("serialize");
+
+    let event = PendingSignersEvent::try_from_slice(&data).expect("deserialize");
+    assert_eq!(event.transaction, transaction);
+    assert_eq!(event.pending_signers, owners);
+}
+
+fn decode<T: BorshDeserialize>(event: &impl Event, kind: EventKind) -> T {
+    let fields = event.encode().expect("encode");
+    assert_eq!(fields.len(), 2);
+
+    let (header, decoded_kind) = EventHeader::decode(&fields[0]).expect("decode header");
+    assert_eq!(header.version, EVENT_VERSION);
+    assert_eq!(decoded_kind, kind);
+
+    T::try_from_slice(&fields[1]).expect("deserialize")
+}
+
+#[test]
+fn test_decode_events_through_envelope() {
+    let multisig = Pubkey::new_unique();
+    let transaction = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let event = PendingSignersEvent::new(transaction, &[(owners[0], true), (owners[1], false)]);
+    let decoded: PendingSignersEvent = decode(&event, EventKind::PendingSigners);
+    assert_eq!(decoded.pending_signers, vec![owners[1]]);
+
+    let event = MultisigCreatedEvent {
+        multisig,
+        threshold: 2,
+        owners: owners.clone(),
+    };
+    let decoded: MultisigCreatedEvent = decode(&event, EventKind::MultisigCreated);
+    assert_eq!(decoded, event);
+
+    let event = TransactionCreatedEvent {
+        multisig,
+        transaction,
+        recipient,
+        amount: 1_000,
+        is_sweep: false,
+    };
+    let decoded: TransactionCreatedEvent = decode(&event, EventKind::TransactionCreated);
+    assert_eq!(decoded, event);
+
+    let event = TransactionExecutedEvent {
+        multisig,
+        transaction,
+        recipient,
+        amount: 1_000,
+    };
+    let decoded: TransactionExecutedEvent = decode(&event, EventKind::TransactionExecuted);
+    assert_eq!(decoded, event);
+
+    let event = MultisigUpdatedEvent {
+        multisig,
+        threshold: 1,
+        owners,
+    };
+    let decoded: MultisigUpdatedEvent = decode(&event, EventKind::MultisigUpdated);
+    assert_eq!(decoded, event);
+}
+
+#[test]
+fn test_decode_unknown_event_kind() {
+    let header = EventHeader {
+        version: EVENT_VERSION,
+        kind: u8::MAX,
+    }
+    .try_to_vec()
+    .expect("serialize");
+
+    assert!(EventHeader::decode(&header).is_err());
+}
+
+#[test]
+fn test_decode_approval_recorded_event() {
+    let multisig = Pubkey::new_unique();
+    let transaction = Pubkey::new_unique();
+    let owners = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    let event = ApprovalRecordedEvent::new(multisig, transaction, owners[1], 2);
+    let decoded: ApprovalRecordedEvent = decode(&event, EventKind::ApprovalRecorded);
+    assert_eq!(decoded, event);
+    assert_eq!(decoded.signer, owners[1]);
+    assert_eq!(decoded.approvals, 2);
+}
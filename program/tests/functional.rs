@@ -1,10 +1,13 @@
 #![cfg(feature = "test-bpf")]
 
+use solana_program::instruction::{AccountMeta, InstructionError};
 use solana_program::{program_pack::Pack, pubkey::Pubkey};
 use solana_program_test::*;
-use solana_sdk::account::ReadableAccount;
+use solana_sdk::account::{Account as SolanaAccount, ReadableAccount};
+use solana_sdk::hash::Hash;
 use solana_sdk::signature::{Keypair, Signer};
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_sdk::transport::TransportError;
 
 use solana_multisig::*;
 
@@ -12,6 +15,93 @@ fn program_test() -> ProgramTest {
     ProgramTest::new("solana_multisig", id(), processor!(Processor::process))
 }
 
+fn add_multisig_account(
+    program_test: &mut ProgramTest,
+    address: Pubkey,
+    lamports: u64,
+    multisig: solana_multisig::Account,
+) {
+    let mut data = vec![0; solana_multisig::Account::LEN];
+    solana_multisig::Account::pack(multisig, &mut data).expect("pack");
+    program_test.add_account(
+        address,
+        SolanaAccount {
+            lamports,
+            data,
+            owner: id(),
+            ..SolanaAccount::default()
+        },
+    );
+}
+
+fn add_transaction_account(
+    program_test: &mut ProgramTest,
+    address: Pubkey,
+    transaction: solana_multisig::Transaction,
+) {
+    let mut data = vec![0; solana_multisig::Transaction::LEN];
+    solana_multisig::Transaction::pack(transaction, &mut data).expect("pack");
+    program_test.add_account(
+        address,
+        SolanaAccount {
+            lamports: 1_000_000,
+            data,
+            owner: id(),
+            ..SolanaAccount::default()
+        },
+    );
+}
+
+async fn approve(
+    banks_client: &mut BanksClient,
+    funder: &Keypair,
+    custodian: &Keypair,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    recent_blockhash: Hash,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::approve_transaction(
+            &custodian.pubkey(),
+            multisig_address,
+            transaction_address,
+            solana_multisig::MultisigInstruction::ApproveTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[funder, custodian], recent_blockhash);
+
+    banks_client.process_transaction(transaction).await
+}
+
+async fn execute(
+    banks_client: &mut BanksClient,
+    funder: &Keypair,
+    custodian: &Keypair,
+    multisig_address: &Pubkey,
+    transaction_address: &Pubkey,
+    recipient_address: &Pubkey,
+    recent_blockhash: Hash,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::execute_transaction(
+            &custodian.pubkey(),
+            multisig_address,
+            transaction_address,
+            recipient_address,
+            solana_multisig::MultisigInstruction::ExecuteTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[funder, custodian], recent_blockhash);
+
+    banks_client.process_transaction(transaction).await
+}
+
 #[tokio::test]
 async fn test_create_multisig_account() {
     let owner = Keypair::new();
@@ -40,6 +130,7 @@ async fn test_create_multisig_account() {
             solana_multisig::MultisigInstruction::CreateAccount {
                 threshold: 1,
                 owners: vec![custodian_address],
+                max_pending: MAX_TRANSACTIONS as u32,
             }
             .pack()
             .expect("pack"),
@@ -73,4 +164,5777 @@ async fn test_create_multisig_account() {
     );
     assert_eq!(multisig_account_data.pending_transactions.len(), 0);
     assert_eq!(multisig_account_data.frozen_amount, 0);
+    assert_eq!(multisig_account_data.reserved_balance, 0);
+}
+
+#[tokio::test]
+async fn test_create_and_execute_transaction() {
+    let wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new()];
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let transaction_account = Keypair::new();
+    let recipient_address = Pubkey::new_unique();
+
+    let (mut banks_client, funder, recent_blockhash) = program_test().start().await;
+
+    // Create a 2-of-2 multisig and fund it
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            solana_multisig::create_associated_account(
+                &funder.pubkey(),
+                &wallet.pubkey(),
+                solana_multisig::MultisigInstruction::CreateAccount {
+                    threshold: 2,
+                    owners: owners.iter().map(|owner| owner.pubkey()).collect(),
+                    max_pending: MAX_TRANSACTIONS as u32,
+                }
+                .pack()
+                .expect("pack"),
+            ),
+            solana_sdk::system_instruction::transfer(
+                &funder.pubkey(),
+                &multisig_address,
+                10_000_000,
+            ),
+        ],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[&funder, &wallet], recent_blockhash);
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("create_account");
+
+    propose(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &wallet.pubkey(),
+        &transaction_account,
+        &recipient_address,
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect("create_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![transaction_account.pubkey()]
+    );
+    assert_eq!(multisig_account_data.frozen_amount, 1_000_000);
+
+    // Creating a transaction doesn't approve it
+    let transaction_account_info = banks_client
+        .get_account(transaction_account.pubkey())
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+    assert_eq!(
+        transaction_account_info.data.len(),
+        solana_multisig::Transaction::TRANSFER_LEN
+    );
+    let transaction_account_data =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account_info.data())
+            .expect("unpack");
+    assert_eq!(transaction_account_data.creator, wallet.pubkey());
+    assert_eq!(
+        transaction_account_data.approval_count(&multisig_account_data),
+        0
+    );
+
+    for owner in &owners {
+        approve(
+            &mut banks_client,
+            &funder,
+            owner,
+            &multisig_address,
+            &transaction_account.pubkey(),
+            recent_blockhash,
+        )
+        .await
+        .expect("approve");
+    }
+
+    execute(
+        &mut banks_client,
+        &funder,
+        &owners[0],
+        &multisig_address,
+        &transaction_account.pubkey(),
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+
+    let transaction_account_info = banks_client
+        .get_account(transaction_account.pubkey())
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+    let transaction_account_data =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account_info.data())
+            .expect("unpack");
+    assert_eq!(transaction_account_data.status, TransactionStatus::Executed);
+    assert_eq!(
+        transaction_account_data.signers,
+        owners
+            .iter()
+            .map(|owner| (owner.pubkey(), true))
+            .collect::<Vec<_>>()
+    );
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert!(multisig_account_data.pending_transactions.is_empty());
+    assert_eq!(multisig_account_data.frozen_amount, 0);
+}
+
+#[tokio::test]
+async fn test_last_updated_slot() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let recipient_address = Pubkey::new_unique();
+
+    let mut context = program_test().start_with_context().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            solana_multisig::create_associated_account(
+                &context.payer.pubkey(),
+                &wallet.pubkey(),
+                solana_multisig::MultisigInstruction::CreateAccount {
+                    threshold: 1,
+                    owners: vec![Pubkey::new_unique()],
+                    max_pending: MAX_TRANSACTIONS as u32,
+                }
+                .pack()
+                .expect("pack"),
+            ),
+            solana_sdk::system_instruction::transfer(
+                &context.payer.pubkey(),
+                &multisig_address,
+                10_000_000,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.sign(&[&context.payer, &wallet], context.last_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("process_transaction");
+
+    let created_slot = Account::unpack(
+        context
+            .banks_client
+            .get_account(multisig_address)
+            .await
+            .expect("get_account")
+            .expect("multisig_account not none")
+            .data(),
+    )
+    .expect("unpack")
+    .last_updated_slot;
+
+    context
+        .warp_to_slot(created_slot + 100)
+        .expect("warp_to_slot");
+    let recent_blockhash = context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .expect("get_latest_blockhash");
+
+    propose(
+        &mut context.banks_client,
+        &context.payer,
+        &wallet,
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &recipient_address,
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect("propose");
+
+    let multisig_account_data = Account::unpack(
+        context
+            .banks_client
+            .get_account(multisig_address)
+            .await
+            .expect("get_account")
+            .expect("multisig_account not none")
+            .data(),
+    )
+    .expect("unpack");
+    assert!(multisig_account_data.last_updated_slot >= created_slot + 100);
+}
+
+#[tokio::test]
+async fn test_create_multisig_account_with_underfunded_address() {
+    let owner = Keypair::new();
+    let (multisig_address, _) = get_associated_address_and_bump_seed(&owner.pubkey(), &id());
+
+    // Someone sent a few lamports to the address before creation
+    let mut program_test = program_test();
+    program_test.add_account(
+        multisig_address,
+        SolanaAccount {
+            lamports: 1_000,
+            ..SolanaAccount::default()
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let rent = banks_client.get_rent().await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::create_associated_account(
+            &funder.pubkey(),
+            &owner.pubkey(),
+            solana_multisig::MultisigInstruction::CreateAccount {
+                threshold: 1,
+                owners: vec![Pubkey::new_unique()],
+                max_pending: MAX_TRANSACTIONS as u32,
+            }
+            .pack()
+            .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[&funder, &owner], recent_blockhash);
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("process_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("associated_account not none");
+
+    assert!(rent.is_exempt(multisig_account.lamports, solana_multisig::Account::LEN));
+    assert_eq!(
+        multisig_account.lamports,
+        rent.minimum_balance(solana_multisig::Account::LEN)
+    );
+}
+
+#[tokio::test]
+async fn test_create_multisig_account_with_zero_threshold() {
+    let owner = Keypair::new();
+    let (mut banks_client, funder, recent_blockhash) = program_test().start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::create_associated_account(
+            &funder.pubkey(),
+            &owner.pubkey(),
+            solana_multisig::MultisigInstruction::CreateAccount {
+                threshold: 0,
+                owners: vec![Pubkey::new_unique()],
+                max_pending: MAX_TRANSACTIONS as u32,
+            }
+            .pack()
+            .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[&funder, &owner], recent_blockhash);
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+    assert_custom_error(err, MultisigError::InvalidThreshold);
+}
+
+#[tokio::test]
+async fn test_pending_transaction_cap() {
+    let wallet = Keypair::new();
+    let custodian = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: 1,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // The cap must be between 1 and MAX_TRANSACTIONS at creation
+    for max_pending in [0, MAX_TRANSACTIONS as u32 + 1] {
+        let owner = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[solana_multisig::create_associated_account(
+                &funder.pubkey(),
+                &owner.pubkey(),
+                solana_multisig::MultisigInstruction::CreateAccount {
+                    threshold: 1,
+                    owners: vec![Pubkey::new_unique()],
+                    max_pending,
+                }
+                .pack()
+                .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, &owner], recent_blockhash);
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .expect_err("process_transaction")
+            .unwrap();
+        assert_custom_error(err, MultisigError::InvalidPendingLimit);
+    }
+
+    // A multisig capped at one pending transaction rejects the second
+    propose(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &Pubkey::new_unique(),
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect("propose");
+
+    let err = propose(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &Pubkey::new_unique(),
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("propose")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(MultisigError::PendingTransactionLimit as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_create_transaction_amount_overflow() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![Pubkey::new_unique()],
+            pending_transactions: vec![Pubkey::new_unique()],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Added to the frozen amount the proposal wraps around u64
+    let err = propose(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &Pubkey::new_unique(),
+        u64::MAX,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("propose")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(MultisigError::AmountOverflow as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_create_multisig_account_with_mismatched_address() {
+    let owner = Keypair::new();
+    let (mut banks_client, funder, recent_blockhash) = program_test().start().await;
+
+    // The associated account of another wallet
+    let mut instruction = solana_multisig::create_associated_account(
+        &funder.pubkey(),
+        &owner.pubkey(),
+        solana_multisig::MultisigInstruction::CreateAccount {
+            threshold: 1,
+            owners: vec![Pubkey::new_unique()],
+            max_pending: MAX_TRANSACTIONS as u32,
+        }
+        .pack()
+        .expect("pack"),
+    );
+    instruction.accounts[1].pubkey = get_associated_address(&Pubkey::new_unique());
+
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&funder.pubkey()));
+    transaction.sign(&[&funder, &owner], recent_blockhash);
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+#[tokio::test]
+async fn test_frozen_amount_exceeding_balance_is_rejected() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+
+    // Frozen amount has drifted above the real balance
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 50_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![(custodian.pubkey(), true)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::InsufficientBalance as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_set_reserve() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![wallet.pubkey()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::set_reserve(
+            &wallet.pubkey(),
+            solana_multisig::MultisigInstruction::SetReserve {
+                reserved_balance: 5_000_000,
+            }
+            .pack()
+            .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[&funder, &wallet], recent_blockhash);
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("process_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.reserved_balance, 5_000_000);
+}
+
+#[tokio::test]
+async fn test_create_transaction_respects_reserve() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![wallet.pubkey()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 8_000_000,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Spending into the reserve is rejected
+    let transaction_account = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::create_transaction(
+            &wallet.pubkey(),
+            &wallet.pubkey(),
+            &transaction_account.pubkey(),
+            &recipient_address,
+            solana_multisig::MultisigInstruction::CreateTransaction {
+                amount: 5_000_000,
+                memo: String::new(),
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                soft: false,
+                auto_approve: false,
+            }
+            .pack()
+            .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[&funder, &wallet, &transaction_account], recent_blockhash);
+
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::InsufficientBalance as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_create_transaction_above_reserve() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![wallet.pubkey()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 8_000_000,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let transaction_account = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::create_transaction(
+            &wallet.pubkey(),
+            &wallet.pubkey(),
+            &transaction_account.pubkey(),
+            &recipient_address,
+            solana_multisig::MultisigInstruction::CreateTransaction {
+                amount: 2_000_000,
+                memo: String::new(),
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                soft: false,
+                auto_approve: false,
+            }
+            .pack()
+            .expect("pack"),
+        )],
+        Some(&wallet.pubkey()),
+    );
+    transaction.sign(&[&wallet, &transaction_account], recent_blockhash);
+
+    // Fund the wallet so it can pay for the transaction account
+    let mut fund = Transaction::new_with_payer(
+        &[solana_sdk::system_instruction::transfer(
+            &funder.pubkey(),
+            &wallet.pubkey(),
+            100_000_000,
+        )],
+        Some(&funder.pubkey()),
+    );
+    fund.sign(&[&funder], recent_blockhash);
+    banks_client
+        .process_transaction(fund)
+        .await
+        .expect("process_transaction");
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("process_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.frozen_amount, 2_000_000);
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![transaction_account.pubkey()]
+    );
+}
+
+#[tokio::test]
+async fn test_approve_executed_transaction() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+
+    // Executed transaction is still referenced by the multisig
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Executed,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![
+                (custodians[0].pubkey(), true),
+                (custodians[1].pubkey(), false),
+            ],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Both a signed and an unsigned custodian are rejected
+    for custodian in &custodians {
+        let err = approve(
+            &mut banks_client,
+            &funder,
+            custodian,
+            &multisig_address,
+            &transaction_address,
+            recent_blockhash,
+        )
+        .await
+        .expect_err("approve")
+        .unwrap();
+
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(MultisigError::TransactionAlreadyExecuted as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_approve_closed_transaction() {
+    for (status, error) in [
+        (
+            TransactionStatus::Cancelled,
+            MultisigError::TransactionCancelled,
+        ),
+        (
+            TransactionStatus::Expired,
+            MultisigError::TransactionExpired,
+        ),
+    ] {
+        let custodian = Keypair::new();
+        let multisig_address = Pubkey::new_unique();
+        let transaction_address = Pubkey::new_unique();
+
+        let mut program_test = program_test();
+        add_multisig_account(
+            &mut program_test,
+            multisig_address,
+            10_000_000,
+            solana_multisig::Account {
+                is_initialized: true,
+                threshold: 1,
+                owners: vec![custodian.pubkey()],
+                pending_transactions: vec![transaction_address],
+                frozen_amount: 0,
+                reserved_balance: 0,
+                proposer_policy: ProposerPolicy::Wallet,
+                owner_weights: vec![],
+                strict_owner_match: false,
+                owners_version: 0,
+                nonce: 0,
+                spend_limits: vec![],
+                is_frozen: false,
+                last_updated_slot: 0,
+                max_pending: MAX_TRANSACTIONS as u32,
+                execution_delay_slots: 0,
+                new_owner_grace_secs: 0,
+                owner_added_at: vec![],
+            },
+        );
+        add_transaction_account(
+            &mut program_test,
+            transaction_address,
+            solana_multisig::Transaction {
+                multisig: multisig_address,
+                recipient: Pubkey::new_unique(),
+                amount: 1_000_000,
+                status,
+                is_sweep: false,
+                memo: String::new(),
+                outputs: vec![],
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                is_soft: false,
+                approvals: 0,
+                owners_version: 0,
+                nonce: 0,
+                signers: vec![(custodian.pubkey(), false)],
+                instruction: None,
+                creator: Pubkey::default(),
+                reached_threshold_slot: None,
+            },
+        );
+
+        let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+        // Only pending transactions can be approved, whatever the multisig lists
+        let err = approve(
+            &mut banks_client,
+            &funder,
+            &custodian,
+            &multisig_address,
+            &transaction_address,
+            recent_blockhash,
+        )
+        .await
+        .expect_err("approve")
+        .unwrap();
+        assert_custom_error(err, error);
+    }
+}
+
+#[tokio::test]
+async fn test_approve_twice() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 1,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 1,
+            signers: vec![
+                (custodians[0].pubkey(), false),
+                (custodians[1].pubkey(), false),
+            ],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    // A fresh blockhash, so the runtime doesn't drop the repeat as a duplicate
+    let recent_blockhash = banks_client
+        .get_new_latest_blockhash(&recent_blockhash)
+        .await
+        .expect("get_new_latest_blockhash");
+    let err = approve(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("approve")
+    .unwrap();
+    assert_custom_error(err, MultisigError::AlreadyApproved);
+}
+
+#[tokio::test]
+async fn test_approve_with_mismatched_multisig() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let stale_multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    // Both multisigs list the transaction, which belongs to the first only
+    for address in [multisig_address, stale_multisig_address] {
+        add_multisig_account(
+            &mut program_test,
+            address,
+            10_000_000,
+            solana_multisig::Account {
+                is_initialized: true,
+                threshold: 1,
+                owners: vec![custodian.pubkey()],
+                pending_transactions: vec![transaction_address],
+                frozen_amount: 1_000_000,
+                reserved_balance: 0,
+                proposer_policy: ProposerPolicy::Wallet,
+                owner_weights: vec![],
+                strict_owner_match: false,
+                owners_version: 0,
+                nonce: 1,
+                spend_limits: vec![],
+                is_frozen: false,
+                last_updated_slot: 0,
+                max_pending: MAX_TRANSACTIONS as u32,
+                execution_delay_slots: 0,
+                new_owner_grace_secs: 0,
+                owner_added_at: vec![],
+            },
+        );
+    }
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 1,
+            signers: vec![(custodian.pubkey(), false)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let err = approve(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &stale_multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("approve")
+    .unwrap();
+    assert_custom_error(err, MultisigError::UndefinedTransaction);
+
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+}
+
+#[tokio::test]
+async fn test_revoke_approval() {
+    let custodian = Keypair::new();
+    let stranger = Keypair::new();
+    let owner = Pubkey::new_unique();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: vec![custodian.pubkey(), owner],
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![(custodian.pubkey(), true), (owner, false)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    for signer in [&custodian, &stranger] {
+        let mut transaction = Transaction::new_with_payer(
+            &[solana_multisig::revoke_approval(
+                &signer.pubkey(),
+                &multisig_address,
+                &transaction_address,
+                solana_multisig::MultisigInstruction::RevokeApproval
+                    .pack()
+                    .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, signer], recent_blockhash);
+
+        let result = banks_client.process_transaction(transaction).await;
+        if signer.pubkey() == custodian.pubkey() {
+            result.expect("process_transaction");
+        } else {
+            assert_eq!(
+                result.expect_err("process_transaction").unwrap(),
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(MultisigError::InvalidCustodian as u32)
+                )
+            );
+        }
+    }
+
+    let transaction_account = banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+
+    let transaction_account_data =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account.data()).expect("unpack");
+    assert_eq!(
+        transaction_account_data.signers,
+        vec![(custodian.pubkey(), false), (owner, false)]
+    );
+}
+
+#[tokio::test]
+async fn test_execute_with_readonly_multisig() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![(custodian.pubkey(), true)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let mut instruction = solana_multisig::execute_transaction(
+        &custodian.pubkey(),
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        solana_multisig::MultisigInstruction::ExecuteTransaction
+            .pack()
+            .expect("pack"),
+    );
+    instruction.accounts[1] = AccountMeta::new_readonly(multisig_address, false);
+
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&funder.pubkey()));
+    transaction.sign(&[&funder, &custodian], recent_blockhash);
+
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_approve_requires_threshold() {
+    let custodians = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: custodians.iter().map(|c| (c.pubkey(), false)).collect(),
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // First approval doesn't reach the threshold
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::NotEnoughApprovals as u32)
+        )
+    );
+
+    // Second approval doesn't execute the transfer by itself
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        0
+    );
+
+    // Any owner can execute, including one who didn't approve
+    execute(
+        &mut banks_client,
+        &funder,
+        &custodians[2],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert!(multisig_account_data.pending_transactions.is_empty());
+    assert_eq!(multisig_account_data.frozen_amount, 0);
+}
+
+#[tokio::test]
+async fn test_create_multisig_account_with_duplicate_owners() {
+    let owner = Keypair::new();
+    let custodian_address = Pubkey::new_unique();
+
+    let (mut banks_client, funder, recent_blockhash) = program_test().start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::create_associated_account(
+            &funder.pubkey(),
+            &owner.pubkey(),
+            solana_multisig::MultisigInstruction::CreateAccount {
+                threshold: 1,
+                owners: vec![custodian_address, custodian_address],
+                max_pending: MAX_TRANSACTIONS as u32,
+            }
+            .pack()
+            .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[&funder, &owner], recent_blockhash);
+
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::DuplicateOwner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_execute_with_inconsistent_frozen_amount() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+
+    // Frozen amount doesn't cover the pending transaction
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![(custodian.pubkey(), true)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::AmountOverflow as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_sweep_resolves_balance_on_execution() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let frozen_transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    let rent_exempt = rent.minimum_balance(solana_multisig::Account::LEN);
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent_exempt + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![frozen_transaction_address, transaction_address],
+            frozen_amount: 2_000_000,
+            reserved_balance: 1_000_000,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 0,
+            status: TransactionStatus::Pending,
+            is_sweep: true,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![(custodian.pubkey(), false)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Balance grows after the sweep was created
+    let mut fund = Transaction::new_with_payer(
+        &[solana_sdk::system_instruction::transfer(
+            &funder.pubkey(),
+            &multisig_address,
+            5_000_000,
+        )],
+        Some(&funder.pubkey()),
+    );
+    fund.sign(&[&funder], recent_blockhash);
+    banks_client
+        .process_transaction(fund)
+        .await
+        .expect("process_transaction");
+
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    execute(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    // Reserved and frozen lamports stay on the multisig
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        12_000_000
+    );
+    assert_eq!(
+        banks_client
+            .get_balance(multisig_address)
+            .await
+            .expect("get_balance"),
+        rent_exempt + 3_000_000
+    );
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.frozen_amount, 2_000_000);
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![frozen_transaction_address]
+    );
+
+    let transaction_account = banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+
+    let transaction_account_data =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account.data()).expect("unpack");
+    assert_eq!(transaction_account_data.status, TransactionStatus::Executed);
+    assert_eq!(transaction_account_data.amount, 12_000_000);
+}
+
+#[tokio::test]
+async fn test_create_sweep_transaction() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    let rent_exempt = rent.minimum_balance(solana_multisig::Account::LEN);
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent_exempt + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![wallet.pubkey()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Fund the wallet so it can pay for the transaction account
+    let mut fund = Transaction::new_with_payer(
+        &[solana_sdk::system_instruction::transfer(
+            &funder.pubkey(),
+            &wallet.pubkey(),
+            100_000_000,
+        )],
+        Some(&funder.pubkey()),
+    );
+    fund.sign(&[&funder], recent_blockhash);
+    banks_client
+        .process_transaction(fund)
+        .await
+        .expect("process_transaction");
+
+    let transaction_account = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::create_transaction(
+            &wallet.pubkey(),
+            &wallet.pubkey(),
+            &transaction_account.pubkey(),
+            &recipient_address,
+            solana_multisig::MultisigInstruction::CreateSweepTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&wallet.pubkey()),
+    );
+    transaction.sign(&[&wallet, &transaction_account], recent_blockhash);
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("process_transaction");
+
+    // Sweep doesn't freeze anything until it is executed
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.frozen_amount, 0);
+
+    // Multisig is topped up after the sweep was created
+    let mut top_up = Transaction::new_with_payer(
+        &[solana_sdk::system_instruction::transfer(
+            &funder.pubkey(),
+            &multisig_address,
+            4_000_000,
+        )],
+        Some(&funder.pubkey()),
+    );
+    top_up.sign(&[&funder], recent_blockhash);
+    banks_client
+        .process_transaction(top_up)
+        .await
+        .expect("process_transaction");
+
+    approve(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &multisig_address,
+        &transaction_account.pubkey(),
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    execute(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &multisig_address,
+        &transaction_account.pubkey(),
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        14_000_000
+    );
+    assert_eq!(
+        banks_client
+            .get_balance(multisig_address)
+            .await
+            .expect("get_balance"),
+        rent_exempt
+    );
+}
+
+#[tokio::test]
+async fn test_execute_with_swapped_recipient() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+    let attacker_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![(custodian.pubkey(), true)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        &attacker_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::InvalidRecipient as u32)
+        )
+    );
+    assert_eq!(
+        banks_client
+            .get_balance(attacker_address)
+            .await
+            .expect("get_balance"),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_concurrent_approvals() {
+    let custodians = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 3,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: custodians.iter().map(|c| (c.pubkey(), false)).collect(),
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Both approvals are sent before either is confirmed and lock the same accounts
+    let mut signatures = vec![];
+    for custodian in &custodians[..2] {
+        let mut transaction = Transaction::new_with_payer(
+            &[solana_multisig::approve_transaction(
+                &custodian.pubkey(),
+                &multisig_address,
+                &transaction_address,
+                solana_multisig::MultisigInstruction::ApproveTransaction
+                    .pack()
+                    .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, custodian], recent_blockhash);
+        signatures.push(transaction.signatures[0]);
+        banks_client
+            .send_transaction(transaction)
+            .await
+            .expect("send_transaction");
+    }
+
+    // Wait until the runtime has serialized both approvals
+    let mut statuses = vec![];
+    for _ in 0..50 {
+        statuses = banks_client
+            .get_transaction_statuses(signatures.clone())
+            .await
+            .expect("get_transaction_statuses");
+        if statuses.iter().all(Option::is_some) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    for status in statuses {
+        assert_eq!(status.expect("transaction status").err, None);
+    }
+
+    let transaction_account = banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+
+    let transaction_account_data =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account.data()).expect("unpack");
+    assert_eq!(
+        transaction_account_data.signers,
+        vec![
+            (custodians[0].pubkey(), true),
+            (custodians[1].pubkey(), true),
+            (custodians[2].pubkey(), false),
+        ]
+    );
+    assert_eq!(
+        transaction_account_data
+            .signers
+            .iter()
+            .filter(|(_, is_signed)| *is_signed)
+            .count(),
+        2
+    );
+}
+
+async fn change_threshold(
+    banks_client: &mut BanksClient,
+    funder: &Keypair,
+    owners: &[&Keypair],
+    multisig_address: &Pubkey,
+    new_threshold: u32,
+    recent_blockhash: Hash,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::change_threshold(
+            multisig_address,
+            &owners
+                .iter()
+                .map(|owner| owner.pubkey())
+                .collect::<Vec<_>>(),
+            solana_multisig::MultisigInstruction::ChangeThreshold { new_threshold }
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+
+    let mut signers = vec![funder];
+    signers.extend_from_slice(owners);
+    transaction.sign(&signers, recent_blockhash);
+
+    banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn test_change_threshold() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 1,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![
+                (custodians[0].pubkey(), true),
+                (custodians[1].pubkey(), false),
+            ],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+    assert_custom_error(err, MultisigError::NotEnoughApprovals);
+
+    // Lower 2-of-2 to 1-of-2
+    change_threshold(
+        &mut banks_client,
+        &funder,
+        &[&custodians[0], &custodians[1]],
+        &multisig_address,
+        1,
+        recent_blockhash,
+    )
+    .await
+    .expect("change_threshold");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.threshold, 1);
+
+    // The existing approval is now enough, without approving again. A fresh
+    // blockhash, so the runtime doesn't drop the repeat as a duplicate
+    let recent_blockhash = banks_client
+        .get_new_latest_blockhash(&recent_blockhash)
+        .await
+        .expect("get_new_latest_blockhash");
+    execute(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_change_threshold_validation() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let stranger = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    for new_threshold in [0, 3] {
+        let err = change_threshold(
+            &mut banks_client,
+            &funder,
+            &[&custodians[0], &custodians[1]],
+            &multisig_address,
+            new_threshold,
+            recent_blockhash,
+        )
+        .await
+        .expect_err("change_threshold")
+        .unwrap();
+
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(MultisigError::CustodianLimit as u32)
+            )
+        );
+    }
+
+    // Non-owner signatures don't count towards the threshold
+    let err = change_threshold(
+        &mut banks_client,
+        &funder,
+        &[&custodians[0], &stranger],
+        &multisig_address,
+        1,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("change_threshold")
+    .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn propose(
+    banks_client: &mut BanksClient,
+    funder: &Keypair,
+    proposer: &Keypair,
+    wallet_address: &Pubkey,
+    transaction_account: &Keypair,
+    recipient_address: &Pubkey,
+    amount: u64,
+    recent_blockhash: Hash,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            solana_sdk::system_instruction::transfer(
+                &funder.pubkey(),
+                &proposer.pubkey(),
+                10_000_000,
+            ),
+            solana_multisig::create_transaction(
+                &proposer.pubkey(),
+                wallet_address,
+                &transaction_account.pubkey(),
+                recipient_address,
+                solana_multisig::MultisigInstruction::CreateTransaction {
+                    amount,
+                    memo: String::new(),
+                    not_before_slot: 0,
+                    expires_at_slot: 0,
+                    soft: false,
+                    auto_approve: false,
+                }
+                .pack()
+                .expect("pack"),
+            ),
+        ],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[funder, proposer, transaction_account], recent_blockhash);
+
+    banks_client.process_transaction(transaction).await
+}
+
+fn add_proposer_policy_multisig(
+    program_test: &mut ProgramTest,
+    wallet: &Keypair,
+    owners: &[Keypair],
+    proposer_policy: ProposerPolicy,
+) -> Pubkey {
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: owners.iter().map(|owner| owner.pubkey()).collect(),
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    multisig_address
+}
+
+#[tokio::test]
+async fn test_set_proposer_policy() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let set_proposer_policy = |proposer_policy: ProposerPolicy| {
+        let mut transaction = Transaction::new_with_payer(
+            &[solana_multisig::set_proposer_policy(
+                &multisig_address,
+                &[custodians[0].pubkey()],
+                solana_multisig::MultisigInstruction::SetProposerPolicy { proposer_policy }
+                    .pack()
+                    .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, &custodians[0]], recent_blockhash);
+        transaction
+    };
+
+    // Only owners can be allowed to propose
+    let err = banks_client
+        .process_transaction(set_proposer_policy(ProposerPolicy::Allowlist(vec![
+            Pubkey::new_unique(),
+        ])))
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::InvalidCustodian as u32)
+        )
+    );
+
+    let proposer_policy = ProposerPolicy::Allowlist(vec![custodians[1].pubkey()]);
+    banks_client
+        .process_transaction(set_proposer_policy(proposer_policy.clone()))
+        .await
+        .expect("process_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.proposer_policy, proposer_policy);
+}
+
+#[tokio::test]
+async fn test_proposer_policy_rejects_owner() {
+    let wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new()];
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+
+    let wallet_only = Keypair::new();
+    add_proposer_policy_multisig(
+        &mut program_test,
+        &wallet_only,
+        &owners,
+        ProposerPolicy::Wallet,
+    );
+    add_proposer_policy_multisig(
+        &mut program_test,
+        &wallet,
+        &owners,
+        ProposerPolicy::Allowlist(vec![owners[0].pubkey()]),
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Owner can't propose under the wallet policy,
+    // non-listed owner can't propose under the allowlist
+    for (wallet_address, proposer) in [
+        (wallet_only.pubkey(), &owners[0]),
+        (wallet.pubkey(), &owners[1]),
+    ] {
+        let err = propose(
+            &mut banks_client,
+            &funder,
+            proposer,
+            &wallet_address,
+            &Keypair::new(),
+            &recipient_address,
+            1_000_000,
+            recent_blockhash,
+        )
+        .await
+        .expect_err("propose")
+        .unwrap();
+
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(MultisigError::InvalidProposer as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_proposer_policy_allows_proposer() {
+    let wallet = Keypair::new();
+    let any_owner_wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new()];
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_proposer_policy_multisig(
+        &mut program_test,
+        &wallet,
+        &owners,
+        ProposerPolicy::Allowlist(vec![owners[0].pubkey()]),
+    );
+    let any_owner_multisig = add_proposer_policy_multisig(
+        &mut program_test,
+        &any_owner_wallet,
+        &owners,
+        ProposerPolicy::AnyOwner,
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Wallet can always propose
+    propose(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &recipient_address,
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect("propose");
+
+    // Owner can propose under the any owner policy
+    let transaction_account = Keypair::new();
+    propose(
+        &mut banks_client,
+        &funder,
+        &owners[1],
+        &any_owner_wallet.pubkey(),
+        &transaction_account,
+        &recipient_address,
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect("propose");
+
+    let multisig_account = banks_client
+        .get_account(any_owner_multisig)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![transaction_account.pubkey()]
+    );
+}
+
+#[tokio::test]
+async fn test_reject_self_transfer_and_zero_amount() {
+    let wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new()];
+
+    let mut program_test = program_test();
+    let multisig_address =
+        add_proposer_policy_multisig(&mut program_test, &wallet, &owners, ProposerPolicy::Wallet);
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    for (recipient_address, amount, error) in [
+        (multisig_address, 1_000_000, MultisigError::SelfTransfer),
+        (Pubkey::new_unique(), 0, MultisigError::ZeroAmount),
+    ] {
+        let err = propose(
+            &mut banks_client,
+            &funder,
+            &wallet,
+            &wallet.pubkey(),
+            &Keypair::new(),
+            &recipient_address,
+            amount,
+            recent_blockhash,
+        )
+        .await
+        .expect_err("propose")
+        .unwrap();
+
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(1, InstructionError::Custom(error as u32))
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_derived_transaction_address() {
+    let wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new()];
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let multisig_address =
+        add_proposer_policy_multisig(&mut program_test, &wallet, &owners, ProposerPolicy::Wallet);
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let create_derived = |nonce: u64, amount: u64| {
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                solana_sdk::system_instruction::transfer(
+                    &funder.pubkey(),
+                    &wallet.pubkey(),
+                    10_000_000,
+                ),
+                solana_multisig::create_derived_transaction(
+                    &wallet.pubkey(),
+                    &wallet.pubkey(),
+                    nonce,
+                    &recipient_address,
+                    solana_multisig::MultisigInstruction::CreateTransaction {
+                        amount,
+                        memo: String::new(),
+                        not_before_slot: 0,
+                        expires_at_slot: 0,
+                        soft: false,
+                        auto_approve: false,
+                    }
+                    .pack()
+                    .expect("pack"),
+                ),
+            ],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, &wallet], recent_blockhash);
+        transaction
+    };
+
+    // Every creation takes the address derived from the next nonce
+    for nonce in 1..=2 {
+        banks_client
+            .process_transaction(create_derived(nonce, 1_000_000 + nonce))
+            .await
+            .expect("process_transaction");
+
+        let transaction_address = get_transaction_address(&multisig_address, nonce);
+        let transaction_account_data = solana_multisig::Transaction::unpack_unchecked(
+            banks_client
+                .get_account(transaction_address)
+                .await
+                .expect("get_account")
+                .expect("transaction_account not none")
+                .data(),
+        )
+        .expect("unpack");
+        assert_eq!(transaction_account_data.nonce, nonce);
+        assert_eq!(transaction_account_data.amount, 1_000_000 + nonce);
+    }
+
+    let multisig_account_data = Account::unpack(
+        banks_client
+            .get_account(multisig_address)
+            .await
+            .expect("get_account")
+            .expect("multisig_account not none")
+            .data(),
+    )
+    .expect("unpack");
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![
+            get_transaction_address(&multisig_address, 1),
+            get_transaction_address(&multisig_address, 2)
+        ]
+    );
+
+    // A used nonce no longer derives the next address
+    let err = banks_client
+        .process_transaction(create_derived(1, 1_000_000))
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn test_deposit_and_propose() {
+    let wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new()];
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let multisig_address =
+        add_proposer_policy_multisig(&mut program_test, &wallet, &owners, ProposerPolicy::Wallet);
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let create_funded = |deposit: u64| {
+        let mut instructions = vec![];
+        if deposit > 0 {
+            instructions.push(solana_sdk::system_instruction::transfer(
+                &funder.pubkey(),
+                &multisig_address,
+                deposit,
+            ));
+        }
+        instructions.push(solana_multisig::create_derived_transaction(
+            &funder.pubkey(),
+            &wallet.pubkey(),
+            1,
+            &recipient_address,
+            solana_multisig::MultisigInstruction::CreateTransaction {
+                amount: 15_000_000,
+                memo: String::new(),
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                soft: false,
+                auto_approve: false,
+            }
+            .pack()
+            .expect("pack"),
+        ));
+
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&funder.pubkey()));
+        transaction.sign(&[&funder, &wallet], recent_blockhash);
+        transaction
+    };
+
+    // The multisig only holds 10M lamports above rent
+    let err = banks_client
+        .process_transaction(create_funded(0))
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+    assert_custom_error(err, MultisigError::InsufficientBalance);
+
+    // A deposit in the same transaction covers the proposal
+    banks_client
+        .process_transaction(create_funded(5_000_000))
+        .await
+        .expect("process_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let rent = solana_sdk::rent::Rent::default();
+    assert_eq!(
+        multisig_account.lamports,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 15_000_000
+    );
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.frozen_amount, 15_000_000);
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![get_transaction_address(&multisig_address, 1)]
+    );
+}
+
+#[tokio::test]
+async fn test_auto_approve_executes_immediately() {
+    let wallet = Keypair::new();
+    let owners = [Keypair::new()];
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let multisig_address = add_proposer_policy_multisig(
+        &mut program_test,
+        &wallet,
+        &owners,
+        ProposerPolicy::AnyOwner,
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let rent = banks_client.get_rent().await.expect("get_rent");
+    let amount = rent.minimum_balance(0);
+
+    let auto_approve = |proposer: &Keypair, transaction_account: &Keypair| {
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                solana_sdk::system_instruction::transfer(
+                    &funder.pubkey(),
+                    &proposer.pubkey(),
+                    10_000_000,
+                ),
+                solana_multisig::create_approved_transaction(
+                    &proposer.pubkey(),
+                    &wallet.pubkey(),
+                    &transaction_account.pubkey(),
+                    &recipient_address,
+                    solana_multisig::MultisigInstruction::CreateTransaction {
+                        amount,
+                        memo: String::new(),
+                        not_before_slot: 0,
+                        expires_at_slot: 0,
+                        soft: false,
+                        auto_approve: true,
+                    }
+                    .pack()
+                    .expect("pack"),
+                ),
+            ],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, proposer, transaction_account], recent_blockhash);
+        transaction
+    };
+
+    // The only owner's approval reaches the threshold, so the transfer is
+    // executed without ever being pending
+    let transaction_account = Keypair::new();
+    banks_client
+        .process_transaction(auto_approve(&owners[0], &transaction_account))
+        .await
+        .expect("process_transaction");
+
+    let recipient_account = banks_client
+        .get_account(recipient_address)
+        .await
+        .expect("get_account")
+        .expect("recipient_account not none");
+    assert_eq!(recipient_account.lamports, amount);
+
+    let transaction_account_data = solana_multisig::Transaction::unpack_unchecked(
+        banks_client
+            .get_account(transaction_account.pubkey())
+            .await
+            .expect("get_account")
+            .expect("transaction_account not none")
+            .data(),
+    )
+    .expect("unpack");
+    assert_eq!(transaction_account_data.status, TransactionStatus::Executed);
+    assert_eq!(
+        transaction_account_data.signers,
+        vec![(owners[0].pubkey(), true)]
+    );
+
+    let multisig_account_data = Account::unpack(
+        banks_client
+            .get_account(multisig_address)
+            .await
+            .expect("get_account")
+            .expect("multisig_account not none")
+            .data(),
+    )
+    .expect("unpack");
+    assert!(multisig_account_data.pending_transactions.is_empty());
+    assert_eq!(multisig_account_data.frozen_amount, 0);
+
+    // The wallet isn't an owner, its transaction stays pending unapproved
+    let transaction_account = Keypair::new();
+    banks_client
+        .process_transaction(auto_approve(&wallet, &transaction_account))
+        .await
+        .expect("process_transaction");
+
+    let transaction_account_data = solana_multisig::Transaction::unpack_unchecked(
+        banks_client
+            .get_account(transaction_account.pubkey())
+            .await
+            .expect("get_account")
+            .expect("transaction_account not none")
+            .data(),
+    )
+    .expect("unpack");
+    assert_eq!(transaction_account_data.status, TransactionStatus::Pending);
+    assert_eq!(
+        transaction_account_data.signers,
+        vec![(owners[0].pubkey(), false)]
+    );
+
+    let multisig_account_data = Account::unpack(
+        banks_client
+            .get_account(multisig_address)
+            .await
+            .expect("get_account")
+            .expect("multisig_account not none")
+            .data(),
+    )
+    .expect("unpack");
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![transaction_account.pubkey()]
+    );
+}
+
+#[tokio::test]
+async fn test_spend_limit() {
+    let wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new()];
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let multisig_address = add_proposer_policy_multisig(
+        &mut program_test,
+        &wallet,
+        &owners,
+        ProposerPolicy::AnyOwner,
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &[&owners[0]],
+        solana_multisig::set_spend_limit(
+            &multisig_address,
+            &[owners[0].pubkey()],
+            solana_multisig::MultisigInstruction::SetSpendLimit {
+                owner: owners[1].pubkey(),
+                limit: Some(1_000_000),
+            }
+            .pack()
+            .expect("pack"),
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect("process_transaction");
+
+    // Owner can propose up to its limit
+    propose(
+        &mut banks_client,
+        &funder,
+        &owners[1],
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &recipient_address,
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect("propose");
+
+    let err = propose(
+        &mut banks_client,
+        &funder,
+        &owners[1],
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &recipient_address,
+        1_000_001,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("propose")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(MultisigError::SpendLimitExceeded as u32)
+        )
+    );
+
+    // Owners without a limit and the wallet are unlimited
+    for proposer in [&owners[0], &wallet] {
+        propose(
+            &mut banks_client,
+            &funder,
+            proposer,
+            &wallet.pubkey(),
+            &Keypair::new(),
+            &recipient_address,
+            2_000_000,
+            recent_blockhash,
+        )
+        .await
+        .expect("propose");
+    }
+}
+
+#[tokio::test]
+async fn test_emergency_freeze() {
+    let wallet = Keypair::new();
+    let owners = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: owners.iter().map(|owner| owner.pubkey()).collect(),
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let set_frozen = |signers: &[&Keypair], frozen: bool| {
+        solana_multisig::set_frozen(
+            &multisig_address,
+            &signers
+                .iter()
+                .map(|signer| signer.pubkey())
+                .collect::<Vec<_>>(),
+            MultisigInstruction::SetFrozen { frozen }
+                .pack()
+                .expect("pack"),
+        )
+    };
+
+    // Only owners can freeze
+    let outsider = Keypair::new();
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &[&outsider],
+        set_frozen(&[&outsider], true),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("set_frozen")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // A single owner is enough to freeze
+    process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &[&owners[0]],
+        set_frozen(&[&owners[0]], true),
+        recent_blockhash,
+    )
+    .await
+    .expect("set_frozen");
+
+    let err = propose(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &recipient_address,
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("propose")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(MultisigError::WalletFrozen as u32)
+        )
+    );
+
+    // Unfreezing takes the threshold
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &[&owners[1]],
+        set_frozen(&[&owners[1]], false),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("set_frozen")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &[&owners[1], &owners[2]],
+        set_frozen(&[&owners[1], &owners[2]], false),
+        recent_blockhash,
+    )
+    .await
+    .expect("set_frozen");
+
+    propose(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &wallet.pubkey(),
+        &Keypair::new(),
+        &recipient_address,
+        1_000_000,
+        recent_blockhash,
+    )
+    .await
+    .expect("propose");
+}
+
+async fn process_signed_by_owners(
+    banks_client: &mut BanksClient,
+    funder: &Keypair,
+    owners: &[&Keypair],
+    instruction: solana_program::instruction::Instruction,
+    recent_blockhash: Hash,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&funder.pubkey()));
+
+    let mut signers = vec![funder];
+    signers.extend_from_slice(owners);
+    transaction.sign(&signers, recent_blockhash);
+
+    banks_client.process_transaction(transaction).await
+}
+
+fn owner_instruction(
+    multisig_address: &Pubkey,
+    owners: &[&Keypair],
+    instruction: MultisigInstruction,
+) -> solana_program::instruction::Instruction {
+    let owners = owners
+        .iter()
+        .map(|owner| owner.pubkey())
+        .collect::<Vec<_>>();
+    let data = instruction.pack().expect("pack");
+    match instruction {
+        MultisigInstruction::AddOwner { .. } => {
+            add_owner(multisig_address, &owners[0], &owners, data)
+        }
+        _ => remove_owner(multisig_address, &owners, data),
+    }
+}
+
+fn assert_custom_error(err: TransactionError, error: MultisigError) {
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+    );
+}
+
+#[tokio::test]
+async fn test_reconcile() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let pending_address = Pubkey::new_unique();
+    let soft_address = Pubkey::new_unique();
+    let closed_address = Pubkey::new_unique();
+    let pending_transactions = vec![pending_address, closed_address, soft_address];
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: pending_transactions.clone(),
+            // Still counts the amount of the closed transaction
+            frozen_amount: 3_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 3,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    for (address, is_soft) in [(pending_address, false), (soft_address, true)] {
+        add_transaction_account(
+            &mut program_test,
+            address,
+            solana_multisig::Transaction {
+                multisig: multisig_address,
+                recipient: Pubkey::new_unique(),
+                amount: 1_000_000,
+                status: TransactionStatus::Pending,
+                is_sweep: false,
+                memo: String::new(),
+                outputs: vec![],
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                is_soft,
+                approvals: 0,
+                owners_version: 0,
+                nonce: 1,
+                signers: vec![
+                    (custodians[0].pubkey(), false),
+                    (custodians[1].pubkey(), false),
+                ],
+                instruction: None,
+                creator: Pubkey::default(),
+                reached_threshold_slot: None,
+            },
+        );
+    }
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let reconcile_instruction = |owners: &[&Keypair], transactions: &[Pubkey]| {
+        reconcile(
+            &multisig_address,
+            transactions,
+            &owners
+                .iter()
+                .map(|owner| owner.pubkey())
+                .collect::<Vec<_>>(),
+            MultisigInstruction::Reconcile.pack().expect("pack"),
+        )
+    };
+
+    // Below the threshold
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &[&custodians[0]],
+        reconcile_instruction(&[&custodians[0]], &pending_transactions),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("reconcile")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // Pending transactions must all be passed, in order
+    let mut reordered = pending_transactions.clone();
+    reordered.reverse();
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &[&custodians[0], &custodians[1]],
+        reconcile_instruction(&[&custodians[0], &custodians[1]], &reordered),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("reconcile")
+    .unwrap();
+    assert_custom_error(err, MultisigError::UndefinedTransaction);
+
+    process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &[&custodians[0], &custodians[1]],
+        reconcile_instruction(&[&custodians[0], &custodians[1]], &pending_transactions),
+        recent_blockhash,
+    )
+    .await
+    .expect("reconcile");
+
+    // Only the funded pending transaction stays frozen
+    let multisig_account_data = Account::unpack(
+        banks_client
+            .get_account(multisig_address)
+            .await
+            .expect("get_account")
+            .expect("multisig_account not none")
+            .data(),
+    )
+    .expect("unpack");
+    assert_eq!(multisig_account_data.frozen_amount, 1_000_000);
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![pending_address, soft_address]
+    );
+}
+
+#[tokio::test]
+async fn test_add_owner() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let full_multisig_address = Pubkey::new_unique();
+    let legacy_multisig_address = Pubkey::new_unique();
+    let pending_multisig_address = Pubkey::new_unique();
+    let new_owner = Pubkey::new_unique();
+
+    let multisig =
+        |owners: Vec<Pubkey>, pending_transactions: Vec<Pubkey>| solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners,
+            pending_transactions,
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        };
+    let owners = custodians.iter().map(|c| c.pubkey()).collect::<Vec<_>>();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        multisig(owners.clone(), vec![]),
+    );
+
+    let mut full_owners = owners.clone();
+    full_owners.extend((owners.len()..MAX_SIGNERS).map(|_| Pubkey::new_unique()));
+    add_multisig_account(
+        &mut program_test,
+        full_multisig_address,
+        10_000_000,
+        multisig(full_owners, vec![]),
+    );
+
+    // First release accounts are full at the legacy limit
+    let mut legacy_owners = owners.clone();
+    legacy_owners.extend((owners.len()..LEGACY_MAX_SIGNERS).map(|_| Pubkey::new_unique()));
+    let mut data = vec![0; solana_multisig::Account::LEGACY_LEN];
+    solana_multisig::Account::pack(multisig(legacy_owners.clone(), vec![]), &mut data)
+        .expect("pack");
+    program_test.add_account(
+        legacy_multisig_address,
+        SolanaAccount {
+            lamports: 10_000_000,
+            data,
+            owner: id(),
+            ..SolanaAccount::default()
+        },
+    );
+    add_multisig_account(
+        &mut program_test,
+        pending_multisig_address,
+        10_000_000,
+        multisig(owners.clone(), vec![Pubkey::new_unique()]),
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+    let signers = [&custodians[0], &custodians[1]];
+
+    // Threshold owners must sign
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers[..1],
+        owner_instruction(
+            &multisig_address,
+            &signers[..1],
+            MultisigInstruction::AddOwner { owner: new_owner },
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("add_owner")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    for (address, owner, error) in [
+        (multisig_address, owners[0], MultisigError::DuplicateOwner),
+        (
+            full_multisig_address,
+            new_owner,
+            MultisigError::CustodianLimit,
+        ),
+        (
+            pending_multisig_address,
+            new_owner,
+            MultisigError::PendingTransactionsExist,
+        ),
+    ] {
+        let err = process_signed_by_owners(
+            &mut banks_client,
+            &funder,
+            &signers,
+            owner_instruction(&address, &signers, MultisigInstruction::AddOwner { owner }),
+            recent_blockhash,
+        )
+        .await
+        .expect_err("add_owner")
+        .unwrap();
+        assert_custom_error(err, error);
+    }
+
+    process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers,
+        owner_instruction(
+            &multisig_address,
+            &signers,
+            MultisigInstruction::AddOwner { owner: new_owner },
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect("add_owner");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(
+        multisig_account_data.owners,
+        vec![owners[0], owners[1], new_owner]
+    );
+    let clock = banks_client
+        .get_sysvar::<solana_program::clock::Clock>()
+        .await
+        .expect("get_sysvar");
+    assert_eq!(
+        multisig_account_data.owner_added_at,
+        vec![0, 0, clock.unix_timestamp]
+    );
+
+    // A full first release account is grown, the funder pays the rent
+    let funder_balance = banks_client
+        .get_balance(funder.pubkey())
+        .await
+        .expect("get_balance");
+    process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers,
+        add_owner(
+            &legacy_multisig_address,
+            &funder.pubkey(),
+            &owners,
+            MultisigInstruction::AddOwner { owner: new_owner }
+                .pack()
+                .expect("pack"),
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect("add_owner");
+
+    let rent = banks_client.get_rent().await.expect("get_rent");
+    let rent_difference = rent.minimum_balance(solana_multisig::Account::LEN)
+        - rent.minimum_balance(solana_multisig::Account::LEGACY_LEN);
+    let multisig_account = banks_client
+        .get_account(legacy_multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    assert_eq!(multisig_account.data.len(), solana_multisig::Account::LEN);
+    assert_eq!(multisig_account.lamports, 10_000_000 + rent_difference);
+    assert!(
+        banks_client
+            .get_balance(funder.pubkey())
+            .await
+            .expect("get_balance")
+            <= funder_balance - rent_difference
+    );
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    legacy_owners.push(new_owner);
+    assert_eq!(multisig_account_data.owners, legacy_owners);
+}
+
+#[tokio::test]
+async fn test_remove_owner() {
+    let custodians = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let owners = custodians.iter().map(|c| c.pubkey()).collect::<Vec<_>>();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: owners.clone(),
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Allowlist(vec![owners[1], owners[2]]),
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+    let signers = [&custodians[0], &custodians[1]];
+
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers,
+        owner_instruction(
+            &multisig_address,
+            &signers,
+            MultisigInstruction::RemoveOwner {
+                owner: Pubkey::new_unique(),
+            },
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("remove_owner")
+    .unwrap();
+    assert_custom_error(err, MultisigError::InvalidCustodian);
+
+    process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers,
+        owner_instruction(
+            &multisig_address,
+            &signers,
+            MultisigInstruction::RemoveOwner { owner: owners[2] },
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect("remove_owner");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.owners, vec![owners[0], owners[1]]);
+    assert_eq!(
+        multisig_account_data.proposer_policy,
+        ProposerPolicy::Allowlist(vec![owners[1]])
+    );
+
+    // Two owners left for 2-of-N, none can be removed
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers,
+        owner_instruction(
+            &multisig_address,
+            &signers,
+            MultisigInstruction::RemoveOwner { owner: owners[1] },
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("remove_owner")
+    .unwrap();
+    assert_custom_error(err, MultisigError::CustodianLimit);
+}
+
+#[tokio::test]
+async fn test_close_account() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let pending_multisig_address = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let owners = custodians.iter().map(|c| c.pubkey()).collect::<Vec<_>>();
+
+    let multisig =
+        |pending_transactions: Vec<Pubkey>, frozen_amount: u64| solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: owners.clone(),
+            pending_transactions,
+            frozen_amount,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        };
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        multisig(vec![], 0),
+    );
+    add_multisig_account(
+        &mut program_test,
+        pending_multisig_address,
+        10_000_000,
+        multisig(vec![Pubkey::new_unique()], 1_000),
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+    let signers = [&custodians[0], &custodians[1]];
+    let data = MultisigInstruction::CloseAccount.pack().expect("pack");
+
+    // Threshold owners must sign
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers[..1],
+        close_account(&multisig_address, &destination, &owners[..1], data.clone()),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("close_account")
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers,
+        close_account(
+            &pending_multisig_address,
+            &destination,
+            &owners,
+            data.clone(),
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("close_account")
+    .unwrap();
+    assert_custom_error(err, MultisigError::PendingTransactionsExist);
+
+    process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers,
+        close_account(&multisig_address, &destination, &owners, data),
+        recent_blockhash,
+    )
+    .await
+    .expect("close_account");
+
+    let destination_account = banks_client
+        .get_account(destination)
+        .await
+        .expect("get_account")
+        .expect("destination_account not none");
+    assert_eq!(destination_account.lamports, 10_000_000);
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account");
+    assert!(multisig_account.is_none());
+}
+
+#[tokio::test]
+async fn test_close_transaction() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![(custodian.pubkey(), true)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let close = |destination: &Pubkey| {
+        let mut transaction = Transaction::new_with_payer(
+            &[close_transaction(
+                &custodian.pubkey(),
+                &multisig_address,
+                &transaction_address,
+                destination,
+                MultisigInstruction::CloseTransaction.pack().expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, &custodian], recent_blockhash);
+        transaction
+    };
+
+    let err = banks_client
+        .process_transaction(close(&Pubkey::new_unique()))
+        .await
+        .expect_err("close_transaction")
+        .unwrap();
+    assert_custom_error(err, MultisigError::TransactionNotExecuted);
+
+    execute(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    banks_client
+        .process_transaction(close(&destination))
+        .await
+        .expect("close_transaction");
+
+    let destination_account = banks_client
+        .get_account(destination)
+        .await
+        .expect("get_account")
+        .expect("destination_account not none");
+    assert_eq!(destination_account.lamports, 1_000_000);
+
+    let transaction_account = banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account");
+    assert!(transaction_account.is_none());
+}
+
+#[tokio::test]
+async fn test_execute_multi_transfer() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipients = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let outputs = vec![
+        (recipients[0], 1_000_000),
+        (recipients[1], 2_000_000),
+        (recipients[2], 3_000_000),
+    ];
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        20_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 6_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipients[0],
+            amount: 6_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![(custodian.pubkey(), true)],
+            memo: String::new(),
+            outputs: outputs.clone(),
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let execute = |recipients: &[Pubkey]| {
+        let mut transaction = Transaction::new_with_payer(
+            &[execute_multi_transfer(
+                &custodian.pubkey(),
+                &multisig_address,
+                &transaction_address,
+                recipients,
+                MultisigInstruction::ExecuteTransaction
+                    .pack()
+                    .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, &custodian], recent_blockhash);
+        transaction
+    };
+
+    // One invalid recipient fails the whole payout
+    let err = banks_client
+        .process_transaction(execute(&[
+            recipients[0],
+            recipients[1],
+            Pubkey::new_unique(),
+        ]))
+        .await
+        .expect_err("execute")
+        .unwrap();
+    assert_custom_error(err, MultisigError::InvalidRecipient);
+
+    for recipient in recipients {
+        assert_eq!(
+            banks_client
+                .get_balance(recipient)
+                .await
+                .expect("get_balance"),
+            0
+        );
+    }
+
+    banks_client
+        .process_transaction(execute(&recipients))
+        .await
+        .expect("execute");
+
+    for (recipient, amount) in outputs {
+        assert_eq!(
+            banks_client
+                .get_balance(recipient)
+                .await
+                .expect("get_balance"),
+            amount
+        );
+    }
+    assert_eq!(
+        banks_client
+            .get_balance(multisig_address)
+            .await
+            .expect("get_balance"),
+        14_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_create_multi_transfer_validation() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let recipient = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        100_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![wallet.pubkey()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let too_many_outputs = (0..=MAX_OUTPUTS)
+        .map(|_| (recipient, 1_000))
+        .collect::<Vec<_>>();
+
+    for (outputs, error) in [
+        (vec![], MultisigError::OutputLimit),
+        (too_many_outputs, MultisigError::OutputLimit),
+        (
+            vec![(recipient, u64::MAX), (recipient, 1)],
+            MultisigError::AmountOverflow,
+        ),
+        (
+            // The recipient account must be the first output
+            vec![(Pubkey::new_unique(), 1_000), (recipient, 1_000)],
+            MultisigError::InvalidRecipient,
+        ),
+    ] {
+        let transaction_account = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[create_transaction(
+                &wallet.pubkey(),
+                &wallet.pubkey(),
+                &transaction_account.pubkey(),
+                &recipient,
+                MultisigInstruction::CreateMultiTransfer {
+                    outputs,
+                    memo: String::new(),
+                }
+                .pack()
+                .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, &wallet, &transaction_account], recent_blockhash);
+
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .expect_err("create_multi_transfer")
+            .unwrap();
+        assert_custom_error(err, error);
+    }
+}
+
+#[tokio::test]
+async fn test_timelocked_transaction() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+    let not_before_slot = 1_000;
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![
+                (custodians[0].pubkey(), true),
+                (custodians[1].pubkey(), false),
+            ],
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let mut context = program_test.start_with_context().await;
+
+    // Approvals are recorded while the timelock is active
+    approve(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        context.last_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let err = execute(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        context.last_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+    assert_custom_error(err, MultisigError::TimelockActive);
+
+    context.warp_to_slot(not_before_slot).expect("warp_to_slot");
+    let recent_blockhash = context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .expect("get_latest_blockhash");
+
+    execute(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_execution_delay() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+    let execution_delay_slots = 500;
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![
+                (custodians[0].pubkey(), true),
+                (custodians[1].pubkey(), false),
+            ],
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let mut context = program_test.start_with_context().await;
+    let signers = [&custodians[0], &custodians[1]];
+
+    process_signed_by_owners(
+        &mut context.banks_client,
+        &context.payer,
+        &signers,
+        solana_multisig::set_execution_delay(
+            &multisig_address,
+            &signers
+                .iter()
+                .map(|signer| signer.pubkey())
+                .collect::<Vec<_>>(),
+            MultisigInstruction::SetExecutionDelay {
+                slots: execution_delay_slots,
+            }
+            .pack()
+            .expect("pack"),
+        ),
+        context.last_blockhash,
+    )
+    .await
+    .expect("set_execution_delay");
+
+    // The approval reaching the threshold starts the delay
+    approve(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        context.last_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let transaction_account = context
+        .banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+    let reached_threshold_slot =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account.data())
+            .expect("unpack")
+            .reached_threshold_slot
+            .expect("reached_threshold_slot recorded");
+
+    let err = execute(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        context.last_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+    assert_custom_error(err, MultisigError::ExecutionDelayActive);
+
+    context
+        .warp_to_slot(reached_threshold_slot + execution_delay_slots)
+        .expect("warp_to_slot");
+    let recent_blockhash = context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .expect("get_latest_blockhash");
+
+    execute(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_owner_grace() {
+    let custodians = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let added_at = 1_000_000;
+    let new_owner_grace_secs = 3_600;
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![0, 0, added_at],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: custodians.iter().map(|c| (c.pubkey(), false)).collect(),
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let mut context = program_test.start_with_context().await;
+    let signers = [&custodians[0], &custodians[1]];
+
+    // The third owner was added a moment ago
+    let mut clock = context
+        .banks_client
+        .get_sysvar::<solana_program::clock::Clock>()
+        .await
+        .expect("get_sysvar");
+    clock.unix_timestamp = added_at + 1;
+    context.set_sysvar(&clock);
+
+    process_signed_by_owners(
+        &mut context.banks_client,
+        &context.payer,
+        &signers,
+        solana_multisig::set_owner_grace(
+            &multisig_address,
+            &signers
+                .iter()
+                .map(|signer| signer.pubkey())
+                .collect::<Vec<_>>(),
+            MultisigInstruction::SetOwnerGrace {
+                secs: new_owner_grace_secs,
+            }
+            .pack()
+            .expect("pack"),
+        ),
+        context.last_blockhash,
+    )
+    .await
+    .expect("set_owner_grace");
+
+    // Owners the multisig was created with aren't held back
+    approve(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        context.last_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let err = approve(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[2],
+        &multisig_address,
+        &transaction_address,
+        context.last_blockhash,
+    )
+    .await
+    .expect_err("approve")
+    .unwrap();
+    assert_custom_error(err, MultisigError::OwnerInGrace);
+
+    // Once the window ends the added owner approves like any other. A fresh
+    // blockhash, so the runtime doesn't drop the repeat as a duplicate
+    clock.unix_timestamp = added_at + new_owner_grace_secs;
+    context.set_sysvar(&clock);
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .expect("get_new_latest_blockhash");
+
+    approve(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[2],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let transaction_account = context
+        .banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+    let transaction =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account.data()).expect("unpack");
+    assert_eq!(
+        transaction.signers,
+        vec![
+            (custodians[0].pubkey(), true),
+            (custodians[1].pubkey(), false),
+            (custodians[2].pubkey(), true),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_expired_transaction() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let expires_at_slot = 1_000;
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![
+                (custodians[0].pubkey(), true),
+                (custodians[1].pubkey(), false),
+            ],
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let mut context = program_test.start_with_context().await;
+
+    let reap = |payer: &Keypair, recent_blockhash: Hash| {
+        let mut transaction = Transaction::new_with_payer(
+            &[reap_expired(
+                &custodians[0].pubkey(),
+                &multisig_address,
+                &transaction_address,
+                &destination,
+                MultisigInstruction::ReapExpired.pack().expect("pack"),
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[payer, &custodians[0]], recent_blockhash);
+        transaction
+    };
+
+    let err = context
+        .banks_client
+        .process_transaction(reap(&context.payer, context.last_blockhash))
+        .await
+        .expect_err("reap_expired")
+        .unwrap();
+    assert_custom_error(err, MultisigError::TransactionNotExpired);
+
+    context.warp_to_slot(expires_at_slot).expect("warp_to_slot");
+    let recent_blockhash = context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .expect("get_latest_blockhash");
+
+    let err = approve(
+        &mut context.banks_client,
+        &context.payer,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("approve")
+    .unwrap();
+    assert_custom_error(err, MultisigError::TransactionExpired);
+
+    context
+        .banks_client
+        .process_transaction(reap(&context.payer, recent_blockhash))
+        .await
+        .expect("reap_expired");
+
+    let multisig_account = context
+        .banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert!(multisig_account_data.pending_transactions.is_empty());
+    assert_eq!(multisig_account_data.frozen_amount, 0);
+    assert_eq!(multisig_account.lamports, 10_000_000);
+
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(destination)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+    assert!(context
+        .banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account")
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_cancel_transaction() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![
+                (custodians[0].pubkey(), true),
+                (custodians[1].pubkey(), false),
+            ],
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let cancel = |owners: &[&Keypair]| {
+        let mut transaction = Transaction::new_with_payer(
+            &[cancel_transaction(
+                &multisig_address,
+                &transaction_address,
+                &destination,
+                &owners
+                    .iter()
+                    .map(|owner| owner.pubkey())
+                    .collect::<Vec<_>>(),
+                MultisigInstruction::CancelTransaction.pack().expect("pack"),
+            )],
+            Some(&payer.pubkey()),
+        );
+        let mut signers = vec![&payer];
+        signers.extend_from_slice(owners);
+        transaction.sign(&signers, recent_blockhash);
+        transaction
+    };
+
+    // A single owner is below the threshold
+    let err = banks_client
+        .process_transaction(cancel(&[&custodians[0]]))
+        .await
+        .expect_err("cancel_transaction")
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    banks_client
+        .process_transaction(cancel(&[&custodians[0], &custodians[1]]))
+        .await
+        .expect("cancel_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert!(multisig_account_data.pending_transactions.is_empty());
+    assert_eq!(multisig_account_data.frozen_amount, 0);
+    assert_eq!(multisig_account.lamports, 10_000_000);
+
+    assert_eq!(
+        banks_client
+            .get_balance(destination)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+    assert!(banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account")
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_aliased_accounts() {
+    let custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            signers: vec![(custodian.pubkey(), true)],
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Multisig passed as the transaction
+    let err = approve(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &multisig_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("approve")
+    .unwrap();
+    assert_custom_error(err, MultisigError::DuplicateAccount);
+
+    // Multisig passed as the recipient
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        &multisig_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+    assert_custom_error(err, MultisigError::DuplicateAccount);
+
+    execute(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+}
+
+#[tokio::test]
+async fn test_create_soft_transaction() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let transaction_account = Keypair::new();
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![wallet.pubkey()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // Fund the wallet so it can pay for the transaction account
+    let mut fund = Transaction::new_with_payer(
+        &[solana_sdk::system_instruction::transfer(
+            &funder.pubkey(),
+            &wallet.pubkey(),
+            100_000_000,
+        )],
+        Some(&funder.pubkey()),
+    );
+    fund.sign(&[&funder], recent_blockhash);
+    banks_client
+        .process_transaction(fund)
+        .await
+        .expect("process_transaction");
+
+    // Soft proposals may exceed the balance, nothing is frozen yet
+    let mut transaction = Transaction::new_with_payer(
+        &[solana_multisig::create_transaction(
+            &wallet.pubkey(),
+            &wallet.pubkey(),
+            &transaction_account.pubkey(),
+            &Pubkey::new_unique(),
+            solana_multisig::MultisigInstruction::CreateTransaction {
+                amount: 50_000_000,
+                memo: String::new(),
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                soft: true,
+                auto_approve: false,
+            }
+            .pack()
+            .expect("pack"),
+        )],
+        Some(&wallet.pubkey()),
+    );
+    transaction.sign(&[&wallet, &transaction_account], recent_blockhash);
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("process_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.frozen_amount, 0);
+    assert_eq!(
+        multisig_account_data.pending_transactions,
+        vec![transaction_account.pubkey()]
+    );
+
+    let transaction_account = banks_client
+        .get_account(transaction_account.pubkey())
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+    let transaction_account_data =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account.data()).expect("unpack");
+    assert!(transaction_account_data.is_soft);
+    assert_eq!(transaction_account_data.amount, 50_000_000);
+}
+
+#[tokio::test]
+async fn test_promote_soft_transaction() {
+    let custodians = [Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let oversized_transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address, oversized_transaction_address],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    for (address, amount, signed) in [
+        (transaction_address, 4_000_000, false),
+        (oversized_transaction_address, 7_000_000, true),
+    ] {
+        add_transaction_account(
+            &mut program_test,
+            address,
+            solana_multisig::Transaction {
+                multisig: multisig_address,
+                recipient: recipient_address,
+                amount,
+                status: TransactionStatus::Pending,
+                is_sweep: false,
+                signers: vec![
+                    (custodians[0].pubkey(), true),
+                    (custodians[1].pubkey(), signed),
+                ],
+                memo: String::new(),
+                outputs: vec![],
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                is_soft: true,
+                approvals: 0,
+                owners_version: 0,
+                nonce: 0,
+                instruction: None,
+                creator: Pubkey::default(),
+                reached_threshold_slot: None,
+            },
+        );
+    }
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let promote = |custodian: &Keypair, transaction_address: &Pubkey| {
+        let mut transaction = Transaction::new_with_payer(
+            &[promote_transaction(
+                &custodian.pubkey(),
+                &multisig_address,
+                transaction_address,
+                MultisigInstruction::PromoteTransaction
+                    .pack()
+                    .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, custodian], recent_blockhash);
+        transaction
+    };
+
+    // Soft proposals can't be executed
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+    assert_custom_error(err, MultisigError::SoftProposal);
+
+    let err = banks_client
+        .process_transaction(promote(&custodians[1], &transaction_address))
+        .await
+        .expect_err("promote")
+        .unwrap();
+    assert_custom_error(err, MultisigError::NotEnoughApprovals);
+
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    banks_client
+        .process_transaction(promote(&custodians[0], &transaction_address))
+        .await
+        .expect("promote");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.frozen_amount, 4_000_000);
+
+    // Promotion checks the balance left after other frozen transactions
+    let err = banks_client
+        .process_transaction(promote(&custodians[0], &oversized_transaction_address))
+        .await
+        .expect_err("promote")
+        .unwrap();
+    assert_custom_error(err, MultisigError::InsufficientBalance);
+
+    execute(
+        &mut banks_client,
+        &funder,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        4_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_create_weighted_multisig_account() {
+    let wallet = Keypair::new();
+    let owners = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let multisig_address = get_associated_address(&wallet.pubkey());
+
+    let (mut banks_client, funder, recent_blockhash) = program_test().start().await;
+
+    let create = |threshold: u32| {
+        let mut transaction = Transaction::new_with_payer(
+            &[solana_multisig::create_associated_account(
+                &funder.pubkey(),
+                &wallet.pubkey(),
+                MultisigInstruction::CreateWeightedAccount {
+                    threshold,
+                    owners: vec![(owners[0], 70), (owners[1], 20), (owners[2], 10)],
+                }
+                .pack()
+                .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, &wallet], recent_blockhash);
+        transaction
+    };
+
+    // Threshold is a weight and can't exceed the total
+    let err = banks_client
+        .process_transaction(create(101))
+        .await
+        .expect_err("process_transaction")
+        .unwrap();
+    assert_custom_error(err, MultisigError::CustodianLimit);
+
+    banks_client
+        .process_transaction(create(80))
+        .await
+        .expect("process_transaction");
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.threshold, 80);
+    assert_eq!(multisig_account_data.owners, owners.to_vec());
+    assert_eq!(multisig_account_data.owner_weights, vec![70, 20, 10]);
+}
+
+#[tokio::test]
+async fn test_weighted_approvals() {
+    let custodians = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 80,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![70, 20, 10],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: custodians.iter().map(|c| (c.pubkey(), false)).collect(),
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    // The largest owner alone is below the threshold
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+    assert_custom_error(err, MultisigError::NotEnoughApprovals);
+
+    // 70 + 10 reaches it
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodians[2],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    execute(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_strict_owner_match_rotation() {
+    let custodians = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let new_custodian = Keypair::new();
+    let multisig_address = Pubkey::new_unique();
+    let transaction_address = Pubkey::new_unique();
+    let recipient_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 2,
+            owners: custodians.iter().map(|c| c.pubkey()).collect(),
+            pending_transactions: vec![transaction_address],
+            frozen_amount: 1_000_000,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: true,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    // Approved by the first owner before the rotation
+    add_transaction_account(
+        &mut program_test,
+        transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 1_000_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![],
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0b001,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+    let signers = [&custodians[1], &custodians[2]];
+
+    // The mode can't be switched under pending transactions
+    let err = process_signed_by_owners(
+        &mut banks_client,
+        &funder,
+        &signers,
+        set_strict_owner_match(
+            &multisig_address,
+            &[custodians[1].pubkey(), custodians[2].pubkey()],
+            MultisigInstruction::SetStrictOwnerMatch { enabled: false }
+                .pack()
+                .expect("pack"),
+        ),
+        recent_blockhash,
+    )
+    .await
+    .expect_err("set_strict_owner_match")
+    .unwrap();
+    assert_custom_error(err, MultisigError::PendingTransactionsExist);
+
+    // Owners rotate while the transaction is pending
+    for instruction in [
+        MultisigInstruction::RemoveOwner {
+            owner: custodians[0].pubkey(),
+        },
+        MultisigInstruction::AddOwner {
+            owner: new_custodian.pubkey(),
+        },
+    ] {
+        process_signed_by_owners(
+            &mut banks_client,
+            &funder,
+            &signers,
+            owner_instruction(&multisig_address, &signers, instruction),
+            recent_blockhash,
+        )
+        .await
+        .expect("rotate owners");
+    }
+
+    let err = approve(
+        &mut banks_client,
+        &funder,
+        &custodians[0],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("approve")
+    .unwrap();
+    assert_custom_error(err, MultisigError::InvalidCustodian);
+
+    // The removed owner's approval doesn't carry over to the new first owner
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let err = execute(
+        &mut banks_client,
+        &funder,
+        &custodians[1],
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("execute")
+    .unwrap();
+    assert_custom_error(err, MultisigError::NotEnoughApprovals);
+
+    approve(
+        &mut banks_client,
+        &funder,
+        &new_custodian,
+        &multisig_address,
+        &transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let transaction_account = banks_client
+        .get_account(transaction_address)
+        .await
+        .expect("get_account")
+        .expect("transaction_account not none");
+    let transaction_account_data =
+        solana_multisig::Transaction::unpack_unchecked(transaction_account.data()).expect("unpack");
+    assert_eq!(transaction_account_data.approvals, 0b101);
+    assert_eq!(transaction_account_data.owners_version, 2);
+
+    execute(
+        &mut banks_client,
+        &funder,
+        &new_custodian,
+        &multisig_address,
+        &transaction_address,
+        &recipient_address,
+        recent_blockhash,
+    )
+    .await
+    .expect("execute");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient_address)
+            .await
+            .expect("get_balance"),
+        1_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_transaction_nonces() {
+    let wallet = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let recipient_address = Pubkey::new_unique();
+    let forged_transaction_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    let rent = solana_sdk::rent::Rent::default();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        rent.minimum_balance(solana_multisig::Account::LEN) + 10_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![wallet.pubkey()],
+            pending_transactions: vec![forged_transaction_address],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    // Stamped with a nonce the multisig never handed out
+    add_transaction_account(
+        &mut program_test,
+        forged_transaction_address,
+        solana_multisig::Transaction {
+            multisig: multisig_address,
+            recipient: recipient_address,
+            amount: 0,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![(wallet.pubkey(), false)],
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 4,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let transaction_accounts = [Keypair::new(), Keypair::new(), Keypair::new()];
+    for transaction_account in &transaction_accounts {
+        propose(
+            &mut banks_client,
+            &funder,
+            &wallet,
+            &wallet.pubkey(),
+            transaction_account,
+            &recipient_address,
+            1_000,
+            recent_blockhash,
+        )
+        .await
+        .expect("propose");
+    }
+
+    for (nonce, transaction_account) in (1..).zip(&transaction_accounts) {
+        let transaction_account = banks_client
+            .get_account(transaction_account.pubkey())
+            .await
+            .expect("get_account")
+            .expect("transaction_account not none");
+        let transaction_account_data =
+            solana_multisig::Transaction::unpack_unchecked(transaction_account.data())
+                .expect("unpack");
+        assert_eq!(transaction_account_data.nonce, nonce);
+    }
+
+    let multisig_account = banks_client
+        .get_account(multisig_address)
+        .await
+        .expect("get_account")
+        .expect("multisig_account not none");
+    let multisig_account_data = Account::unpack(multisig_account.data()).expect("unpack");
+    assert_eq!(multisig_account_data.nonce, 3);
+
+    let err = approve(
+        &mut banks_client,
+        &funder,
+        &wallet,
+        &multisig_address,
+        &forged_transaction_address,
+        recent_blockhash,
+    )
+    .await
+    .expect_err("approve")
+    .unwrap();
+    assert_custom_error(err, MultisigError::InvalidNonce);
+}
+
+#[tokio::test]
+async fn test_execute_instruction() {
+    let wallet = Keypair::new();
+    let custodian = Keypair::new();
+    let multisig_address = get_associated_address(&wallet.pubkey());
+    let authority_address = get_authority_address(&multisig_address);
+    let recipient = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_multisig_account(
+        &mut program_test,
+        multisig_address,
+        100_000_000,
+        solana_multisig::Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![custodian.pubkey()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+    );
+    program_test.add_account(
+        authority_address,
+        SolanaAccount {
+            lamports: 5_000_000,
+            owner: solana_program::system_program::id(),
+            ..SolanaAccount::default()
+        },
+    );
+
+    let (mut banks_client, funder, recent_blockhash) = program_test.start().await;
+
+    let transfer =
+        solana_program::system_instruction::transfer(&authority_address, &recipient, 2_000_000);
+    let instruction = InnerInstruction {
+        program_id: transfer.program_id,
+        accounts: transfer
+            .accounts
+            .iter()
+            .map(|meta| InnerAccountMeta {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        data: transfer.data,
+    };
+
+    let create = |transaction_account: &Keypair, instruction: InnerInstruction| {
+        let program_id = instruction.program_id;
+        let mut transaction = Transaction::new_with_payer(
+            &[create_transaction(
+                &wallet.pubkey(),
+                &wallet.pubkey(),
+                &transaction_account.pubkey(),
+                &program_id,
+                MultisigInstruction::CreateInstructionTransaction {
+                    instruction,
+                    memo: String::new(),
+                }
+                .pack()
+                .expect("pack"),
+            )],
+            Some(&funder.pubkey()),
+        );
+        transaction.sign(&[&funder, &wallet, transaction_account], recent_blockhash);
+        transaction
+    };
+
+    // The multisig can't invoke itself
+    let err = banks_client
+        .process_transaction(create(
+            &Keypair::new(),
+            InnerInstruction {
+                program_id: id(),
+                ..instruction.clone()
+            },
+        ))
+        .await
+        .expect_err("create_instruction_transaction")
+        .unwrap();
+    assert_custom_error(err, MultisigError::InvalidInstruction);
+
+    // nor take the signature of anyone but its authority, e.g. of the owner
+    // executing it
+    let err = banks_client
+        .process_transaction(create(
+            &Keypair::new(),
+            InnerInstruction {
+                accounts: vec![
+                    InnerAccountMeta {
+                        pubkey: custodian.pubkey(),
+                        is_signer: true,
+                        is_writable: true,
+                    },
+                    InnerAccountMeta {
+                        pubkey: recipient,
+                        is_signer: false,
+                        is_writable: true,
+                    },
+                ],
+                ..instruction.clone()
+            },
+        ))
+        .await
+        .expect_err("create_instruction_transaction")
+        .unwrap();
+    assert_custom_error(err, MultisigError::InvalidInstruction);
+
+    let transaction_account = Keypair::new();
+    banks_client
+        .process_transaction(create(&transaction_account, instruction.clone()))
+        .await
+        .expect("create_instruction_transaction");
+
+    // Only instruction transactions are allocated with room for one
+    assert_eq!(
+        banks_client
+            .get_account(transaction_account.pubkey())
+            .await
+            .expect("get_account")
+            .expect("transaction_account not none")
+            .data
+            .len(),
+        solana_multisig::Transaction::LEN
+    );
+
+    approve(
+        &mut banks_client,
+        &funder,
+        &custodian,
+        &multisig_address,
+        &transaction_account.pubkey(),
+        recent_blockhash,
+    )
+    .await
+    .expect("approve");
+
+    let mut transaction = Transaction::new_with_payer(
+        &[execute_instruction(
+            &custodian.pubkey(),
+            &multisig_address,
+            &transaction_account.pubkey(),
+            &instruction,
+            MultisigInstruction::ExecuteTransaction
+                .pack()
+                .expect("pack"),
+        )],
+        Some(&funder.pubkey()),
+    );
+    transaction.sign(&[&funder, &custodian], recent_blockhash);
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .expect("execute");
+
+    assert_eq!(
+        banks_client
+            .get_balance(recipient)
+            .await
+            .expect("get_balance"),
+        2_000_000
+    );
+    assert_eq!(
+        banks_client
+            .get_balance(authority_address)
+            .await
+            .expect("get_balance"),
+        3_000_000
+    );
+
+    // Multisig lamports are untouched
+    assert_eq!(
+        banks_client
+            .get_balance(multisig_address)
+            .await
+            .expect("get_balance"),
+        100_000_000
+    );
 }
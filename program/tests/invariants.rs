@@ -0,0 +1,92 @@
+#![cfg(debug_assertions)]
+
+use solana_program::pubkey::Pubkey;
+
+use solana_multisig::*;
+
+fn multisig(threshold: u32, owners: usize, frozen_amount: u64) -> Account {
+    Account {
+        is_initialized: true,
+        threshold,
+        owners: (0..owners).map(|_| Pubkey::new_unique()).collect(),
+        pending_transactions: vec![],
+        frozen_amount,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    }
+}
+
+fn transaction(multisig: &Account, amount: u64) -> Transaction {
+    Transaction {
+        multisig: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        amount,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        memo: String::new(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 0,
+        signers: multisig
+            .owners
+            .iter()
+            .map(|owner| (*owner, false))
+            .collect(),
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    }
+}
+
+#[test]
+fn test_consistent_state_passes_invariants() {
+    let multisig = multisig(2, 3, 3);
+    multisig.debug_assert_invariants();
+    multisig.debug_assert_frozen_amount(&[transaction(&multisig, 1), transaction(&multisig, 2)]);
+    transaction(&multisig, 1).debug_assert_invariants(&multisig);
+}
+
+#[test]
+#[should_panic(expected = "threshold 3 exceeds 2 owners")]
+fn test_threshold_above_owners_trips_invariant() {
+    multisig(3, 2, 0).debug_assert_invariants();
+}
+
+#[test]
+#[should_panic(expected = "frozen amount doesn't match pending transactions")]
+fn test_frozen_amount_mismatch_trips_invariant() {
+    let multisig = multisig(1, 1, 5);
+    multisig.debug_assert_frozen_amount(&[transaction(&multisig, 1)]);
+}
+
+#[test]
+#[should_panic(expected = "transaction signers don't match multisig owners")]
+fn test_signers_mismatch_trips_invariant() {
+    let multisig = multisig(1, 2, 0);
+    let mut transaction = transaction(&multisig, 1);
+    transaction.signers.pop();
+    transaction.debug_assert_invariants(&multisig);
+}
+
+#[test]
+#[should_panic(expected = "threshold 101 exceeds total owner weight 100")]
+fn test_threshold_above_owner_weight_trips_invariant() {
+    let mut multisig = multisig(101, 3, 0);
+    multisig.owner_weights = vec![70, 20, 10];
+    multisig.debug_assert_invariants();
+}
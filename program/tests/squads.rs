@@ -0,0 +1,86 @@
+use borsh::BorshSerialize;
+
+use solana_program::instruction::AccountMeta;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+use solana_multisig::*;
+
+fn squads_data(name: &str, args: &[u8]) -> Vec<u8> {
+    let mut data = squads_discriminator(name).to_vec();
+    data.extend_from_slice(args);
+    data
+}
+
+fn round_trip(data: &[u8]) -> MultisigInstruction {
+    let packed = unpack_squads_instruction(data)
+        .expect("unpack_squads_instruction")
+        .pack()
+        .expect("pack");
+    MultisigInstruction::unpack(&packed).expect("unpack")
+}
+
+#[test]
+fn test_squads_create_round_trip() {
+    let members = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let mut args = 2u16.to_le_bytes().to_vec();
+    args.extend_from_slice(Pubkey::new_unique().as_ref());
+    args.extend_from_slice(&members.try_to_vec().expect("serialize"));
+
+    match round_trip(&squads_data("create", &args)) {
+        MultisigInstruction::CreateAccount {
+            threshold,
+            owners,
+            max_pending,
+        } => {
+            assert_eq!(threshold, 2);
+            assert_eq!(owners, members);
+            assert_eq!(max_pending, MAX_TRANSACTIONS as u32);
+        }
+        _ => panic!("expected CreateAccount"),
+    }
+}
+
+#[test]
+fn test_squads_approve_and_execute_round_trip() {
+    assert!(matches!(
+        round_trip(&squads_data("approve_transaction", &[])),
+        MultisigInstruction::ApproveTransaction
+    ));
+
+    let account_list = vec![0u8, 1, 2].try_to_vec().expect("serialize");
+    assert!(matches!(
+        round_trip(&squads_data("execute_transaction", &account_list)),
+        MultisigInstruction::ExecuteTransaction
+    ));
+}
+
+#[test]
+fn test_squads_unsupported_instruction() {
+    assert_eq!(
+        unpack_squads_instruction(&squads_data("create_transaction", &0u32.to_le_bytes())).err(),
+        Some(ProgramError::InvalidInstructionData)
+    );
+    assert_eq!(
+        unpack_squads_instruction(&[0; 4]).err(),
+        Some(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+fn test_squads_approve_accounts() {
+    let multisig = Pubkey::new_unique();
+    let transaction = Pubkey::new_unique();
+    let member = Pubkey::new_unique();
+
+    let accounts = map_squads_approve_accounts(&[
+        AccountMeta::new(multisig, false),
+        AccountMeta::new(transaction, false),
+        AccountMeta::new(member, true),
+    ])
+    .expect("map_squads_approve_accounts");
+
+    let instruction = approve_transaction(&member, &multisig, &transaction, vec![]);
+    assert_eq!(accounts, instruction.accounts);
+}
@@ -0,0 +1,1516 @@
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::{Pubkey, PUBKEY_BYTES};
+
+use solana_multisig::*;
+
+#[test]
+fn test_unpack_create_account_with_huge_owners_len() {
+    let mut data = vec![1];
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    ));
+}
+
+#[test]
+fn test_unpack_create_account_with_too_many_owners() {
+    // Short of the 255 owners it claims, rejected before the length check
+    let mut data = vec![1];
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&255u32.to_le_bytes());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+
+    assert_eq!(
+        MultisigInstruction::unpack(&data).err(),
+        Some(ProgramError::InvalidInstructionData)
+    );
+
+    // Even with every owner present
+    let mut data = vec![1];
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&(MAX_SIGNERS as u32 + 1).to_le_bytes());
+    for _ in 0..=MAX_SIGNERS {
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+    }
+
+    assert_eq!(
+        MultisigInstruction::unpack(&data).err(),
+        Some(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+fn test_unpack_account_with_huge_owners_len() {
+    let mut data = vec![0; Account::LEN];
+    Account::pack(
+        Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![Pubkey::new_unique()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+        &mut data,
+    )
+    .expect("pack");
+
+    // owners_len follows is_initialized, threshold, frozen_amount and reserved_balance
+    data[21..25].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    assert_eq!(
+        Account::unpack(&data).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn test_unpack_transaction_with_huge_signers_len() {
+    let mut data = vec![0; Transaction::LEN];
+
+    // signers_len follows multisig, recipient, amount, status and is_sweep
+    data[74..78].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    assert_eq!(
+        Transaction::unpack_unchecked(&data).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn test_transaction_status_round_trip() {
+    for status in [
+        TransactionStatus::Pending,
+        TransactionStatus::Executed,
+        TransactionStatus::Cancelled,
+        TransactionStatus::Expired,
+    ] {
+        let mut data = vec![0; Transaction::LEN];
+        Transaction::pack(
+            Transaction {
+                multisig: Pubkey::new_unique(),
+                recipient: Pubkey::new_unique(),
+                amount: 1_000,
+                status,
+                is_sweep: false,
+                signers: vec![(Pubkey::new_unique(), false)],
+                memo: String::new(),
+                outputs: vec![],
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                is_soft: false,
+                approvals: 0,
+                owners_version: 0,
+                nonce: 0,
+                instruction: None,
+                creator: Pubkey::default(),
+                reached_threshold_slot: None,
+            },
+            &mut data,
+        )
+        .expect("pack");
+
+        let transaction = Transaction::unpack_unchecked(&data).expect("unpack");
+        assert_eq!(transaction.status, status);
+    }
+}
+
+#[test]
+fn test_unpack_legacy_is_executed() {
+    let mut data = vec![0; Transaction::LEN];
+
+    // The status byte follows multisig, recipient and amount, where the
+    // is_executed boolean used to be
+    for (is_executed, status) in [
+        (0, TransactionStatus::Pending),
+        (1, TransactionStatus::Executed),
+    ] {
+        data[72] = is_executed;
+        assert_eq!(
+            Transaction::unpack_unchecked(&data).expect("unpack").status,
+            status
+        );
+    }
+
+    data[72] = 4;
+    assert_eq!(
+        Transaction::unpack_unchecked(&data).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn test_transaction_memo_round_trip() {
+    for memo in ["", "Payroll for March, invoice #42"] {
+        let mut data = vec![0; Transaction::LEN];
+        Transaction::pack(
+            Transaction {
+                multisig: Pubkey::new_unique(),
+                recipient: Pubkey::new_unique(),
+                amount: 1_000,
+                status: TransactionStatus::Pending,
+                is_sweep: false,
+                signers: vec![(Pubkey::new_unique(), false)],
+                memo: memo.to_string(),
+                outputs: vec![],
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                is_soft: false,
+                approvals: 0,
+                owners_version: 0,
+                nonce: 0,
+                instruction: None,
+                creator: Pubkey::default(),
+                reached_threshold_slot: None,
+            },
+            &mut data,
+        )
+        .expect("pack");
+
+        let transaction = Transaction::unpack_unchecked(&data).expect("unpack");
+        assert_eq!(transaction.memo, memo);
+
+        let instruction = MultisigInstruction::CreateTransaction {
+            amount: 1_000,
+            memo: memo.to_string(),
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            soft: false,
+            auto_approve: false,
+        }
+        .pack()
+        .expect("pack");
+        match MultisigInstruction::unpack(&instruction).expect("unpack") {
+            MultisigInstruction::CreateTransaction {
+                amount,
+                memo: unpacked,
+                ..
+            } => {
+                assert_eq!(amount, 1_000);
+                assert_eq!(unpacked, memo);
+            }
+            _ => panic!("unexpected instruction"),
+        }
+    }
+}
+
+#[test]
+fn test_unpack_truncated_create_account() {
+    let mut data = vec![1];
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    assert!(MultisigInstruction::unpack(&data).is_ok());
+
+    for len in 0..data.len() {
+        assert!(matches!(
+            MultisigInstruction::unpack(&data[..len]),
+            Err(ProgramError::InvalidInstructionData)
+        ));
+    }
+}
+
+#[test]
+fn test_create_account_max_pending_round_trip() {
+    let owners = vec![Pubkey::new_unique()];
+    let legacy = MultisigInstruction::CreateAccount {
+        threshold: 1,
+        owners: owners.clone(),
+        max_pending: MAX_TRANSACTIONS as u32,
+    }
+    .pack()
+    .expect("pack");
+
+    // The default cap is left out, as before the field existed
+    assert_eq!(legacy.len(), 1 + 1 + 4 + 4 + PUBKEY_BYTES);
+
+    for max_pending in [1, MAX_TRANSACTIONS as u32] {
+        let data = MultisigInstruction::CreateAccount {
+            threshold: 1,
+            owners: owners.clone(),
+            max_pending,
+        }
+        .pack()
+        .expect("pack");
+
+        match MultisigInstruction::unpack(&data).expect("unpack") {
+            MultisigInstruction::CreateAccount {
+                max_pending: unpacked,
+                ..
+            } => assert_eq!(unpacked, max_pending),
+            _ => panic!("expected CreateAccount"),
+        }
+    }
+
+    // A cap must be a full `u32`
+    let mut data = legacy;
+    data.extend_from_slice(&[1, 0]);
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    ));
+}
+
+#[test]
+fn test_unpack_create_transaction_length() {
+    let data = MultisigInstruction::CreateTransaction {
+        amount: 1_000,
+        memo: String::new(),
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        soft: true,
+        auto_approve: false,
+    }
+    .pack()
+    .expect("pack");
+
+    // Truncated amount
+    for len in 2..10 {
+        assert!(matches!(
+            MultisigInstruction::unpack(&data[..len]),
+            Err(ProgramError::InvalidInstructionData)
+        ));
+    }
+
+    // Trailing bytes after the auto-approve flag
+    let mut data = data;
+    data.extend_from_slice(&[0, 0]);
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    ));
+}
+
+#[test]
+fn test_unpack_create_transaction_without_memo() {
+    let mut data = vec![2];
+    data.extend_from_slice(&1_000u64.to_le_bytes());
+
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::CreateTransaction {
+            amount: 1_000,
+            memo,
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            soft: false,
+            auto_approve: false,
+        }) if memo.is_empty()
+    ));
+}
+
+#[test]
+fn test_unpack_oversized_memo() {
+    let mut data = vec![2];
+    data.extend_from_slice(&1_000u64.to_le_bytes());
+    data.push(MAX_MEMO_LEN as u8 + 1);
+    data.extend_from_slice(&[b'a'; MAX_MEMO_LEN + 1]);
+
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    ));
+
+    let memo = "a".repeat(MAX_MEMO_LEN + 1);
+    assert!(MultisigInstruction::CreateTransaction {
+        amount: 1_000,
+        memo,
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        soft: false,
+        auto_approve: false,
+    }
+    .pack()
+    .is_err());
+}
+
+#[test]
+fn test_multi_transfer_round_trip() {
+    let outputs = vec![
+        (Pubkey::new_unique(), 1_000),
+        (Pubkey::new_unique(), 2_000),
+        (Pubkey::new_unique(), 3_000),
+    ];
+
+    let data = MultisigInstruction::CreateMultiTransfer {
+        outputs: outputs.clone(),
+        memo: "Payouts".to_string(),
+    }
+    .pack()
+    .expect("pack");
+    match MultisigInstruction::unpack(&data).expect("unpack") {
+        MultisigInstruction::CreateMultiTransfer {
+            outputs: unpacked,
+            memo,
+        } => {
+            assert_eq!(unpacked, outputs);
+            assert_eq!(memo, "Payouts");
+        }
+        _ => panic!("unexpected instruction"),
+    }
+
+    let mut data = vec![0; Transaction::LEN];
+    Transaction::pack(
+        Transaction {
+            multisig: Pubkey::new_unique(),
+            recipient: outputs[0].0,
+            amount: 6_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![(Pubkey::new_unique(), false)],
+            memo: String::new(),
+            outputs: outputs.clone(),
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 0,
+            instruction: None,
+            creator: Pubkey::default(),
+            reached_threshold_slot: None,
+        },
+        &mut data,
+    )
+    .expect("pack");
+    assert_eq!(
+        Transaction::unpack_unchecked(&data)
+            .expect("unpack")
+            .outputs,
+        outputs
+    );
+}
+
+#[test]
+fn test_create_transaction_timelock_round_trip() {
+    let data = MultisigInstruction::CreateTransaction {
+        amount: 1_000,
+        memo: "Vesting".to_string(),
+        not_before_slot: 42,
+        expires_at_slot: 100,
+        soft: false,
+        auto_approve: false,
+    }
+    .pack()
+    .expect("pack");
+
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::CreateTransaction {
+            amount: 1_000,
+            not_before_slot: 42,
+            expires_at_slot: 100,
+            soft: false,
+            ..
+        })
+    ));
+
+    // Expiry is optional
+    assert!(matches!(
+        MultisigInstruction::unpack(&data[..data.len() - 9]),
+        Ok(MultisigInstruction::CreateTransaction {
+            not_before_slot: 42,
+            expires_at_slot: 0,
+            soft: false,
+            ..
+        })
+    ));
+
+    // Truncated expiry and timelock
+    for len in [data.len() - 2, data.len() - 10] {
+        assert!(matches!(
+            MultisigInstruction::unpack(&data[..len]),
+            Err(ProgramError::InvalidInstructionData)
+        ));
+    }
+}
+
+#[test]
+fn test_create_soft_transaction_round_trip() {
+    let data = MultisigInstruction::CreateTransaction {
+        amount: 1_000,
+        memo: String::new(),
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        soft: true,
+        auto_approve: false,
+    }
+    .pack()
+    .expect("pack");
+
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::CreateTransaction { soft: true, .. })
+    ));
+
+    // Without the flag the transaction is funded
+    assert!(matches!(
+        MultisigInstruction::unpack(&data[..data.len() - 1]),
+        Ok(MultisigInstruction::CreateTransaction { soft: false, .. })
+    ));
+
+    let mut data = data;
+    *data.last_mut().unwrap() = 2;
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    ));
+}
+
+#[test]
+fn test_create_auto_approved_transaction_round_trip() {
+    let data = MultisigInstruction::CreateTransaction {
+        amount: 1_000,
+        memo: String::new(),
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        soft: false,
+        auto_approve: true,
+    }
+    .pack()
+    .expect("pack");
+
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::CreateTransaction {
+            soft: false,
+            auto_approve: true,
+            ..
+        })
+    ));
+
+    // Without the flag the proposer doesn't approve
+    assert!(matches!(
+        MultisigInstruction::unpack(&data[..data.len() - 1]),
+        Ok(MultisigInstruction::CreateTransaction {
+            auto_approve: false,
+            ..
+        })
+    ));
+
+    let mut data = data;
+    *data.last_mut().unwrap() = 2;
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    ));
+}
+
+#[test]
+fn test_unpack_strict_account() {
+    let account = |owners: Vec<Pubkey>| Account {
+        is_initialized: true,
+        threshold: 1,
+        owners,
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+
+    let mut data = vec![0; Account::LEN];
+    Account::pack(account(vec![Pubkey::new_unique()]), &mut data).expect("pack");
+    assert!(Account::unpack_strict(&data).is_ok());
+
+    // Garbage in the unused second owner slot, after the 29 byte header and the first owner
+    let mut garbage = data.clone();
+    garbage[29 + 32] = 0xff;
+    assert!(Account::unpack(&garbage).is_ok());
+    assert_eq!(
+        Account::unpack_strict(&garbage).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+
+    // Trailing bytes past the account layout
+    let mut longer = data.clone();
+    longer.push(0);
+    assert!(Account::unpack_strict(&longer).is_err());
+
+    // Packing fewer owners over a previous account stays canonical
+    Account::pack(
+        account(vec![Pubkey::new_unique(), Pubkey::new_unique()]),
+        &mut data,
+    )
+    .expect("pack");
+    Account::pack(account(vec![Pubkey::new_unique()]), &mut data).expect("pack");
+    assert!(Account::unpack_strict(&data).is_ok());
+}
+
+#[test]
+fn test_weighted_account_round_trip() {
+    let owners = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    let data = MultisigInstruction::CreateWeightedAccount {
+        threshold: 80,
+        owners: vec![(owners[0], 70), (owners[1], 20), (owners[2], 10)],
+    }
+    .pack()
+    .expect("pack");
+    match MultisigInstruction::unpack(&data).expect("unpack") {
+        MultisigInstruction::CreateWeightedAccount {
+            threshold,
+            owners: unpacked,
+        } => {
+            assert_eq!(threshold, 80);
+            assert_eq!(
+                unpacked,
+                vec![(owners[0], 70), (owners[1], 20), (owners[2], 10)]
+            );
+        }
+        _ => panic!("unexpected instruction"),
+    }
+    assert!(MultisigInstruction::unpack(&data[..data.len() - 1]).is_err());
+
+    let mut data = vec![0; Account::LEN];
+    Account::pack(
+        Account {
+            is_initialized: true,
+            threshold: 80,
+            owners: owners.to_vec(),
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![70, 20, 10],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+        &mut data,
+    )
+    .expect("pack");
+
+    let account = Account::unpack_strict(&data).expect("unpack");
+    assert_eq!(account.owner_weights, vec![70, 20, 10]);
+    assert_eq!(account.total_weight(), 100);
+    assert_eq!(account.owner_weight(&Pubkey::new_unique()), 0);
+
+    let approval_weight = |signed: [bool; 3]| {
+        let signers = owners.iter().copied().zip(signed).collect::<Vec<_>>();
+        account.approval_weight(&signers)
+    };
+    assert_eq!(approval_weight([true, false, true]), 80);
+    assert_eq!(approval_weight([true, true, false]), 90);
+    assert_eq!(approval_weight([false, true, true]), 30);
+    assert_eq!(approval_weight([true, false, false]), 70);
+}
+
+#[test]
+fn test_unweighted_account_counts_owners() {
+    let account = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+
+    assert!(!account.is_weighted());
+    assert_eq!(account.total_weight(), 2);
+    assert_eq!(
+        account.approval_weight(&[(account.owners[0], true), (account.owners[1], false)]),
+        1
+    );
+}
+
+#[test]
+fn test_strict_approvals_follow_live_owners() {
+    let owners = vec![
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let mut multisig = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: owners.clone(),
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: true,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+    let mut transaction = Transaction {
+        multisig: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        amount: 1_000,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        signers: vec![],
+        memo: String::new(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 0,
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+
+    transaction.set_approval(&multisig, 0, true);
+    transaction.set_approval(&multisig, 2, true);
+    transaction.set_approval(&multisig, 2, false);
+    assert_eq!(
+        transaction.signer_status(&multisig),
+        vec![(owners[0], true), (owners[1], false), (owners[2], false)]
+    );
+
+    let mut data = vec![0; Transaction::LEN];
+    Transaction::pack(transaction, &mut data).expect("pack");
+    let mut transaction = Transaction::unpack_unchecked(&data).expect("unpack");
+    assert_eq!(transaction.approvals, 0b001);
+
+    // Rotating the first owner out voids its approval
+    multisig.owners.remove(0);
+    multisig.owners_version += 1;
+    assert_eq!(
+        transaction.signer_status(&multisig),
+        vec![(owners[1], false), (owners[2], false)]
+    );
+
+    transaction.set_approval(&multisig, 1, true);
+    assert_eq!(transaction.owners_version, 1);
+    assert_eq!(
+        transaction.signer_status(&multisig),
+        vec![(owners[1], false), (owners[2], true)]
+    );
+}
+
+#[test]
+fn test_spend_limit_round_trip() {
+    let owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let data = MultisigInstruction::SetSpendLimit {
+        owner: owners[0],
+        limit: Some(5_000),
+    }
+    .pack()
+    .expect("pack");
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::SetSpendLimit { owner, limit: Some(5_000) }) if owner == owners[0]
+    ));
+
+    // Without the limit it is cleared
+    assert!(matches!(
+        MultisigInstruction::unpack(&data[..data.len() - 8]),
+        Ok(MultisigInstruction::SetSpendLimit { limit: None, .. })
+    ));
+    assert!(MultisigInstruction::unpack(&data[..data.len() - 1]).is_err());
+
+    let mut data = vec![0; Account::LEN];
+    Account::pack(
+        Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: owners.to_vec(),
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::AnyOwner,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![(owners[0], 5_000)],
+            is_frozen: false,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+        &mut data,
+    )
+    .expect("pack");
+
+    let account = Account::unpack_strict(&data).expect("unpack");
+    assert_eq!(account.spend_limits, vec![(owners[0], 5_000)]);
+    assert_eq!(account.spend_limit(&owners[0]), Some(5_000));
+    assert_eq!(account.spend_limit(&owners[1]), None);
+}
+
+#[test]
+fn test_spendable_excludes_frozen_amount() {
+    let account = Account {
+        is_initialized: true,
+        threshold: 1,
+        owners: vec![Pubkey::new_unique()],
+        pending_transactions: vec![],
+        frozen_amount: 3_000,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+
+    assert_eq!(account.spendable(10_000), 7_000);
+    assert_eq!(account.spendable(3_000), 0);
+    // Frozen amount above the balance saturates instead of wrapping
+    assert_eq!(account.spendable(1_000), 0);
+}
+
+#[test]
+fn test_versioned_instruction_round_trip() {
+    let data = MultisigInstruction::ChangeThreshold { new_threshold: 2 }
+        .pack()
+        .expect("pack");
+    assert_eq!(data[0] & 0x7f, INSTRUCTION_VERSION);
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::ChangeThreshold { new_threshold: 2 })
+    ));
+
+    // Legacy data starts right at the tag
+    assert!(matches!(
+        MultisigInstruction::unpack(&data[1..]),
+        Ok(MultisigInstruction::ChangeThreshold { new_threshold: 2 })
+    ));
+
+    let mut data = data;
+    data[0] += 1;
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    ));
+}
+
+#[test]
+fn test_reconcile_round_trip() {
+    let data = MultisigInstruction::Reconcile.pack().expect("pack");
+    assert_eq!(data[1], 22);
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::Reconcile)
+    ));
+}
+
+#[test]
+fn test_cancel_transaction_round_trip() {
+    let data = MultisigInstruction::CancelTransaction.pack().expect("pack");
+    assert_eq!(data[1], 24);
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::CancelTransaction)
+    ));
+}
+
+#[test]
+fn test_instruction_transaction_round_trip() {
+    let instruction = InnerInstruction {
+        program_id: Pubkey::new_unique(),
+        accounts: vec![
+            InnerAccountMeta {
+                pubkey: Pubkey::new_unique(),
+                is_signer: true,
+                is_writable: true,
+            },
+            InnerAccountMeta {
+                pubkey: Pubkey::new_unique(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ],
+        data: vec![1, 2, 3],
+    };
+
+    let data = MultisigInstruction::CreateInstructionTransaction {
+        instruction: instruction.clone(),
+        memo: "cpi".to_string(),
+    }
+    .pack()
+    .expect("pack");
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::CreateInstructionTransaction { instruction: unpacked, memo })
+            if unpacked == instruction && memo == "cpi"
+    ));
+    assert!(MultisigInstruction::unpack(&data[..data.len() - 1]).is_err());
+
+    let transaction = Transaction {
+        multisig: Pubkey::new_unique(),
+        recipient: instruction.program_id,
+        amount: 0,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        signers: vec![],
+        memo: String::new(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 1,
+        instruction: Some(instruction.clone()),
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+    assert_eq!(
+        transaction.recipients(),
+        vec![
+            instruction.program_id,
+            instruction.accounts[0].pubkey,
+            instruction.accounts[1].pubkey
+        ]
+    );
+
+    assert_eq!(transaction.data_len(), Transaction::LEN);
+
+    let mut data = vec![0; Transaction::LEN];
+    Transaction::pack(transaction, &mut data).expect("pack");
+    let mut transaction = Transaction::unpack_unchecked(&data).expect("unpack");
+    assert_eq!(transaction.instruction, Some(instruction.clone()));
+
+    // Transfers are allocated without room for an instruction
+    let mut transfer_data = vec![0; Transaction::TRANSFER_LEN];
+    assert_eq!(
+        Transaction::pack(
+            Transaction::unpack_unchecked(&data).expect("unpack"),
+            &mut transfer_data
+        )
+        .err(),
+        Some(ProgramError::AccountDataTooSmall)
+    );
+
+    transaction.instruction = None;
+    assert_eq!(transaction.data_len(), Transaction::TRANSFER_LEN);
+    Transaction::pack(transaction, &mut transfer_data).expect("pack");
+    let transaction = Transaction::unpack_unchecked(&transfer_data).expect("unpack");
+    assert_eq!(transaction.instruction, None);
+    assert_eq!(transaction.nonce, 1);
+
+    // Oversized instructions don't fit the layout
+    assert!(!InnerInstruction {
+        data: vec![0; MAX_INSTRUCTION_DATA_LEN + 1],
+        ..instruction
+    }
+    .is_bounded());
+}
+
+#[test]
+fn test_set_execution_delay_round_trip() {
+    let data = MultisigInstruction::SetExecutionDelay { slots: 500 }
+        .pack()
+        .expect("pack");
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::SetExecutionDelay { slots: 500 })
+    ));
+    assert!(MultisigInstruction::unpack(&data[..data.len() - 1]).is_err());
+}
+
+#[test]
+fn test_set_owner_grace_round_trip() {
+    let data = MultisigInstruction::SetOwnerGrace { secs: 3_600 }
+        .pack()
+        .expect("pack");
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::SetOwnerGrace { secs: 3_600 })
+    ));
+    assert!(MultisigInstruction::unpack(&data[..data.len() - 1]).is_err());
+}
+
+#[test]
+fn test_set_frozen_round_trip() {
+    let data = MultisigInstruction::SetFrozen { frozen: true }
+        .pack()
+        .expect("pack");
+    assert!(matches!(
+        MultisigInstruction::unpack(&data),
+        Ok(MultisigInstruction::SetFrozen { frozen: true })
+    ));
+    assert!(MultisigInstruction::unpack(&data[..data.len() - 1]).is_err());
+
+    let mut data = vec![0; Account::LEN];
+    Account::pack(
+        Account {
+            is_initialized: true,
+            threshold: 1,
+            owners: vec![Pubkey::new_unique()],
+            pending_transactions: vec![],
+            frozen_amount: 0,
+            reserved_balance: 0,
+            proposer_policy: ProposerPolicy::Wallet,
+            owner_weights: vec![],
+            strict_owner_match: false,
+            owners_version: 0,
+            nonce: 0,
+            spend_limits: vec![],
+            is_frozen: true,
+            last_updated_slot: 0,
+            max_pending: MAX_TRANSACTIONS as u32,
+            execution_delay_slots: 0,
+            new_owner_grace_secs: 0,
+            owner_added_at: vec![],
+        },
+        &mut data,
+    )
+    .expect("pack");
+    assert!(Account::unpack_strict(&data).expect("unpack").is_frozen);
+}
+
+#[test]
+fn test_approval_progress() {
+    let owners = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let mut multisig = Account {
+        is_initialized: true,
+        threshold: 2,
+        owners: owners.to_vec(),
+        pending_transactions: vec![],
+        frozen_amount: 0,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    };
+    let mut transaction = Transaction {
+        multisig: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        amount: 1_000,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        signers: vec![(owners[0], true), (owners[1], false), (owners[2], true)],
+        memo: String::new(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 1,
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+
+    assert_eq!(transaction.approval_count(&multisig), 2);
+    assert_eq!(transaction.signed_by(&multisig, &owners[0]), Some(true));
+    assert_eq!(transaction.signed_by(&multisig, &owners[1]), Some(false));
+    assert_eq!(
+        transaction.signed_by(&multisig, &Pubkey::new_unique()),
+        None
+    );
+
+    // Strict multisigs count approvals from the bitmap
+    multisig.strict_owner_match = true;
+    transaction.signers = vec![];
+    transaction.set_approval(&multisig, 1, true);
+    assert_eq!(transaction.approval_count(&multisig), 1);
+    assert_eq!(transaction.signed_by(&multisig, &owners[1]), Some(true));
+    assert_eq!(transaction.signed_by(&multisig, &owners[2]), Some(false));
+}
+
+#[test]
+fn test_transaction_creator_round_trip() {
+    let creator = Pubkey::new_unique();
+    let mut data = vec![0; Transaction::LEN];
+    Transaction::pack(
+        Transaction {
+            multisig: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            amount: 1_000,
+            status: TransactionStatus::Pending,
+            is_sweep: false,
+            signers: vec![(creator, false)],
+            memo: String::new(),
+            outputs: vec![],
+            not_before_slot: 0,
+            expires_at_slot: 0,
+            is_soft: false,
+            approvals: 0,
+            owners_version: 0,
+            nonce: 1,
+            instruction: None,
+            creator,
+            reached_threshold_slot: None,
+        },
+        &mut data,
+    )
+    .expect("pack");
+
+    // The creator follows the instruction slots, the reached threshold slot comes last
+    let creator_offset = Transaction::LEN - 9 - 32;
+    assert_eq!(&data[creator_offset..creator_offset + 32], creator.as_ref());
+
+    let transaction = Transaction::unpack_unchecked(&data).expect("unpack");
+    assert_eq!(transaction.creator, creator);
+    assert_eq!(transaction.signers, vec![(creator, false)]);
+}
+
+fn account_with_owners(owners: Vec<Pubkey>) -> Account {
+    Account {
+        is_initialized: true,
+        threshold: owners.len() as u32,
+        spend_limits: vec![(owners[owners.len() - 1], 5_000)],
+        proposer_policy: ProposerPolicy::Allowlist(owners.clone()),
+        owner_weights: vec![2; owners.len()],
+        owners,
+        pending_transactions: vec![Pubkey::new_unique()],
+        frozen_amount: 1_000,
+        reserved_balance: 0,
+        strict_owner_match: false,
+        owners_version: 3,
+        nonce: 7,
+        is_frozen: false,
+        last_updated_slot: 42,
+        max_pending: 4,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    }
+}
+
+#[test]
+fn test_account_round_trip_with_many_owners() {
+    for owners_len in [16, MAX_SIGNERS] {
+        let owners = (0..owners_len)
+            .map(|_| Pubkey::new_unique())
+            .collect::<Vec<_>>();
+        let mut data = vec![0; Account::LEN];
+        Account::pack(account_with_owners(owners.clone()), &mut data).expect("pack");
+
+        let account = Account::unpack(&data).expect("unpack");
+        assert_eq!(account.owners, owners);
+        assert_eq!(account.owner_weights, vec![2; owners_len]);
+        assert_eq!(
+            account.proposer_policy,
+            ProposerPolicy::Allowlist(owners.clone())
+        );
+        assert_eq!(account.spend_limits, vec![(owners[owners_len - 1], 5_000)]);
+        assert_eq!(account.pending_transactions.len(), 1);
+        assert_eq!(account.max_pending, 4);
+    }
+}
+
+#[test]
+fn test_transaction_round_trip_with_many_signers() {
+    for signers_len in [16, MAX_SIGNERS] {
+        let signers = (0..signers_len)
+            .map(|index| (Pubkey::new_unique(), index % 2 == 0))
+            .collect::<Vec<_>>();
+        let mut data = vec![0; Transaction::LEN];
+        Transaction::pack(
+            Transaction {
+                multisig: Pubkey::new_unique(),
+                recipient: Pubkey::new_unique(),
+                amount: 1_000,
+                status: TransactionStatus::Pending,
+                is_sweep: false,
+                signers: signers.clone(),
+                memo: String::new(),
+                outputs: vec![],
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                is_soft: false,
+                // The last owner of a full multisig still has a bit
+                approvals: 1 << (signers_len - 1),
+                owners_version: 0,
+                nonce: 0,
+                instruction: None,
+                creator: Pubkey::default(),
+                reached_threshold_slot: None,
+            },
+            &mut data,
+        )
+        .expect("pack");
+
+        let transaction = Transaction::unpack_unchecked(&data).expect("unpack");
+        assert_eq!(transaction.signers, signers);
+        assert_eq!(transaction.approvals, 1 << (signers_len - 1));
+    }
+}
+
+// Account with only the settings the first release has room for
+fn first_release_account(owners: Vec<Pubkey>) -> Account {
+    Account {
+        is_initialized: true,
+        threshold: owners.len() as u32,
+        owners,
+        pending_transactions: vec![Pubkey::new_unique()],
+        frozen_amount: 1_000,
+        reserved_balance: 0,
+        proposer_policy: ProposerPolicy::Wallet,
+        owner_weights: vec![],
+        strict_owner_match: false,
+        owners_version: 0,
+        nonce: 0,
+        spend_limits: vec![],
+        is_frozen: false,
+        last_updated_slot: 0,
+        max_pending: MAX_TRANSACTIONS as u32,
+        execution_delay_slots: 0,
+        new_owner_grace_secs: 0,
+        owner_added_at: vec![],
+    }
+}
+
+#[test]
+fn test_legacy_account_round_trip() {
+    let owners = (0..LEGACY_MAX_SIGNERS)
+        .map(|_| Pubkey::new_unique())
+        .collect::<Vec<_>>();
+    let mut data = vec![0; Account::LEGACY_LEN];
+    Account::pack(first_release_account(owners.clone()), &mut data).expect("pack");
+
+    let account = Account::unpack_strict(&data).expect("unpack");
+    assert_eq!(account.owners, owners);
+    assert_eq!(account.frozen_amount, 1_000);
+    assert_eq!(Account::max_owners(data.len()), LEGACY_MAX_SIGNERS);
+
+    let mut repacked = vec![0; Account::LEGACY_LEN];
+    Account::pack(account, &mut repacked).expect("pack");
+    assert_eq!(repacked, data);
+
+    // A legacy account has no room for more owners once every transaction
+    // slot is taken
+    let owners = (0..=LEGACY_MAX_SIGNERS)
+        .map(|_| Pubkey::new_unique())
+        .collect::<Vec<_>>();
+    let mut account = first_release_account(owners);
+    account.pending_transactions = (0..MAX_TRANSACTIONS)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    assert_eq!(
+        Account::pack(account, &mut data).err(),
+        Some(ProgramError::AccountDataTooSmall)
+    );
+
+    // nor for settings added since
+    let owners = (0..LEGACY_MAX_SIGNERS)
+        .map(|_| Pubkey::new_unique())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        Account::pack(account_with_owners(owners), &mut data).err(),
+        Some(ProgramError::AccountDataTooSmall)
+    );
+}
+
+#[test]
+fn test_legacy_transaction_round_trip() {
+    let signers = (0..LEGACY_MAX_SIGNERS)
+        .map(|index| (Pubkey::new_unique(), index == 0))
+        .collect::<Vec<_>>();
+    let transaction = |signers: Vec<(Pubkey, bool)>, memo: &str| Transaction {
+        multisig: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        amount: 1_000,
+        status: TransactionStatus::Pending,
+        is_sweep: false,
+        signers,
+        memo: memo.to_string(),
+        outputs: vec![],
+        not_before_slot: 0,
+        expires_at_slot: 0,
+        is_soft: false,
+        approvals: 0,
+        owners_version: 0,
+        nonce: 0,
+        instruction: None,
+        creator: Pubkey::default(),
+        reached_threshold_slot: None,
+    };
+    let mut data = vec![0; Transaction::LEGACY_LEN];
+    Transaction::pack(transaction(signers.clone(), ""), &mut data).expect("pack");
+
+    // The last signer fills the account
+    assert_eq!(
+        &data[Transaction::LEGACY_LEN - 33..Transaction::LEGACY_LEN - 1],
+        signers[LEGACY_MAX_SIGNERS - 1].0.as_ref()
+    );
+
+    let unpacked = Transaction::unpack_unchecked(&data).expect("unpack");
+    assert_eq!(unpacked.signers, signers);
+
+    let mut repacked = vec![0; Transaction::LEGACY_LEN];
+    Transaction::pack(unpacked, &mut repacked).expect("pack");
+    assert_eq!(repacked, data);
+
+    let too_many_signers = (0..=LEGACY_MAX_SIGNERS)
+        .map(|_| (Pubkey::new_unique(), false))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        Transaction::pack(transaction(too_many_signers, ""), &mut data).err(),
+        Some(ProgramError::AccountDataTooSmall)
+    );
+    assert_eq!(
+        Transaction::pack(transaction(signers, "legacy"), &mut data).err(),
+        Some(ProgramError::AccountDataTooSmall)
+    );
+}
+
+#[test]
+fn test_execution_delay_round_trip() {
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut multisig = account_with_owners(owners.clone());
+    multisig.execution_delay_slots = 500;
+
+    let mut data = vec![0; Account::LEN];
+    Account::pack(multisig.clone(), &mut data).expect("pack");
+    assert_eq!(
+        Account::unpack(&data)
+            .expect("unpack")
+            .execution_delay_slots,
+        500
+    );
+
+    // Legacy accounts have no room for a delay
+    let mut legacy_data = vec![0; Account::LEGACY_LEN];
+    assert_eq!(
+        Account::pack(multisig, &mut legacy_data).err(),
+        Some(ProgramError::AccountDataTooSmall)
+    );
+    Account::pack(first_release_account(owners.clone()), &mut legacy_data).expect("pack");
+    assert_eq!(
+        Account::unpack(&legacy_data)
+            .expect("unpack")
+            .execution_delay_slots,
+        0
+    );
+
+    for reached_threshold_slot in [None, Some(0), Some(42)] {
+        let mut data = vec![0; Transaction::LEN];
+        Transaction::pack(
+            Transaction {
+                multisig: Pubkey::new_unique(),
+                recipient: Pubkey::new_unique(),
+                amount: 1_000,
+                status: TransactionStatus::Pending,
+                is_sweep: false,
+                signers: owners.iter().map(|owner| (*owner, true)).collect(),
+                memo: String::new(),
+                outputs: vec![],
+                not_before_slot: 0,
+                expires_at_slot: 0,
+                is_soft: false,
+                approvals: 0,
+                owners_version: 0,
+                nonce: 0,
+                instruction: None,
+                creator: Pubkey::default(),
+                reached_threshold_slot,
+            },
+            &mut data,
+        )
+        .expect("pack");
+
+        assert_eq!(
+            Transaction::unpack_unchecked(&data)
+                .expect("unpack")
+                .reached_threshold_slot,
+            reached_threshold_slot
+        );
+    }
+}
+
+#[test]
+fn test_owner_grace_round_trip() {
+    let owners = vec![
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let mut multisig = account_with_owners(owners.clone());
+    multisig.new_owner_grace_secs = 3_600;
+    multisig.owner_added_at = vec![0, 0, 1_000_000];
+
+    let mut data = vec![0; Account::LEN];
+    Account::pack(multisig, &mut data).expect("pack");
+    let multisig = Account::unpack(&data).expect("unpack");
+    assert_eq!(multisig.new_owner_grace_secs, 3_600);
+    assert_eq!(multisig.owner_added_at, vec![0, 0, 1_000_000]);
+
+    // Only the added owner waits, and only until the window ends
+    assert!(!multisig.in_grace(&owners[0], 1_000_000));
+    assert!(multisig.in_grace(&owners[2], 1_000_000));
+    assert!(multisig.in_grace(&owners[2], 1_003_599));
+    assert!(!multisig.in_grace(&owners[2], 1_003_600));
+    assert!(!multisig.in_grace(&Pubkey::new_unique(), 1_000_000));
+
+    // Owners the multisig was created with have no addition time
+    let multisig = account_with_owners(owners.clone());
+    let mut data = vec![0; Account::LEN];
+    Account::pack(multisig, &mut data).expect("pack");
+    assert_eq!(
+        Account::unpack(&data).expect("unpack").owner_added_at,
+        vec![0; owners.len()]
+    );
+}
+
+#[test]
+fn test_unpack_first_release() {
+    let data = include_bytes!("fixtures/account-597");
+    let account = Account::unpack_strict(data).expect("unpack");
+    assert_eq!(account.threshold, 2);
+    assert_eq!(
+        account.owners,
+        vec![Pubkey::new(&[1; 32]), Pubkey::new(&[2; 32])]
+    );
+    assert_eq!(account.pending_transactions, vec![Pubkey::new(&[3; 32])]);
+    assert_eq!(account.frozen_amount, 1_000);
+    assert_eq!(account.reserved_balance, 0);
+    assert_eq!(account.max_pending, MAX_TRANSACTIONS as u32);
+    assert_eq!(Account::max_owners(data.len()), LEGACY_MAX_SIGNERS);
+    assert!(!Account::has_nonce(data.len()));
+    assert!(Account::has_nonce(Account::LEN));
+
+    // Owners directly follow the list lengths, there is no reserved balance
+    assert_eq!(Account::owners_offset(data.len()), Some(21));
+    assert_eq!(&data[21..53], &[1; 32]);
+
+    // Nothing is lost moving to the current layout and back
+    let mut widened = vec![0; Account::LEN];
+    Account::pack(account.clone(), &mut widened).expect("pack");
+    let widened = Account::unpack_unchecked(&widened).expect("unpack");
+    let mut repacked = vec![0; data.len()];
+    Account::pack(widened, &mut repacked).expect("pack");
+    assert_eq!(&repacked[..], &data[..]);
+
+    // Bookkeeping without room is dropped
+    let mut account = account;
+    account.last_updated_slot = 42;
+    account.owners_version = 3;
+    Account::pack(account.clone(), &mut repacked).expect("pack");
+    assert_eq!(&repacked[..], &data[..]);
+
+    // settings without room fail
+    for update in [
+        |account: &mut Account| account.reserved_balance = 7,
+        |account: &mut Account| account.nonce = 1,
+        |account: &mut Account| account.execution_delay_slots = 500,
+        |account: &mut Account| account.new_owner_grace_secs = 3_600,
+    ] {
+        let mut account = account.clone();
+        update(&mut account);
+        assert_eq!(
+            Account::pack(account, &mut repacked).err(),
+            Some(ProgramError::AccountDataTooSmall)
+        );
+    }
+
+    let data = include_bytes!("fixtures/transaction-341");
+    let transaction = Transaction::unpack_unchecked(data).expect("unpack");
+    assert_eq!(transaction.multisig, Pubkey::new(&[9; 32]));
+    assert_eq!(transaction.recipient, Pubkey::new(&[8; 32]));
+    assert_eq!(transaction.amount, 1_000);
+    assert_eq!(transaction.status, TransactionStatus::Pending);
+    assert!(!transaction.is_sweep);
+    assert_eq!(
+        transaction.signers,
+        vec![
+            (Pubkey::new(&[1; 32]), true),
+            (Pubkey::new(&[2; 32]), false)
+        ]
+    );
+
+    let mut widened = vec![0; Transaction::LEN];
+    Transaction::pack(
+        Transaction::unpack_unchecked(data).expect("unpack"),
+        &mut widened,
+    )
+    .expect("pack");
+    let mut repacked = vec![0; data.len()];
+    Transaction::pack(
+        Transaction::unpack_unchecked(&widened).expect("unpack"),
+        &mut repacked,
+    )
+    .expect("pack");
+    assert_eq!(&repacked[..], &data[..]);
+
+    // Executing only flips the status byte in place
+    let mut transaction = transaction;
+    transaction.status = TransactionStatus::Executed;
+    let mut repacked = vec![0; data.len()];
+    Transaction::pack(transaction, &mut repacked).expect("pack");
+    assert_eq!(repacked[72], 1);
+    assert_eq!(repacked[..72], data[..72]);
+    assert_eq!(repacked[73..], data[73..]);
+}